@@ -16,7 +16,7 @@ use holochain_conductor_api::{
 };
 use holochain_types::app::InstalledAppId;
 use holochain_types::websocket::AllowedOrigins;
-use wind_tunnel_instruments::Reporter;
+use wind_tunnel_instruments::{OperationRecord, Reporter};
 use wind_tunnel_instruments_derive::wind_tunnel_instrument;
 
 pub struct AdminWebsocketInstrumented {
@@ -109,7 +109,7 @@ impl AdminWebsocketInstrumented {
             .map_err(handle_api_err)
     }
 
-    #[wind_tunnel_instrument(prefix = "admin_")] // post = post_process_response
+    #[wind_tunnel_instrument(prefix = "admin_", post_hook = post_get_dna_definition)]
     pub async fn get_dna_definition(&self, cell_id: CellId) -> anyhow::Result<DnaDef> {
         self.inner
             .get_dna_definition(cell_id)
@@ -168,11 +168,10 @@ impl AdminWebsocketInstrumented {
         &self,
         request: AuthorizeSigningCredentialsPayload,
     ) -> anyhow::Result<SigningCredentials> {
-        Ok(self
-            .inner
+        self.inner
             .authorize_signing_credentials(request)
             .await
-            .map_err(handle_api_err)?)
+            .map_err(handle_api_err)
     }
 
     #[wind_tunnel_instrument(prefix = "admin_")]
@@ -187,6 +186,18 @@ impl AdminWebsocketInstrumented {
     }
 }
 
+fn post_get_dna_definition(
+    operation_record: &mut OperationRecord,
+    result: &anyhow::Result<DnaDef>,
+) {
+    if let Ok(dna_def) = result {
+        operation_record.add_field(
+            "zome_count",
+            (dna_def.integrity_zomes.len() + dna_def.coordinator_zomes.len()) as u64,
+        );
+    }
+}
+
 impl std::fmt::Debug for AdminWebsocketInstrumented {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AdminWebsocketInstrumented").finish()
@@ -59,7 +59,7 @@ impl AppWebsocketInstrumented {
             .map_err(handle_api_err)
     }
 
-    #[wind_tunnel_instrument(prefix = "app_", pre_hook = pre_call_zome)]
+    #[wind_tunnel_instrument(prefix = "app_", pre_hook = pre_call_zome, post_hook = post_call_zome)]
     pub async fn call_zome(
         &self,
         target: ZomeCallTarget,
@@ -142,6 +142,12 @@ fn pre_call_zome(
     }
 }
 
+fn post_call_zome(operation_record: &mut OperationRecord, result: &anyhow::Result<ExternIO>) {
+    if let Ok(response) = result {
+        operation_record.add_field("response_bytes", response.0.len() as u64);
+    }
+}
+
 impl Debug for AppWebsocketInstrumented {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AppWebsocketInstrumented").finish()
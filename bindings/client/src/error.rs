@@ -1,18 +1,50 @@
 use holochain_client::ConductorApiError;
+use holochain_conductor_api::ExternalApiWireError;
+use holochain_websocket::WebsocketError;
 use wind_tunnel_core::prelude::AgentBailError;
+use wind_tunnel_instruments::prelude::ClassifiedError;
 
 /// Handle a Conductor API error, returning an `anyhow::Error`.
 ///
 /// If the error is a websocket closed error, this function will panic. There is currently no way to
 /// reconnect websockets so once the connection drops, the scenario won't recover. It is better to
 /// treat the error as fatal amd stop than keep logging errors until the scenario finishes.
+///
+/// Other errors are returned as a [ClassifiedError], so that failed operations are reported with
+/// the kind of conductor error.
 pub fn handle_api_err(err: ConductorApiError) -> anyhow::Error {
     match err {
         // Handle websocket closed errors by shutting down the process, as this is a fatal error
         // for this agent.
-        ConductorApiError::WebsocketError(holochain_websocket::WebsocketError::Close(_)) => {
+        ConductorApiError::WebsocketError(WebsocketError::Close(_)) => {
             AgentBailError::default().into()
         }
-        _ => anyhow::anyhow!("Conductor API error: {err:?}"),
+        _ => ClassifiedError::new(
+            conductor_error_kind(&err),
+            format!("Conductor API error: {err:?}"),
+        )
+        .into(),
+    }
+}
+
+/// The kind of a Conductor API error, by the variant of the error.
+fn conductor_error_kind(err: &ConductorApiError) -> &'static str {
+    match err {
+        ConductorApiError::WebsocketError(WebsocketError::Timeout(_)) => "timeout",
+        ConductorApiError::WebsocketError(_) => "websocket",
+        ConductorApiError::ExternalApiWireError(wire_error) => match wire_error {
+            ExternalApiWireError::InternalError(_) => "internal",
+            ExternalApiWireError::Deserialization(_) => "deserialization",
+            ExternalApiWireError::DnaReadError(_) => "dna_read",
+            ExternalApiWireError::RibosomeError(_) => "ribosome",
+            ExternalApiWireError::ZomeCallAuthenticationFailed(_)
+            | ExternalApiWireError::ZomeCallUnauthorized(_) => "unauthorized",
+            ExternalApiWireError::CountersigningSessionError(_) => "countersigning",
+        },
+        ConductorApiError::FreshNonceError(_) | ConductorApiError::SignZomeCallError(_) => {
+            "signing"
+        }
+        ConductorApiError::CellNotFound | ConductorApiError::AppNotFound => "not_found",
+        ConductorApiError::IoError(_) => "io",
     }
 }
@@ -14,7 +14,7 @@ use kitsune2_transport_iroh::config::{IrohTransportConfig, IrohTransportModConfi
 use op_store::{DynWtOpStore, WtOp, WtOpStore, WtOpStoreFactory};
 use std::{sync::Arc, time::Duration};
 use tokio::sync::Mutex;
-use wind_tunnel_instruments::prelude::{ReportMetric, Reporter};
+use wind_tunnel_instruments::prelude::Reporter;
 use wind_tunnel_instruments_derive::wind_tunnel_instrument;
 
 mod op_store;
@@ -196,11 +196,12 @@ impl WtChatter {
             log::info!("agent {} said {}", state.agent.agent(), message);
         }
 
-        self.reporter.add_custom(
-            ReportMetric::new("said_messages")
-                .with_tag("agent_id", state.agent.agent().to_string())
-                .with_field("num_messages", message_ids.len() as u32),
-        );
+        self.reporter
+            .counter(
+                "said_messages",
+                &[("agent_id", &state.agent.agent().to_string())],
+            )
+            .add(message_ids.len() as u64);
 
         Ok(message_ids)
     }
@@ -217,11 +218,10 @@ mod tests {
 
     pub(crate) fn test_reporter() -> Arc<Reporter> {
         let runtime = tokio::runtime::Handle::current();
-        let shutdown_listener = ShutdownHandle::new().new_listener();
         Arc::new(
             ReportConfig::new("".to_string(), "".to_string())
                 .enable_in_memory()
-                .init_reporter(&runtime, shutdown_listener)
+                .init_reporter(&runtime, &ShutdownHandle::new())
                 .unwrap(),
         )
     }
@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use time_slice_hash_store::TimeSliceHashStore;
 use tokio::sync::RwLock;
-use wind_tunnel_instruments::prelude::{ReportMetric, Reporter};
+use wind_tunnel_instruments::prelude::Reporter;
 
 mod time_slice_hash_store;
 
@@ -224,11 +224,12 @@ impl OpStore for WtOpStore {
             log::info!("{} ops have come in to {}", op_ids.len(), self.agent_id);
             if number_of_added_ops > 0 {
                 // Ops may have come in multiple times. This reports only distinct op ids as heard messages.
-                self.reporter.add_custom(
-                    ReportMetric::new("heard_messages")
-                        .with_tag("agent_id", self.agent_id.to_string())
-                        .with_field("num_messages", number_of_added_ops),
-                );
+                self.reporter
+                    .counter(
+                        "heard_messages",
+                        &[("agent_id", &self.agent_id.to_string())],
+                    )
+                    .add(number_of_added_ops);
             }
 
             Ok(op_ids)
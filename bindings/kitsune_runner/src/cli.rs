@@ -1,7 +1,8 @@
 use crate::common::to_connection_string;
 use clap::Parser;
-use wind_tunnel_runner::parse_agent_behaviour;
-use wind_tunnel_runner::prelude::{ReporterOpt, WindTunnelScenarioCli};
+use std::path::PathBuf;
+use wind_tunnel_runner::prelude::{LoadStage, ReporterOpt, WindTunnelScenarioCli};
+use wind_tunnel_runner::{parse_agent_behaviour, parse_load_stage, parse_param};
 
 #[derive(Parser)]
 #[command(about, long_about = None)]
@@ -27,6 +28,30 @@ pub struct WindTunnelKitsuneScenarioCli {
     #[clap(long)]
     pub duration: Option<u64>,
 
+    /// The number of seconds at the start of the run to treat as a warmup.
+    ///
+    /// Operations and custom metrics recorded during the warmup are tagged with `phase=warmup` so that
+    /// they can be excluded from the results.
+    #[clap(long)]
+    pub warmup: Option<u64>,
+
+    /// Add a stage to the load profile, to activate agents gradually rather than all at once.
+    /// Specify the stage in the format `target_agents:ramp_s:hold_s`. For example `--load-stage=10:0:60`.
+    ///
+    /// Specify the flag multiple times to build up a profile. If no duration is set, then the
+    /// scenario runs for the total duration of the load profile.
+    #[clap(long, value_parser = parse_load_stage)]
+    pub load_stage: Vec<LoadStage>,
+
+    /// A TOML or JSON file of parameters for the scenario.
+    #[clap(long)]
+    pub params: Option<PathBuf>,
+
+    /// Set a scenario parameter, in the format `key=value`. This overrides any value set in the
+    /// `--params` file.
+    #[clap(long, value_parser = parse_param)]
+    pub param: Vec<(String, String)>,
+
     /// Assign a behaviour to a number of agents. Specify the behaviour and number of agents to assign
     /// it to in the format `behaviour:count`. For example `--behaviour=login:5`.
     ///
@@ -52,9 +77,35 @@ pub struct WindTunnelKitsuneScenarioCli {
     #[clap(long, default_value = "false")]
     pub no_progress: bool,
 
-    /// The reporter to use.
-    #[arg(long, value_enum, default_value_t = ReporterOpt::InMemory)]
-    pub reporter: ReporterOpt,
+    /// The reporters to use. Specify the flag multiple times, or separate reporters with commas, to
+    /// enable several reporters at once. For example `--reporter=influx-file,in-memory`.
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [ReporterOpt::InMemory])]
+    pub reporter: Vec<ReporterOpt>,
+
+    /// A TOML or JSON file that lists the reporters to use, with options for each reporter. The file
+    /// is read as JSON if it has a `.json` extension and as TOML otherwise.
+    ///
+    /// This replaces the reporters selected with `--reporter`.
+    #[arg(long, conflicts_with = "reporter")]
+    pub reporter_config: Option<PathBuf>,
+
+    /// The directory to write metrics files to, for reporters that do not have a directory set in
+    /// the reporter config.
+    #[arg(long, env = "WT_METRICS_DIR")]
+    pub metrics_dir: Option<PathBuf>,
+
+    /// An NTP server to measure the offset of this machine's clock against, as `host` or
+    /// `host:port`, so that timings can be compared across machines.
+    ///
+    /// The offset is recorded in the run summary and on each reported operation.
+    #[arg(long, env = "WT_TIME_SERVER")]
+    pub time_server: Option<String>,
+
+    /// The file to append the summary of this run to.
+    ///
+    /// If not set, the summary is appended to `run_summary.jsonl` in the working directory.
+    #[arg(long, env = "RUN_SUMMARY_PATH")]
+    pub run_summary_path: Option<PathBuf>,
 
     /// Set the ID of this run
     ///
@@ -75,9 +126,17 @@ impl TryInto<WindTunnelScenarioCli> for WindTunnelKitsuneScenarioCli {
             agents: self.agents,
             behaviour: self.behaviour,
             duration: self.duration,
+            warmup: self.warmup,
+            load_stage: self.load_stage,
+            params: self.params,
+            param: self.param,
             soak: self.soak,
             no_progress: self.no_progress,
             reporter: self.reporter,
+            reporter_config: self.reporter_config,
+            metrics_dir: self.metrics_dir,
+            time_server: self.time_server,
+            run_summary_path: self.run_summary_path,
             run_id: self.run_id,
         })
     }
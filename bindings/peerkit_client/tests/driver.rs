@@ -37,11 +37,10 @@ done
 
 fn test_reporter() -> Arc<Reporter> {
     let runtime = tokio::runtime::Handle::current();
-    let shutdown_listener = ShutdownHandle::new().new_listener();
     Arc::new(
         ReportConfig::new("".to_string(), "".to_string())
             .enable_in_memory()
-            .init_reporter(&runtime, shutdown_listener)
+            .init_reporter(&runtime, &ShutdownHandle::new())
             .unwrap(),
     )
 }
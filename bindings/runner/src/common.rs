@@ -1,10 +1,12 @@
 use crate::bin_path::{WT_HOLOCHAIN_PATH_ENV, holochain_path};
 use crate::build_info::holochain_build_info;
+use crate::conductor_config::conductor_config_overlay_from_env;
+use crate::conductor_pool::{agents_per_conductor_from_env, watch_for_unexpected_exit};
 use crate::context::HolochainAgentContext;
 use crate::holochain_runner::{HolochainConfig, HolochainRunner};
 use crate::prelude::CallZomeOptions;
 use crate::runner_context::HolochainRunnerContext;
-use anyhow::Context;
+use anyhow::{Context, bail};
 use holochain_client_instrumented::ToSocketAddr;
 use holochain_client_instrumented::prelude::{
     AdminWebsocket, AppWebsocket, AuthorizeSigningCredentialsPayload, ClientAgentSigner,
@@ -24,6 +26,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{env, fs, io};
+use wind_tunnel_core::prelude::AgentBailError;
 use wind_tunnel_runner::prelude::{
     AgentContext, HookResult, Reporter, RunnerContext, UserValuesConstraint, WindTunnelResult,
 };
@@ -724,7 +727,7 @@ where
 {
     let cell_id = ctx.get().cell_id();
     let app_agent_client = ctx.get().app_client();
-    ctx.runner_context().executor().execute_in_place(async {
+    let result = ctx.runner_context().executor().execute_in_place(async {
         let result = app_agent_client
             .call_zome(
                 cell_id.into(),
@@ -738,7 +741,39 @@ where
         result
             .decode()
             .map_err(|e| anyhow::anyhow!("Decoding failure: {e:?}"))
-    })
+    });
+
+    // A call fails if the conductor has crashed, in which case the agent can't continue
+    if result.is_err() {
+        check_holochain_conductor(ctx)?;
+    }
+
+    result
+}
+
+/// Check that the conductor run by [`run_holochain_conductor`] has not exited unexpectedly, for
+/// example because it crashed.
+///
+/// Returns an [`AgentBailError`] with the conductor's exit status if it has exited, so that the
+/// agent stops running its behaviour. This is checked by [`call_zome`] and
+/// [`call_zome_with_options`] when a call fails. The conductor is also checked in the background
+/// while the agent runs, so that the agent bails before its next behaviour iteration if the
+/// conductor crashes between calls. If no conductor was started by this agent, this function does
+/// nothing.
+pub fn check_holochain_conductor<SV: UserValuesConstraint>(
+    ctx: &mut AgentContext<HolochainRunnerContext, HolochainAgentContext<SV>>,
+) -> WindTunnelResult<()> {
+    if let Some(conductor) = &ctx.get().conductor
+        && let Some(exit_status) = conductor.runner().unexpected_exit()
+    {
+        log::error!("Holochain conductor exited unexpectedly with {exit_status}");
+        return Err(AgentBailError::new(format!(
+            "Holochain conductor exited unexpectedly with {exit_status}"
+        ))
+        .into());
+    }
+
+    Ok(())
 }
 
 /// Get a randomized list of peers connected to the conductor in the `ctx` for a given cell.
@@ -823,14 +858,31 @@ fn get_cell_id_for_role_name(app_info: &AppInfo, role_name: &RoleName) -> anyhow
 
 /// If [`wind_tunnel_runner::prelude::RunnerContext::connection_string`] is not set then this
 /// function runs an instance of the Holochain conductor, using the configuration built from the
-/// [`HolochainAgentContext::holochain_config`] and stores the running process in
-/// [`HolochainAgentContext::holochain_runner`].
+/// [`HolochainAgentContext::holochain_config`] and stores the running conductor in
+/// [`HolochainAgentContext::conductor`].
 ///
 /// This function also creates an admin interface bound to a random, available port and sets
 /// [`HolochainAgentContext::admin_ws_url`] to a 127.0.0.1 address with that port.
 ///
+/// By default each agent runs its own conductor. If the
+/// [`WT_AGENTS_PER_CONDUCTOR_ENV`](crate::prelude::WT_AGENTS_PER_CONDUCTOR_ENV) environment
+/// variable is set to more than `1`, then that many agents share each conductor, which is named
+/// `conductor-<n>` and is started by the first of its agents to call this function, using that
+/// agent's config. Each agent still generates its own agent key and installs its own app. The
+/// [`HolochainRunnerContext`] keeps track of which conductor each agent is assigned to, see
+/// [`HolochainRunnerContext::conductor_for_agent`], and a shared conductor is shut down once all
+/// of its agents have finished.
+///
+/// The conductor's stdout and stderr are written to `<conductor-name>.stdout.log` and
+/// `<conductor-name>.stderr.log` in the `holochain-<run-id>-logs` directory of `WT_METRICS_DIR`,
+/// and the lines that match the configured [`LogPattern`](crate::prelude::LogPattern)s are counted.
+///
 /// Override the binary used to start the conductor with the [`WT_HOLOCHAIN_PATH_ENV`] environment
-/// variable.
+/// variable, and fields of the conductor config with a YAML file at the path in the
+/// [`WT_CONDUCTOR_CONFIG_ENV`](crate::prelude::WT_CONDUCTOR_CONFIG_ENV) environment variable. The
+/// overrides from the file are merged on top of any set with
+/// [`HolochainConfigBuilder::with_config_overlay`](crate::holochain_runner::HolochainConfigBuilder::with_config_overlay),
+/// and are recorded in the run summary.
 pub fn run_holochain_conductor<SV: UserValuesConstraint>(
     ctx: &mut AgentContext<HolochainRunnerContext, HolochainAgentContext<SV>>,
 ) -> WindTunnelResult<()> {
@@ -841,54 +893,85 @@ pub fn run_holochain_conductor<SV: UserValuesConstraint>(
         return Ok(());
     }
 
-    let holochain_path = holochain_path()?;
-    log::debug!(
-        "Using holochain binary at path: {}",
-        holochain_path.display()
+    let agents_per_conductor = agents_per_conductor_from_env()?;
+    let runner_context = ctx.runner_context().clone();
+    let conductor_name = runner_context.get().conductors.assign(
+        ctx.agent_index(),
+        ctx.agent_name(),
+        agents_per_conductor,
     );
-    ctx.get_mut()
-        .holochain_config_mut()
-        .with_bin_path(holochain_path);
-
-    let admin_port = {
-        // Bind to an ephemeral port to reserve it, then release before starting the conductor.
-        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))
-            .context("Failed to bind ephemeral port for admin interface")?;
-        listener.local_addr()?.port()
-    };
-    let conductor_root_path = {
-        let mut path = env::temp_dir();
-        path.push(ctx.runner_context().get_run_id());
-        path.push(ctx.agent_name());
+    let assigned_behaviour = ctx.assigned_behaviour().to_string();
+    let mut holochain_config = ctx.get_mut().take_holochain_config();
+    if let Some(config_overlay) = conductor_config_overlay_from_env()? {
+        holochain_config.with_config_overlay(config_overlay);
+    }
 
-        path
-    };
-    let holochain_metrics_path = {
-        let mut path: PathBuf = std::env::var("WT_METRICS_DIR")
-            .expect("WT_METRICS_DIR must be set.")
-            .into();
-        path.push(format!(
-            "holochain-{}-{}.influx",
-            ctx.runner_context().get_run_id(),
-            ctx.agent_name()
-        ));
-
-        path
-    };
-    let agent_name = ctx.agent_name().to_string();
-    ctx.get_mut()
-        .holochain_config_mut()
-        .with_conductor_root_path(&conductor_root_path)
-        .with_admin_port(admin_port)
-        .with_agent_name(agent_name)
-        .with_metrics_path(&holochain_metrics_path);
-
-    let config = ctx.get_mut().take_holochain_config().build()?;
-
-    ctx.get_mut().holochain_runner = match ctx.runner_context().executor().execute_in_place(
-        create_and_start_holochain_conductor(config, &conductor_root_path),
-    ) {
-        Ok(runner) => Some(runner),
+    // Recorded for every behaviour, including agents that join a conductor another agent started
+    if let Some(config_overlay) = holochain_config.config_overlay() {
+        runner_context.record_config_overlay(
+            format!("conductor_config.{assigned_behaviour}"),
+            config_overlay.clone(),
+        );
+    }
+
+    let shared = agents_per_conductor > 1;
+    let conductor = runner_context
+        .get()
+        .conductors
+        .get_or_start(&conductor_name, shared, || {
+            let holochain_path = holochain_path()?;
+            log::debug!(
+                "Using holochain binary at path: {}",
+                holochain_path.display()
+            );
+            holochain_config.with_bin_path(holochain_path);
+
+            let admin_port = {
+                // Bind to an ephemeral port to reserve it, then release before starting the conductor.
+                let listener = std::net::TcpListener::bind(("127.0.0.1", 0))
+                    .context("Failed to bind ephemeral port for admin interface")?;
+                listener.local_addr()?.port()
+            };
+            let conductor_root_path = {
+                let mut path = env::temp_dir();
+                path.push(runner_context.get_run_id());
+                path.push(&conductor_name);
+
+                path
+            };
+            let metrics_dir: PathBuf = std::env::var("WT_METRICS_DIR")
+                .expect("WT_METRICS_DIR must be set.")
+                .into();
+            let holochain_metrics_path = metrics_dir.join(format!(
+                "holochain-{}-{}.influx",
+                runner_context.get_run_id(),
+                conductor_name
+            ));
+            let holochain_log_dir =
+                metrics_dir.join(format!("holochain-{}-logs", runner_context.get_run_id()));
+            holochain_config
+                .with_conductor_root_path(&conductor_root_path)
+                .with_admin_port(admin_port)
+                .with_metrics_path(&holochain_metrics_path)
+                .with_log_dir(&holochain_log_dir);
+            if shared {
+                holochain_config.with_conductor_name(&conductor_name);
+            } else {
+                holochain_config.with_agent_name(&conductor_name);
+            }
+
+            let config = holochain_config.build()?;
+
+            runner_context
+                .executor()
+                .execute_in_place(create_and_start_holochain_conductor(
+                    config,
+                    &conductor_root_path,
+                    runner_context.reporter(),
+                ))
+        });
+    let conductor = match conductor {
+        Ok(conductor) => conductor,
         Err(err) => {
             log::error!("Failed to start Holochain conductor: {err}");
             // force stop conductor if we failed to start it and return error
@@ -897,13 +980,23 @@ pub fn run_holochain_conductor<SV: UserValuesConstraint>(
         }
     };
 
-    ctx.get_mut().admin_ws_url = Some(
-        format!("ws://127.0.0.1:{admin_port}")
-            .to_socket_addr()
-            .with_context(|| {
-                format!("Failed to create admin_ws_url from 'ws://127.0.0.1:{admin_port}'")
-            })?,
-    );
+    let admin_ws_url = conductor.runner().admin_ws_url().ok_or(anyhow::anyhow!(
+        "Failed to get admin websocket url of Holochain conductor"
+    ))?;
+
+    // Bail as soon as the conductor crashes, rather than waiting for a zome call to fail
+    let bail_handle = ctx.bail_handle();
+    runner_context.executor().spawn(watch_for_unexpected_exit(
+        Arc::downgrade(&conductor),
+        move |exit_status| {
+            log::error!("Holochain conductor exited unexpectedly with {exit_status}");
+            bail_handle.bail(format!(
+                "Holochain conductor exited unexpectedly with {exit_status}"
+            ));
+        },
+    ));
+    ctx.get_mut().conductor = Some(conductor);
+    ctx.get_mut().admin_ws_url = Some(admin_ws_url);
 
     Ok(())
 }
@@ -915,9 +1008,10 @@ pub fn run_holochain_conductor<SV: UserValuesConstraint>(
 async fn create_and_start_holochain_conductor(
     config: HolochainConfig,
     conductor_root_path: &Path,
+    reporter: Arc<Reporter>,
 ) -> anyhow::Result<HolochainRunner> {
     let mut err = match async {
-        let mut runner = HolochainRunner::create(&config)?;
+        let mut runner = HolochainRunner::create(&config)?.with_reporter(reporter);
         log::info!("Created runner {runner:?}");
         runner.run().await?;
         anyhow::Ok(runner)
@@ -994,11 +1088,12 @@ pub fn conductor_build_info(
 
 /// Stops the Holochain conductor if one is running.
 ///
-/// This function will take the `holochain_runner` from the context and gracefully shut down
-/// the conductor process. The conductor's data directory will still be retained.
+/// This function will gracefully shut down the agent's conductor process, see
+/// [`HolochainRunner::shutdown`]. The conductor's data directory will still be retained.
 ///
 /// If no conductor is running this function does nothing.
 /// If using an external conductor via connection-string, this function does nothing.
+/// Returns an error if the conductor is shared with other agents, see [`run_holochain_conductor`].
 ///
 /// Call this function as follows:
 /// ```rust
@@ -1012,16 +1107,27 @@ pub fn conductor_build_info(
 pub fn stop_holochain_conductor<SV: UserValuesConstraint>(
     ctx: &mut AgentContext<HolochainRunnerContext, HolochainAgentContext<SV>>,
 ) -> WindTunnelResult<()> {
-    if let Some(mut runner) = ctx.get_mut().holochain_runner.take() {
+    if let Some(conductor) = ctx.get().conductor.clone() {
+        if conductor.is_shared() {
+            bail!(
+                "Cannot stop Holochain conductor '{}' because it is shared with other agents",
+                conductor.name()
+            );
+        }
+
         log::info!("Stopping Holochain conductor");
-        runner.shutdown();
-        log::info!("Holochain conductor stopped");
+        let result = ctx
+            .runner_context()
+            .executor()
+            .execute_in_place(conductor.runner().shutdown());
 
         let ctx = ctx.get_mut();
-        ctx.holochain_runner = Some(runner);
         ctx.app_client = None;
         ctx.app_ws_url = None;
         ctx.admin_ws_url = None;
+
+        result?;
+        log::info!("Holochain conductor stopped");
     } else {
         log::debug!("No Holochain conductor is running, so nothing to stop");
     }
@@ -1033,12 +1139,20 @@ pub fn stop_holochain_conductor<SV: UserValuesConstraint>(
 ///
 /// If no conductor was created, this function does nothing.
 /// If using an external conductor via connection-string, this function does nothing.
+/// Returns an error if the conductor is shared with other agents, see [`run_holochain_conductor`].
 pub fn start_holochain_conductor<SV: UserValuesConstraint>(
     ctx: &mut AgentContext<HolochainRunnerContext, HolochainAgentContext<SV>>,
 ) -> WindTunnelResult<()> {
-    if let Some(mut runner) = ctx.get_mut().holochain_runner.take() {
-        log::info!("Starting Holochain conductor");
+    if let Some(conductor) = ctx.get().conductor.clone() {
+        if conductor.is_shared() {
+            bail!(
+                "Cannot start Holochain conductor '{}' because it is shared with other agents",
+                conductor.name()
+            );
+        }
 
+        log::info!("Starting Holochain conductor");
+        let mut runner = conductor.runner();
         if let Err(err) = ctx
             .runner_context()
             .executor()
@@ -1055,7 +1169,7 @@ pub fn start_holochain_conductor<SV: UserValuesConstraint>(
         let admin_ws_url = runner.admin_ws_url().ok_or(anyhow::anyhow!(
             "Failed to get admin websocket url of Holochain conductor"
         ))?;
-        ctx.get_mut().holochain_runner = Some(runner);
+        drop(runner);
         ctx.get_mut().admin_ws_url = Some(admin_ws_url);
 
         configure_app_ws_url(ctx)?;
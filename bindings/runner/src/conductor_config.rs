@@ -0,0 +1,150 @@
+//! Partial conductor configs, known as overlays, that are merged into the [`ConductorConfig`]
+//! that Wind Tunnel generates for a conductor.
+
+use std::{env, fs, path::Path};
+
+use anyhow::{Context, bail};
+use holochain_conductor_api::conductor::ConductorConfig;
+use serde_json::Value;
+use wind_tunnel_runner::prelude::WindTunnelResult;
+
+/// Environment variable with the path to a YAML file of conductor config overrides, which is
+/// merged into the config of every conductor that Wind Tunnel runs.
+pub const WT_CONDUCTOR_CONFIG_ENV: &str = "WT_CONDUCTOR_CONFIG";
+
+/// Fields of the conductor config that Wind Tunnel sets itself, which an overlay must not change.
+const MANAGED_FIELDS: [&str; 3] = ["data_root_path", "keystore", "admin_interfaces"];
+
+/// Load the conductor config overlay from the file at the path in [`WT_CONDUCTOR_CONFIG_ENV`], if
+/// it is set.
+pub(crate) fn conductor_config_overlay_from_env() -> WindTunnelResult<Option<Value>> {
+    match env::var(WT_CONDUCTOR_CONFIG_ENV).ok().as_deref() {
+        None | Some("") => Ok(None),
+        Some(path) => load_overlay(Path::new(path))
+            .with_context(|| format!("Failed to load '{WT_CONDUCTOR_CONFIG_ENV}={path}'"))
+            .map(Some),
+    }
+}
+
+/// Load a conductor config overlay from a YAML file.
+fn load_overlay(path: &Path) -> WindTunnelResult<Value> {
+    let content = fs::read_to_string(path).context("Failed to read conductor config overlay")?;
+    let overlay: Value =
+        yaml_serde::from_str(&content).context("Failed to parse conductor config overlay")?;
+    if !overlay.is_object() {
+        bail!("Conductor config overlay must be a mapping of config fields");
+    }
+
+    Ok(overlay)
+}
+
+/// Merge `overlay` into `base`.
+///
+/// Mappings are merged field by field, so an overlay only needs the fields that it changes. Any
+/// other value in the overlay, including a list, replaces the value in `base`.
+pub(crate) fn merge_overlay(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_overlay(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Apply an overlay to a conductor config, returning the merged config.
+///
+/// Returns an error if the overlay changes a field that Wind Tunnel manages, or if the merged
+/// config is not a valid conductor config, for example because of a misspelled field.
+pub(crate) fn apply_overlay(
+    conductor_config: &ConductorConfig,
+    overlay: &Value,
+) -> WindTunnelResult<ConductorConfig> {
+    if let Some(field) = MANAGED_FIELDS
+        .iter()
+        .find(|field| overlay.get(field).is_some())
+    {
+        bail!("Conductor config overlay cannot set '{field}', it is managed by Wind Tunnel");
+    }
+
+    let mut merged = serde_json::to_value(conductor_config)
+        .context("Failed to serialize the conductor config")?;
+    merge_overlay(&mut merged, overlay.clone());
+
+    serde_json::from_value(merged).context("Conductor config overlay is not valid conductor config")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_should_merge_nested_fields() {
+        let mut base = json!({"network": {"target_arc_factor": 1, "disable_publish": false}});
+
+        merge_overlay(
+            &mut base,
+            json!({"network": {"disable_publish": true}, "tracing_override": "debug"}),
+        );
+
+        assert_eq!(
+            json!({
+                "network": {"target_arc_factor": 1, "disable_publish": true},
+                "tracing_override": "debug"
+            }),
+            base
+        );
+    }
+
+    #[test]
+    fn test_should_apply_overlay_to_conductor_config() {
+        let conductor_config = ConductorConfig::default();
+
+        let merged = apply_overlay(
+            &conductor_config,
+            &json!({"network": {"target_arc_factor": 0}, "db_max_readers": 16}),
+        )
+        .unwrap();
+
+        assert_eq!(0, merged.network.target_arc_factor);
+        assert_eq!(16, merged.db_max_readers);
+        assert_eq!(
+            conductor_config.network.bootstrap_url,
+            merged.network.bootstrap_url
+        );
+    }
+
+    #[test]
+    fn test_should_not_apply_overlay_with_unknown_field() {
+        let result = apply_overlay(
+            &ConductorConfig::default(),
+            &json!({"network": {"target_arc_factr": 0}}),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_should_not_apply_overlay_to_managed_field() {
+        let result = apply_overlay(
+            &ConductorConfig::default(),
+            &json!({"data_root_path": "/tmp/elsewhere"}),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_should_load_overlay_from_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overlay.yaml");
+        fs::write(&path, "network:\n  target_arc_factor: 0\n").unwrap();
+
+        let overlay = load_overlay(&path).unwrap();
+
+        assert_eq!(json!({"network": {"target_arc_factor": 0}}), overlay);
+    }
+}
@@ -0,0 +1,271 @@
+//! Capture of the output of a conductor, which is written to log files and scanned for lines that
+//! match [`LogPattern`]s.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use anyhow::Context;
+use wind_tunnel_runner::prelude::{Counter, Reporter, WindTunnelResult};
+
+/// The name of the counter that lines matching a [`LogPattern`] are counted with.
+pub const CONDUCTOR_LOG_LINES_COUNTER: &str = "conductor_log_lines";
+
+/// A pattern to count in the lines that a conductor logs.
+///
+/// A line matches if it contains the pattern, once any colour codes have been removed. Matching
+/// lines are counted with the [`CONDUCTOR_LOG_LINES_COUNTER`] counter, tagged with the agent and
+/// with the name of the pattern as `pattern`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogPattern {
+    pub name: String,
+    pub pattern: String,
+}
+
+impl LogPattern {
+    pub fn new(name: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            pattern: pattern.into(),
+        }
+    }
+}
+
+/// The patterns that are counted unless they are replaced, which match the warnings, errors and
+/// panics that a conductor logs.
+pub(crate) fn default_log_patterns() -> Vec<LogPattern> {
+    vec![
+        LogPattern::new("warn", " WARN "),
+        LogPattern::new("error", " ERROR "),
+        LogPattern::new("panic", "panicked at"),
+    ]
+}
+
+/// A log file that stops being written to once it reaches its maximum size.
+#[derive(Debug)]
+struct LogFile {
+    file: File,
+    size: u64,
+    max_size: Option<u64>,
+}
+
+impl LogFile {
+    /// Open a log file to append to, so that the output of a restarted conductor is kept.
+    fn open(path: &Path, max_size: Option<u64>) -> WindTunnelResult<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create log directory '{}'", parent.display())
+            })?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file '{}'", path.display()))?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            file,
+            size,
+            max_size,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let len = line.len() as u64 + 1;
+        if let Some(max_size) = self.max_size
+            && self.size + len > max_size
+        {
+            if self.size < max_size {
+                // Mark the log as truncated, once
+                let _ = self.file.write_all(b"[log truncated]\n");
+                self.size = max_size;
+            }
+            return;
+        }
+
+        if let Err(err) = writeln!(self.file, "{line}") {
+            log::warn!("Failed to write to conductor log file: {err}");
+        }
+        self.size += len;
+    }
+}
+
+/// Handles the lines of one output stream of a conductor, logging them, writing them to a log file
+/// and counting the ones that match the [`LogPattern`]s.
+#[derive(Debug)]
+pub(crate) struct ConductorOutput {
+    log_target: String,
+    log_file: Option<LogFile>,
+    patterns: Vec<(String, Counter)>,
+}
+
+impl ConductorOutput {
+    pub(crate) fn new(
+        log_target: String,
+        log_path: Option<&Path>,
+        max_log_size: Option<u64>,
+        patterns: &[LogPattern],
+        reporter: Option<&Reporter>,
+        tags: &[(&str, &str)],
+    ) -> WindTunnelResult<Self> {
+        let log_file = log_path
+            .map(|log_path| LogFile::open(log_path, max_log_size))
+            .transpose()?;
+        let patterns = match reporter {
+            Some(reporter) => patterns
+                .iter()
+                .map(|log_pattern| {
+                    let mut pattern_tags = vec![("pattern", log_pattern.name.as_str())];
+                    pattern_tags.extend_from_slice(tags);
+                    (
+                        log_pattern.pattern.clone(),
+                        reporter.counter(CONDUCTOR_LOG_LINES_COUNTER, &pattern_tags),
+                    )
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            log_target,
+            log_file,
+            patterns,
+        })
+    }
+
+    /// Handle a line of output from the conductor.
+    pub(crate) fn line(&mut self, line: &str) {
+        if log::log_enabled!(target: &self.log_target, log::Level::Info) {
+            log::info!(target: &self.log_target, "{line}");
+        }
+
+        let line = strip_colour_codes(line);
+        for (pattern, counter) in &self.patterns {
+            if line.contains(pattern.as_str()) {
+                counter.inc();
+            }
+        }
+        if let Some(log_file) = &mut self.log_file {
+            log_file.write_line(&line);
+        }
+    }
+}
+
+/// Remove ANSI escape sequences, such as colour codes, from a line.
+fn strip_colour_codes(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip to the end of the sequence, which is a letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_strip_colour_codes() {
+        assert_eq!(
+            "2025-01-01T00:00:00Z  WARN holochain: slow",
+            strip_colour_codes("2025-01-01T00:00:00Z \u{1b}[33m WARN\u{1b}[0m holochain: slow")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_count_lines_matching_patterns() {
+        let reporter = wind_tunnel_runner::prelude::ReportConfig::new(
+            "test-run".to_string(),
+            "test-scenario".to_string(),
+        )
+        .init_reporter(
+            &tokio::runtime::Handle::current(),
+            &wind_tunnel_core::prelude::ShutdownHandle::new(),
+        )
+        .unwrap();
+
+        let mut output = ConductorOutput::new(
+            "holochain_conductor::agent-0".to_string(),
+            None,
+            None,
+            &default_log_patterns(),
+            Some(&reporter),
+            &[("agent", "agent-0")],
+        )
+        .unwrap();
+        output.line("2025-01-01T00:00:00Z \u{1b}[31mERROR\u{1b}[0m holochain: failed");
+        output.line("2025-01-01T00:00:00Z ERROR holochain: failed again");
+        output.line("2025-01-01T00:00:00Z  INFO holochain: fine");
+        output.line("thread 'main' panicked at src/main.rs:1:1");
+
+        let totals = reporter
+            .counter_totals()
+            .into_iter()
+            .map(|counter| {
+                let pattern = counter
+                    .tags
+                    .iter()
+                    .find(|(key, _)| key == "pattern")
+                    .map(|(_, value)| value.clone())
+                    .unwrap();
+                (pattern, counter.total)
+            })
+            .collect::<Vec<_>>();
+        assert!(totals.contains(&("error".to_string(), 2)), "{totals:?}");
+        assert!(totals.contains(&("panic".to_string(), 1)), "{totals:?}");
+        assert!(!totals.iter().any(|(pattern, _)| pattern == "warn"));
+    }
+
+    #[test]
+    fn test_should_stop_writing_log_file_at_max_size() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("logs").join("agent-0.stdout.log");
+
+        let mut output = ConductorOutput::new(
+            "holochain_conductor".to_string(),
+            Some(&path),
+            Some(16),
+            &default_log_patterns(),
+            None,
+            &[],
+        )
+        .unwrap();
+        output.line("first line");
+        output.line("second line");
+        output.line("third line");
+
+        assert_eq!(
+            "first line\n[log truncated]\n",
+            fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_should_append_to_existing_log_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("agent-0.stderr.log");
+        fs::write(&path, "before restart\n").unwrap();
+
+        let mut output =
+            ConductorOutput::new("target".to_string(), Some(&path), None, &[], None, &[]).unwrap();
+        output.line("after restart");
+
+        assert_eq!(
+            "before restart\nafter restart\n",
+            fs::read_to_string(&path).unwrap()
+        );
+    }
+}
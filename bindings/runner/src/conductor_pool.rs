@@ -0,0 +1,248 @@
+//! The Holochain conductors that Wind Tunnel runs locally, and the agents that are assigned to each
+//! of them.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    process::ExitStatus,
+    sync::{Arc, Mutex, MutexGuard, TryLockError, Weak},
+    time::Duration,
+};
+
+use anyhow::bail;
+use wind_tunnel_runner::prelude::WindTunnelResult;
+
+use crate::holochain_runner::HolochainRunner;
+
+/// Environment variable with the number of agents that share each conductor that Wind Tunnel runs,
+/// which is `1` if it is not set.
+pub const WT_AGENTS_PER_CONDUCTOR_ENV: &str = "WT_AGENTS_PER_CONDUCTOR";
+
+/// How often [`watch_for_unexpected_exit`] checks whether a conductor has exited.
+const EXIT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Get the number of agents that share each conductor from [`WT_AGENTS_PER_CONDUCTOR_ENV`].
+pub(crate) fn agents_per_conductor_from_env() -> WindTunnelResult<usize> {
+    match env::var(WT_AGENTS_PER_CONDUCTOR_ENV).ok().as_deref() {
+        None | Some("") => Ok(1),
+        Some(value) => match value.parse::<usize>() {
+            Ok(0) | Err(_) => bail!(
+                "'{WT_AGENTS_PER_CONDUCTOR_ENV}={value}' must be a number of agents greater than zero"
+            ),
+            Ok(agents_per_conductor) => Ok(agents_per_conductor),
+        },
+    }
+}
+
+/// A conductor that Wind Tunnel runs for one or more agents.
+///
+/// Each agent using the conductor holds an [`Arc`] to it, and the conductor is shut down and its
+/// files are cleaned up when the last of them is dropped.
+#[derive(Debug)]
+pub(crate) struct LocalConductor {
+    name: String,
+    shared: bool,
+    runner: Mutex<HolochainRunner>,
+}
+
+impl LocalConductor {
+    /// The name of the conductor, which is the name of its agent if it is not shared.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the conductor can be used by more than one agent.
+    pub(crate) fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    pub(crate) fn runner(&self) -> MutexGuard<'_, HolochainRunner> {
+        self.runner.lock().expect("Conductor runner lock poisoned")
+    }
+}
+
+/// The conductors that Wind Tunnel runs locally, keyed by name, and the conductor that each agent
+/// is assigned to.
+#[derive(Debug, Default)]
+pub(crate) struct ConductorPool {
+    /// The running conductor with each name, if any agent is still using it. Each entry is locked
+    /// while its conductor is being started, so that it is only started once.
+    conductors: Mutex<HashMap<String, Arc<Mutex<Weak<LocalConductor>>>>>,
+
+    /// The name of the conductor that each agent is assigned to.
+    assignments: Mutex<BTreeMap<String, String>>,
+}
+
+impl ConductorPool {
+    /// Assign an agent to a conductor, returning the name of the conductor.
+    ///
+    /// Agents are assigned by index, so that agents `0..agents_per_conductor` share the conductor
+    /// `conductor-0` and so on. If each agent has its own conductor, then the conductor is named
+    /// after the agent.
+    pub(crate) fn assign(
+        &self,
+        agent_index: usize,
+        agent_name: &str,
+        agents_per_conductor: usize,
+    ) -> String {
+        let conductor_name = if agents_per_conductor > 1 {
+            format!("conductor-{}", agent_index / agents_per_conductor)
+        } else {
+            agent_name.to_string()
+        };
+        self.assignments
+            .lock()
+            .expect("Conductor assignments lock poisoned")
+            .insert(agent_name.to_string(), conductor_name.clone());
+
+        conductor_name
+    }
+
+    /// The name of the conductor that an agent was assigned to, if any.
+    pub(crate) fn conductor_for_agent(&self, agent_name: &str) -> Option<String> {
+        self.assignments
+            .lock()
+            .expect("Conductor assignments lock poisoned")
+            .get(agent_name)
+            .cloned()
+    }
+
+    /// Get the running conductor with this name, or start it with `start` if no agent is using one.
+    pub(crate) fn get_or_start(
+        &self,
+        conductor_name: &str,
+        shared: bool,
+        start: impl FnOnce() -> WindTunnelResult<HolochainRunner>,
+    ) -> WindTunnelResult<Arc<LocalConductor>> {
+        let entry = self
+            .conductors
+            .lock()
+            .expect("Conductors lock poisoned")
+            .entry(conductor_name.to_string())
+            .or_default()
+            .clone();
+        let mut entry = entry.lock().expect("Conductor lock poisoned");
+
+        if let Some(conductor) = entry.upgrade() {
+            log::debug!("Using running Holochain conductor '{conductor_name}'");
+            return Ok(conductor);
+        }
+
+        let conductor = Arc::new(LocalConductor {
+            name: conductor_name.to_string(),
+            shared,
+            runner: Mutex::new(start()?),
+        });
+        *entry = Arc::downgrade(&conductor);
+
+        Ok(conductor)
+    }
+}
+
+/// Watch a conductor until it exits unexpectedly, for example because it crashed, then call
+/// `on_exit` with its exit status.
+///
+/// Stops watching, without calling `on_exit`, once no agent is using the conductor.
+pub(crate) async fn watch_for_unexpected_exit(
+    conductor: Weak<LocalConductor>,
+    on_exit: impl FnOnce(ExitStatus),
+) {
+    let mut ticks = tokio::time::interval(EXIT_CHECK_INTERVAL);
+    loop {
+        ticks.tick().await;
+        let Some(conductor) = conductor.upgrade() else {
+            return;
+        };
+        // Check again on the next tick if an agent is starting or stopping the conductor, rather
+        // than blocking the async thread until it is done
+        let exit_status = match conductor.runner.try_lock() {
+            Ok(mut runner) => runner.unexpected_exit(),
+            Err(TryLockError::WouldBlock) => None,
+            Err(TryLockError::Poisoned(_)) => return,
+        };
+        if let Some(exit_status) = exit_status {
+            on_exit(exit_status);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::holochain_runner::HolochainConfigBuilder;
+
+    fn create_runner(conductor_root: &std::path::Path) -> WindTunnelResult<HolochainRunner> {
+        let mut builder = HolochainConfigBuilder::default();
+        builder
+            .with_conductor_root_path(conductor_root)
+            .with_admin_port(0)
+            .with_metrics_path(conductor_root.with_extension("influx"));
+        HolochainRunner::create(&builder.build()?)
+    }
+
+    #[test]
+    fn test_should_assign_agents_to_conductors_by_index() {
+        let pool = ConductorPool::default();
+
+        assert_eq!("conductor-0", pool.assign(0, "agent-0", 3));
+        assert_eq!("conductor-0", pool.assign(2, "agent-2", 3));
+        assert_eq!("conductor-1", pool.assign(3, "agent-3", 3));
+        assert_eq!("agent-4", pool.assign(4, "agent-4", 1));
+
+        assert_eq!(
+            Some("conductor-0".to_string()),
+            pool.conductor_for_agent("agent-2")
+        );
+        assert_eq!(None, pool.conductor_for_agent("agent-5"));
+    }
+
+    #[test]
+    fn test_should_start_shared_conductor_once() {
+        let tmp = tempdir().unwrap();
+        let conductor_root = tmp.path().join("conductor-0");
+        let pool = ConductorPool::default();
+        let starts = AtomicUsize::new(0);
+        let start = || {
+            starts.fetch_add(1, Ordering::SeqCst);
+            create_runner(&conductor_root)
+        };
+
+        let first = pool.get_or_start("conductor-0", true, start).unwrap();
+        let second = pool.get_or_start("conductor-0", true, start).unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(1, starts.load(Ordering::SeqCst));
+        assert!(first.is_shared());
+
+        // The conductor is cleaned up once the last agent using it is done
+        drop(first);
+        assert!(conductor_root.exists());
+        drop(second);
+        assert!(!conductor_root.exists());
+
+        pool.get_or_start("conductor-0", true, start).unwrap();
+        assert_eq!(2, starts.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_should_start_conductor_again_after_failed_start() {
+        let tmp = tempdir().unwrap();
+        let pool = ConductorPool::default();
+
+        let result = pool.get_or_start("agent-0", false, || bail!("Failed to start"));
+        assert!(result.is_err());
+
+        let conductor = pool
+            .get_or_start("agent-0", false, || {
+                create_runner(&tmp.path().join("agent-0"))
+            })
+            .unwrap();
+        assert_eq!("agent-0", conductor.name());
+        assert!(!conductor.is_shared());
+    }
+}
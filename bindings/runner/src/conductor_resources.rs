@@ -0,0 +1,181 @@
+//! Sampling of the resources used by a conductor process, which are reported as the
+//! `conductor_resources` metric.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::task::AbortHandle;
+use wind_tunnel_runner::prelude::{ReportMetric, Reporter};
+
+/// The resources used by a conductor at the time it was sampled.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ResourceSample {
+    /// CPU usage since the previous sample, as a percentage of one core.
+    pub(crate) cpu_percent: f32,
+    /// Resident set size, in bytes.
+    pub(crate) rss_bytes: u64,
+    /// The number of open file descriptors, if the platform provides it.
+    pub(crate) open_fds: Option<usize>,
+    /// The number of threads, if the platform provides it.
+    pub(crate) threads: Option<usize>,
+    /// The total size of the files in the conductor's data directory, in bytes.
+    pub(crate) data_dir_bytes: u64,
+}
+
+impl ResourceSample {
+    fn into_metric(self, tags: &[(&'static str, String)]) -> ReportMetric {
+        let mut metric = ReportMetric::new("conductor_resources")
+            .with_field("cpu_percent", self.cpu_percent as f64)
+            .with_field("rss_bytes", self.rss_bytes)
+            .with_field("data_dir_bytes", self.data_dir_bytes);
+        if let Some(open_fds) = self.open_fds {
+            metric = metric.with_field("open_fds", open_fds as u64);
+        }
+        if let Some(threads) = self.threads {
+            metric = metric.with_field("threads", threads as u64);
+        }
+        for (key, value) in tags {
+            metric = metric.with_tag(*key, value.clone());
+        }
+        metric
+    }
+}
+
+/// Samples the resources used by one process and the size of its data directory.
+#[derive(Debug)]
+pub(crate) struct ResourceSampler {
+    pid: Pid,
+    data_dir: PathBuf,
+    system: System,
+}
+
+impl ResourceSampler {
+    pub(crate) fn new(pid: u32, data_dir: impl Into<PathBuf>) -> Self {
+        let mut sampler = Self {
+            pid: Pid::from_u32(pid),
+            data_dir: data_dir.into(),
+            system: System::new(),
+        };
+        // CPU usage is measured between refreshes, so refresh once to start measuring
+        sampler.refresh();
+        sampler
+    }
+
+    fn refresh(&mut self) {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[self.pid]),
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cpu()
+                .with_memory()
+                .with_tasks(),
+        );
+    }
+
+    /// Take a sample, or return [`None`] if the process is no longer running.
+    pub(crate) fn sample(&mut self) -> Option<ResourceSample> {
+        self.refresh();
+        let process = self.system.process(self.pid)?;
+
+        Some(ResourceSample {
+            cpu_percent: process.cpu_usage(),
+            rss_bytes: process.memory(),
+            open_fds: process.open_files(),
+            threads: process.tasks().map(|tasks| tasks.len()),
+            data_dir_bytes: dir_size(&self.data_dir),
+        })
+    }
+}
+
+/// Start sampling the resources used by a conductor every `interval`, reporting each sample as the
+/// `conductor_resources` metric with `tags`.
+///
+/// Sampling stops when the conductor exits or the returned handle is aborted.
+pub(crate) fn start_resource_sampling(
+    mut sampler: ResourceSampler,
+    interval: Duration,
+    reporter: Arc<Reporter>,
+    tags: Vec<(&'static str, String)>,
+) -> AbortHandle {
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        loop {
+            ticks.tick().await;
+            // Walking the data directory is blocking, so sample off the async threads
+            let (returned_sampler, sample) = match tokio::task::spawn_blocking(move || {
+                let sample = sampler.sample();
+                (sampler, sample)
+            })
+            .await
+            {
+                Ok(sampled) => sampled,
+                Err(err) => {
+                    log::error!("Failed to sample conductor resources: {err}");
+                    return;
+                }
+            };
+            sampler = returned_sampler;
+
+            let Some(sample) = sample else {
+                log::debug!("Conductor is no longer running, stopped sampling its resources");
+                return;
+            };
+            reporter.add_custom(sample.into_metric(&tags));
+        }
+    })
+    .abort_handle()
+}
+
+/// The total size of the files in a directory and its subdirectories, ignoring files that cannot be
+/// read, for example because the conductor removed them while they were being counted.
+fn dir_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_should_sample_running_process() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("databases")).unwrap();
+        fs::write(tmp.path().join("conductor-config.yaml"), [0u8; 100]).unwrap();
+        fs::write(tmp.path().join("databases").join("dht.sqlite3"), [0u8; 400]).unwrap();
+
+        let mut sampler = ResourceSampler::new(std::process::id(), tmp.path());
+        let sample = sampler.sample().unwrap();
+
+        assert!(sample.rss_bytes > 0);
+        assert_eq!(500, sample.data_dir_bytes);
+        #[cfg(target_os = "linux")]
+        {
+            assert!(sample.open_fds.unwrap() > 0);
+            assert!(sample.threads.unwrap() > 0);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_should_not_sample_exited_process() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        let mut sampler = ResourceSampler::new(pid, "/nonexistent");
+
+        assert_eq!(None, sampler.sample());
+    }
+}
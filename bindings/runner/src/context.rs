@@ -1,10 +1,12 @@
 use holochain_client_instrumented::prelude::AppWebsocket;
 use holochain_types::prelude::{CellId, RoleName};
 use std::fmt::Debug;
+use std::sync::Arc;
 use std::{collections::HashMap, net::SocketAddr};
 use wind_tunnel_runner::prelude::UserValuesConstraint;
 
-use crate::holochain_runner::{HolochainConfigBuilder, HolochainRunner};
+use crate::conductor_pool::LocalConductor;
+use crate::holochain_runner::HolochainConfigBuilder;
 
 #[derive(Debug, Default)]
 pub struct DefaultScenarioValues {
@@ -24,7 +26,8 @@ pub struct HolochainAgentContext<T: UserValuesConstraint = DefaultScenarioValues
     pub(crate) app_ws_url: Option<SocketAddr>,
     pub(crate) admin_ws_url: Option<SocketAddr>,
     pub(crate) holochain_config: Option<HolochainConfigBuilder>,
-    pub(crate) holochain_runner: Option<HolochainRunner>,
+    /// The locally run conductor that this agent uses, which may be shared with other agents.
+    pub(crate) conductor: Option<Arc<LocalConductor>>,
     pub scenario_values: T,
 }
 
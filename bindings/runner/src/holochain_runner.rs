@@ -1,6 +1,13 @@
 //! Provides the ability to configure and run a Holochain conductor as a [`Child`] process.
 
-use std::{fs, net::SocketAddr, path::PathBuf, process::Stdio, time::Duration};
+use std::{
+    fs,
+    net::SocketAddr,
+    path::PathBuf,
+    process::{ExitStatus, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, anyhow};
 use holochain_client_instrumented::ToSocketAddr;
@@ -16,9 +23,24 @@ use holochain_types::websocket::AllowedOrigins;
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     process::{Child, Command},
+    task::AbortHandle,
     time::timeout,
 };
-use wind_tunnel_runner::prelude::WindTunnelResult;
+use wind_tunnel_runner::prelude::{ReportMetric, Reporter, WindTunnelResult};
+
+use crate::conductor_config::{apply_overlay, merge_overlay};
+use crate::conductor_log::{ConductorOutput, LogPattern, default_log_patterns};
+use crate::conductor_resources::{ResourceSampler, start_resource_sampling};
+
+/// How long to wait for a conductor to exit after asking it to shut down, before killing it.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often to check whether a conductor has exited, when waiting for it outside of an async
+/// context.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often to sample the resources used by a conductor.
+const DEFAULT_RESOURCE_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
 
 /// Used to build a [`HolochainConfig`], which is then passed into [`HolochainRunner::run`] to
 /// spawn a [`Child`] process running a Holochain conductor with the specified config.
@@ -35,6 +57,12 @@ pub struct HolochainConfigBuilder {
     /// will also be [`None`].
     agent_name: Option<String>,
 
+    /// The name of the conductor, if it is shared by more than one agent.
+    ///
+    /// If [`None`] when [`Self::build`] is called, then the [`HolochainConfig::conductor_name`]
+    /// field will also be [`None`].
+    conductor_name: Option<String>,
+
     /// If set when [`Self::build`] is called then an admin interface is created on the conductor
     /// that is accessible via this port.
     admin_port: Option<u16>,
@@ -50,6 +78,31 @@ pub struct HolochainConfigBuilder {
     /// The path where influxive metrics will be written, by setting the
     /// env variable HOLOCHAIN_INFLUXIVE_FILE for the holochain process.
     metrics_path: Option<PathBuf>,
+
+    /// Conductor config overrides that are merged into the generated [`ConductorConfig`].
+    config_overlay: Option<serde_json::Value>,
+
+    /// How long to wait for the conductor to exit when it is shut down, before killing it.
+    ///
+    /// If [`None`] when [`Self::build`] is called then a default of 10 seconds is used.
+    shutdown_timeout: Option<Duration>,
+
+    /// The directory that the conductor's stdout and stderr are written to, as
+    /// `<agent-name>.stdout.log` and `<agent-name>.stderr.log`.
+    ///
+    /// If [`None`] when [`Self::build`] is called then the output is not written to files.
+    log_dir: Option<PathBuf>,
+
+    /// The maximum size of each log file in bytes, after which output is no longer written to it.
+    max_log_size: Option<u64>,
+
+    /// Patterns to count in the conductor's output, in addition to or replacing the defaults.
+    log_patterns: Vec<LogPattern>,
+
+    /// How often to sample the resources used by the conductor.
+    ///
+    /// If [`None`] when [`Self::build`] is called then a default of 5 seconds is used.
+    resource_sample_interval: Option<Duration>,
 }
 
 impl HolochainConfigBuilder {
@@ -60,6 +113,63 @@ impl HolochainConfigBuilder {
         self
     }
 
+    /// Override fields of the generated [`ConductorConfig`], such as network tuning or the database
+    /// sync level, with a partial config.
+    ///
+    /// The overlay is merged into the generated config field by field, so it only needs the fields
+    /// that it changes, for example `json!({"network": {"request_timeout_s": 120}})`. Calling this
+    /// again merges the new overlay on top of the previous one. The data root path, keystore and
+    /// admin interfaces are set by Wind Tunnel and cannot be overridden.
+    pub fn with_config_overlay(&mut self, overlay: serde_json::Value) -> &mut Self {
+        match &mut self.config_overlay {
+            Some(config_overlay) => merge_overlay(config_overlay, overlay),
+            None => self.config_overlay = Some(overlay),
+        }
+        self
+    }
+
+    /// The conductor config overrides that will be merged into the generated config, if any.
+    pub(crate) fn config_overlay(&self) -> Option<&serde_json::Value> {
+        self.config_overlay.as_ref()
+    }
+
+    /// Set how long to wait for the conductor to exit when it is shut down, before it is killed.
+    pub fn with_shutdown_timeout(&mut self, shutdown_timeout: Duration) -> &mut Self {
+        self.shutdown_timeout = Some(shutdown_timeout);
+        self
+    }
+
+    /// Set the maximum size of each of the conductor's log files, in bytes. Once a log file reaches
+    /// this size, it is marked as truncated and no more output is written to it.
+    pub fn with_max_log_size(&mut self, max_log_size: u64) -> &mut Self {
+        self.max_log_size = Some(max_log_size);
+        self
+    }
+
+    /// Count the lines of the conductor's output that match a [`LogPattern`].
+    ///
+    /// The patterns named `warn`, `error` and `panic` are counted by default, and are replaced by a
+    /// pattern with the same name.
+    pub fn with_log_pattern(&mut self, log_pattern: LogPattern) -> &mut Self {
+        self.log_patterns
+            .retain(|existing| existing.name != log_pattern.name);
+        self.log_patterns.push(log_pattern);
+        self
+    }
+
+    /// Set how often the CPU, memory, open file descriptors, threads and data directory size of the
+    /// conductor are sampled and reported as the `conductor_resources` metric.
+    pub fn with_resource_sample_interval(&mut self, interval: Duration) -> &mut Self {
+        self.resource_sample_interval = Some(interval);
+        self
+    }
+
+    /// Write the conductor's stdout and stderr to log files in this directory.
+    pub(crate) fn with_log_dir(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.log_dir = Some(path.into());
+        self
+    }
+
     /// Override the path to the `holochain` binary that is used to start a conductor.
     pub(crate) fn with_bin_path(&mut self, path: impl Into<PathBuf>) -> &mut Self {
         self.bin_path = Some(path.into());
@@ -78,6 +188,12 @@ impl HolochainConfigBuilder {
         self
     }
 
+    /// Set the name of the conductor, when it is shared by more than one agent.
+    pub(crate) fn with_conductor_name(&mut self, conductor_name: impl Into<String>) -> &mut Self {
+        self.conductor_name = Some(conductor_name.into());
+        self
+    }
+
     /// Set the root path of the conductor where the generated config is written and the conductor
     /// stores its data.
     pub(crate) fn with_conductor_root_path(&mut self, path: impl Into<PathBuf>) -> &mut Self {
@@ -120,16 +236,36 @@ impl HolochainConfigBuilder {
         if let Some(target_arc_factor) = self.target_arc_factor {
             conductor_config.network.target_arc_factor = target_arc_factor;
         }
+        if let Some(config_overlay) = &self.config_overlay {
+            conductor_config = apply_overlay(&conductor_config, config_overlay)?;
+        }
         let metrics_path = self.metrics_path.ok_or(anyhow!(
             "Metrics path not set, this should be set by the Wind Tunnel runner"
         ))?;
 
+        let mut log_patterns = default_log_patterns();
+        log_patterns.retain(|default| {
+            !self
+                .log_patterns
+                .iter()
+                .any(|log_pattern| log_pattern.name == default.name)
+        });
+        log_patterns.extend(self.log_patterns);
+
         Ok(HolochainConfig {
             bin_path,
             agent_name: self.agent_name,
+            conductor_name: self.conductor_name,
             conductor_root_path,
             conductor_config,
             metrics_path,
+            shutdown_timeout: self.shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT),
+            log_dir: self.log_dir,
+            max_log_size: self.max_log_size,
+            log_patterns,
+            resource_sample_interval: self
+                .resource_sample_interval
+                .unwrap_or(DEFAULT_RESOURCE_SAMPLE_INTERVAL),
         })
     }
 }
@@ -147,6 +283,9 @@ pub struct HolochainConfig {
     /// The name of the agent that runs on this conductor.
     agent_name: Option<String>,
 
+    /// The name of the conductor, if it is shared by more than one agent.
+    conductor_name: Option<String>,
+
     /// The path where the generated config and the data for the Holochain conductor is stored.
     conductor_root_path: PathBuf,
 
@@ -157,6 +296,21 @@ pub struct HolochainConfig {
     /// The path where influxive metrics will be written, by setting the
     /// env variable HOLOCHAIN_INFLUXIVE_FILE for the holochain process.
     metrics_path: PathBuf,
+
+    /// How long to wait for the conductor to exit when it is shut down, before killing it.
+    shutdown_timeout: Duration,
+
+    /// The directory that the conductor's stdout and stderr are written to, if any.
+    log_dir: Option<PathBuf>,
+
+    /// The maximum size of each log file in bytes, if any.
+    max_log_size: Option<u64>,
+
+    /// The patterns that are counted in the conductor's output.
+    log_patterns: Vec<LogPattern>,
+
+    /// How often to sample the resources used by the conductor.
+    resource_sample_interval: Duration,
 }
 
 /// Holds the [`Child`] process that is running the Holochain conductor, as well as the path to the
@@ -172,6 +326,12 @@ pub struct HolochainRunner {
 
     /// The url of the admin websocket interface
     admin_ws_url: Option<SocketAddr>,
+
+    /// The reporter that conductor shutdowns and resource usage are reported to, if set.
+    reporter: Option<Arc<Reporter>>,
+
+    /// The task that samples the resources used by the conductor, while it is running.
+    resource_sampling: Option<AbortHandle>,
 }
 
 impl HolochainRunner {
@@ -199,9 +359,18 @@ impl HolochainRunner {
             config: config.clone(),
             holochain_handle: None,
             admin_ws_url: None,
+            reporter: None,
+            resource_sampling: None,
         })
     }
 
+    /// Report conductor shutdowns and resource usage to this reporter, as the `conductor_shutdown`
+    /// and `conductor_resources` metrics.
+    pub(crate) fn with_reporter(mut self, reporter: Arc<Reporter>) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
     /// Runs an instance of Holochain from the stored [`HolochainConfig`]. Storing the [`Child`]
     /// process internally so it can be gracefully shutdown and clean up directories on
     /// [`Drop::drop`].
@@ -217,6 +386,7 @@ impl HolochainRunner {
             .arg("--piped")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .kill_on_drop(true)
             .spawn()
             .context("Failed to run Holochain conductor")?;
@@ -230,13 +400,26 @@ impl HolochainRunner {
             .await
             .context("Failed to write the password to the process running the conductor")?;
 
+        let mut stdout_output = self.conductor_output("stdout")?;
+        let mut stderr_output = self.conductor_output("stderr")?;
+
+        let holochain_stderr = holochain_handle
+            .stderr
+            .take()
+            .context("Failed to get stderr for the running Holochain conductor")?;
+        tokio::spawn(async move {
+            let mut stderr_lines = BufReader::new(holochain_stderr).lines();
+            while let Ok(Some(line)) = stderr_lines.next_line().await {
+                stderr_output.line(&line);
+            }
+        });
+
         log::trace!("Waiting for the conductor to start");
         let holochain_stdout = holochain_handle
             .stdout
             .take()
             .context("Failed to get stdout for the running Holochain conductor")?;
 
-        let agent_name = self.config.agent_name.clone();
         timeout(Duration::from_secs(30), async move {
             let mut stdout_lines = BufReader::new(holochain_stdout).lines();
             loop {
@@ -245,18 +428,11 @@ impl HolochainRunner {
                     .await
                     .context("Failed to read line from Holochain conductor stdout")?
                     .ok_or(anyhow!("Holochain conductor shutdown before it was ready"))?;
-                let mut log_target = "holochain_conductor".to_string();
-                if let Some(agent_name) = &agent_name {
-                    log_target.push_str("::");
-                    log_target.push_str(agent_name);
-                }
-                log::info!(target: &log_target, "{line}");
+                stdout_output.line(&line);
                 if line == "Conductor ready." {
                     tokio::spawn(async move {
                         while let Ok(Some(line)) = stdout_lines.next_line().await {
-                            if log::log_enabled!(target: &log_target, log::Level::Info) {
-                                log::info!(target: &log_target, "{line}");
-                            }
+                            stdout_output.line(&line);
                         }
                     });
 
@@ -267,6 +443,16 @@ impl HolochainRunner {
         .await
         .context("Timed-out whilst waiting for the Holochain conductor to be ready")??;
 
+        if let Some(reporter) = &self.reporter
+            && let Some(pid) = holochain_handle.id()
+        {
+            self.resource_sampling = Some(start_resource_sampling(
+                ResourceSampler::new(pid, &self.config.conductor_root_path),
+                self.config.resource_sample_interval,
+                reporter.clone(),
+                self.metric_tags(),
+            ));
+        }
         self.holochain_handle = Some(holochain_handle);
 
         let admin_port = self
@@ -283,11 +469,188 @@ impl HolochainRunner {
         Ok(())
     }
 
-    pub fn shutdown(&mut self) {
+    /// Create the handler for one of the conductor's output streams, which logs its lines with the
+    /// target `holochain_conductor::<name>`, writes them to the log file for the stream and counts
+    /// the lines that match the log patterns.
+    ///
+    /// The name is the conductor's name if it is shared, or otherwise the name of its agent.
+    fn conductor_output(&self, stream: &str) -> WindTunnelResult<ConductorOutput> {
+        let name = self
+            .config
+            .conductor_name
+            .as_deref()
+            .or(self.config.agent_name.as_deref());
+        let log_target = match name {
+            Some(name) => format!("holochain_conductor::{name}"),
+            None => "holochain_conductor".to_string(),
+        };
+        let log_path =
+            self.config.log_dir.as_ref().map(|log_dir| {
+                log_dir.join(format!("{}.{stream}.log", name.unwrap_or("conductor")))
+            });
+        let tags = self.metric_tags();
+        let tags = tags
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect::<Vec<_>>();
+
+        ConductorOutput::new(
+            log_target,
+            log_path.as_deref(),
+            self.config.max_log_size,
+            &self.config.log_patterns,
+            self.reporter.as_deref(),
+            &tags,
+        )
+    }
+
+    /// The tags for the metrics reported about this conductor: `conductor` with the name of a
+    /// shared conductor, or `agent` with the name of the agent that runs on it.
+    fn metric_tags(&self) -> Vec<(&'static str, String)> {
+        let mut tags = Vec::new();
+        if let Some(conductor_name) = &self.config.conductor_name {
+            tags.push(("conductor", conductor_name.clone()));
+        }
+        if let Some(agent_name) = &self.config.agent_name {
+            tags.push(("agent", agent_name.clone()));
+        }
+        tags
+    }
+
+    /// Shut down the conductor, if it is running.
+    ///
+    /// The conductor is sent `SIGTERM` so that it can close its databases cleanly, and is killed if
+    /// it has not exited within the shutdown timeout. The exit status and how long the shutdown took
+    /// are reported as the `conductor_shutdown` metric.
+    ///
+    /// Returns the exit status of the conductor, or [`None`] if it was not running.
+    pub async fn shutdown(&mut self) -> WindTunnelResult<Option<ExitStatus>> {
+        self.stop_resource_sampling();
+        let Some(mut holochain_handle) = self.holochain_handle.take() else {
+            return Ok(None);
+        };
         log::info!("Shutting down holochain conductor");
 
-        // This will drop the child process, which will shutdown the conductor
-        self.holochain_handle = None;
+        let started = Instant::now();
+        let mut killed = false;
+        let exit_status = match holochain_handle.try_wait()? {
+            Some(exit_status) => {
+                log::warn!("Holochain conductor had already exited with {exit_status}");
+                exit_status
+            }
+            None => {
+                terminate(&mut holochain_handle)?;
+                match timeout(self.config.shutdown_timeout, holochain_handle.wait()).await {
+                    Ok(exit_status) => {
+                        exit_status.context("Failed to wait for the Holochain conductor to exit")?
+                    }
+                    Err(_) => {
+                        log::warn!(
+                            "Holochain conductor did not exit within {:?}, killing it",
+                            self.config.shutdown_timeout
+                        );
+                        killed = true;
+                        holochain_handle
+                            .kill()
+                            .await
+                            .context("Failed to kill the Holochain conductor")?;
+                        holochain_handle
+                            .wait()
+                            .await
+                            .context("Failed to wait for the Holochain conductor to exit")?
+                    }
+                }
+            }
+        };
+        self.report_shutdown(Some(exit_status), started.elapsed(), killed);
+
+        Ok(Some(exit_status))
+    }
+
+    /// Shut down the conductor like [`Self::shutdown`], but by polling for it to exit so that this
+    /// can be called when the runner is dropped.
+    ///
+    /// When this is called on a multi-thread Tokio runtime, the other tasks on the worker thread
+    /// are moved off it while waiting, so that they are not blocked for up to the shutdown timeout.
+    fn shutdown_blocking(&mut self) {
+        self.stop_resource_sampling();
+        let Some(mut holochain_handle) = self.holochain_handle.take() else {
+            return;
+        };
+        log::info!("Shutting down holochain conductor");
+
+        let started = Instant::now();
+        if let Err(err) = terminate(&mut holochain_handle) {
+            log::error!("{err:?}");
+        }
+        let shutdown_timeout = self.config.shutdown_timeout;
+        let exit_status = block_in_place_if_possible(|| {
+            loop {
+                match holochain_handle.try_wait() {
+                    Ok(Some(exit_status)) => break Some(exit_status),
+                    Ok(None) if started.elapsed() < shutdown_timeout => {
+                        std::thread::sleep(EXIT_POLL_INTERVAL);
+                    }
+                    Ok(None) => break None,
+                    Err(err) => {
+                        log::error!(
+                            "Failed to check whether the Holochain conductor exited: {err}"
+                        );
+                        break None;
+                    }
+                }
+            }
+        });
+
+        let killed = exit_status.is_none();
+        if killed {
+            log::warn!(
+                "Holochain conductor did not exit within {:?}, killing it",
+                self.config.shutdown_timeout
+            );
+            if let Err(err) = holochain_handle.start_kill() {
+                log::error!("Failed to kill the Holochain conductor: {err}");
+            }
+        }
+        self.report_shutdown(exit_status, started.elapsed(), killed);
+    }
+
+    fn stop_resource_sampling(&mut self) {
+        if let Some(resource_sampling) = self.resource_sampling.take() {
+            resource_sampling.abort();
+        }
+    }
+
+    /// Log and report how the conductor exited when it was shut down.
+    fn report_shutdown(&self, exit_status: Option<ExitStatus>, duration: Duration, killed: bool) {
+        match exit_status {
+            Some(exit_status) => {
+                log::info!("Holochain conductor exited with {exit_status} after {duration:?}")
+            }
+            None => log::info!("Holochain conductor was killed after {duration:?}"),
+        }
+
+        let Some(reporter) = &self.reporter else {
+            return;
+        };
+        let mut metric = ReportMetric::new("conductor_shutdown")
+            .with_field("duration_s", duration.as_secs_f64())
+            .with_field("killed", killed);
+        if let Some(exit_code) = exit_status.and_then(|exit_status| exit_status.code()) {
+            metric = metric.with_field("exit_code", exit_code as i64);
+        }
+        for (key, value) in self.metric_tags() {
+            metric = metric.with_tag(key, value);
+        }
+        reporter.add_custom(metric);
+    }
+
+    /// Get the exit status of the conductor if it has exited while it was meant to be running, for
+    /// example because it crashed.
+    ///
+    /// Returns [`None`] if the conductor is still running or has not been started.
+    pub fn unexpected_exit(&mut self) -> Option<ExitStatus> {
+        self.holochain_handle.as_mut()?.try_wait().ok().flatten()
     }
 
     /// Get the admin websocket url
@@ -309,6 +672,8 @@ impl HolochainRunner {
 
 impl Drop for HolochainRunner {
     fn drop(&mut self) {
+        self.shutdown_blocking();
+
         log::trace!("Cleaning up the conductor files");
         if let Err(err) = fs::remove_dir_all(&self.config.conductor_root_path) {
             log::error!("Failed to cleanup the conductor files: {err}");
@@ -324,13 +689,51 @@ impl Drop for HolochainRunner {
     }
 }
 
+/// Run blocking code, telling the Tokio runtime first if it is called from one of its worker
+/// threads, so that the other tasks on that worker can be moved to another thread.
+///
+/// This is only possible on a multi-thread runtime. The current thread runtime has no other
+/// threads to move tasks to, so it is blocked until `f` returns.
+fn block_in_place_if_possible<T>(f: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// Ask the conductor to shut down.
+#[cfg(unix)]
+fn terminate(holochain_handle: &mut Child) -> WindTunnelResult<()> {
+    use nix::{
+        sys::signal::{Signal, kill},
+        unistd::Pid,
+    };
+
+    // The process has already been waited for, so it has exited
+    let Some(pid) = holochain_handle.id() else {
+        return Ok(());
+    };
+    kill(Pid::from_raw(pid as i32), Signal::SIGTERM)
+        .context("Failed to send SIGTERM to the Holochain conductor")
+}
+
+/// Ask the conductor to shut down, which without signals means killing it.
+#[cfg(not(unix))]
+fn terminate(holochain_handle: &mut Child) -> WindTunnelResult<()> {
+    holochain_handle
+        .start_kill()
+        .context("Failed to kill the Holochain conductor")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::tempdir;
 
-    #[test]
-    fn conductor_dir_retained_after_shutdown() {
+    #[tokio::test]
+    async fn conductor_dir_retained_after_shutdown() {
         let tmp = tempdir().unwrap();
         let conductor_root = tmp.path().join("conductor");
         let metrics_path = tmp.path().join("metrics.influx");
@@ -348,7 +751,7 @@ mod tests {
         assert!(conductor_root.exists(),);
 
         // Shutdown the runner
-        runner.shutdown();
+        assert!(runner.shutdown().await.unwrap().is_none());
 
         // Conductor dir still exists
         assert!(conductor_root.exists());
@@ -359,4 +762,266 @@ mod tests {
         // Conductor dir deleted
         assert!(!conductor_root.exists());
     }
+
+    #[test]
+    fn shared_conductor_metrics_are_tagged_with_conductor() {
+        let tmp = tempdir().unwrap();
+        let mut builder = HolochainConfigBuilder::default();
+        builder
+            .with_conductor_root_path(tmp.path().join("conductor-0"))
+            .with_admin_port(0)
+            .with_metrics_path(tmp.path().join("metrics.influx"))
+            .with_conductor_name("conductor-0");
+        let runner = HolochainRunner::create(&builder.build().unwrap()).unwrap();
+
+        assert_eq!(
+            vec![("conductor", "conductor-0".to_string())],
+            runner.metric_tags()
+        );
+    }
+
+    #[test]
+    fn config_overlays_are_merged_into_conductor_config() {
+        let tmp = tempdir().unwrap();
+
+        let mut builder = HolochainConfigBuilder::default();
+        builder
+            .with_conductor_root_path(tmp.path().join("conductor"))
+            .with_admin_port(0)
+            .with_metrics_path(tmp.path().join("metrics.influx"))
+            .with_config_overlay(serde_json::json!({"network": {"target_arc_factor": 0}}))
+            .with_config_overlay(serde_json::json!({"network": {"request_timeout_s": 120}}));
+        assert_eq!(
+            Some(
+                &serde_json::json!({"network": {"target_arc_factor": 0, "request_timeout_s": 120}})
+            ),
+            builder.config_overlay()
+        );
+        let config = builder.build().expect("Failed to build HolochainConfig");
+
+        assert_eq!(0, config.conductor_config.network.target_arc_factor);
+        assert_eq!(120, config.conductor_config.network.request_timeout_s);
+        assert!(config.conductor_config.admin_interfaces.is_some());
+    }
+
+    /// Create a runner for a fake `holochain` binary, a shell script that sets `trap` for signals,
+    /// reads the password and reports that it is ready, then runs `script`.
+    #[cfg(unix)]
+    fn fake_conductor_runner(
+        tmp: &tempfile::TempDir,
+        trap: &str,
+        script: &str,
+        shutdown_timeout: Duration,
+    ) -> HolochainRunner {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let bin_path = tmp.path().join("holochain");
+        fs::write(
+            &bin_path,
+            format!("#!/bin/sh\n{trap}\nread password\necho \"Conductor ready.\"\n{script}\n"),
+        )
+        .unwrap();
+        fs::set_permissions(&bin_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut builder = HolochainConfigBuilder::default();
+        builder
+            .with_bin_path(bin_path)
+            .with_conductor_root_path(tmp.path().join("conductor"))
+            .with_admin_port(0)
+            .with_metrics_path(tmp.path().join("metrics.influx"))
+            .with_agent_name("agent-0")
+            .with_log_dir(tmp.path().join("logs"))
+            .with_shutdown_timeout(shutdown_timeout);
+        let config = builder.build().expect("Failed to build HolochainConfig");
+        HolochainRunner::create(&config).expect("Failed to create runner")
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shutdown_lets_conductor_exit_cleanly() {
+        let tmp = tempdir().unwrap();
+        let mut runner = fake_conductor_runner(
+            &tmp,
+            "trap 'exit 0' TERM",
+            "while true; do sleep 0.1; done",
+            Duration::from_secs(10),
+        );
+        runner.run().await.unwrap();
+
+        let started = Instant::now();
+        let exit_status = runner.shutdown().await.unwrap().unwrap();
+
+        assert_eq!(Some(0), exit_status.code());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shutdown_kills_conductor_after_timeout() {
+        use std::os::unix::process::ExitStatusExt as _;
+
+        let tmp = tempdir().unwrap();
+        let mut runner = fake_conductor_runner(
+            &tmp,
+            "trap '' TERM",
+            "while true; do sleep 0.1; done",
+            Duration::from_millis(500),
+        );
+        runner.run().await.unwrap();
+
+        let exit_status = runner.shutdown().await.unwrap().unwrap();
+
+        assert_eq!(
+            Some(nix::sys::signal::Signal::SIGKILL as i32),
+            exit_status.signal()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn drop_does_not_block_other_tasks_on_worker() {
+        let tmp = tempdir().unwrap();
+        let mut runner = fake_conductor_runner(
+            &tmp,
+            "trap '' TERM",
+            "while true; do sleep 0.1; done",
+            Duration::from_millis(500),
+        );
+        runner.run().await.unwrap();
+
+        let ticks = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    ticks.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+            }
+        });
+
+        // Drop the runner on the only worker thread, which waits for the conductor to be killed
+        let ticks_before_drop = ticks.load(std::sync::atomic::Ordering::SeqCst);
+        let ticks_during_drop = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                drop(runner);
+                ticks.load(std::sync::atomic::Ordering::SeqCst)
+            }
+        })
+        .await
+        .unwrap();
+        ticker.abort();
+
+        assert!(ticks_during_drop > ticks_before_drop + 5);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn conductor_crash_is_detected() {
+        let tmp = tempdir().unwrap();
+        let mut runner = fake_conductor_runner(&tmp, "", "exit 3", Duration::from_secs(10));
+        runner.run().await.unwrap();
+
+        let started = Instant::now();
+        let exit_status = loop {
+            if let Some(exit_status) = runner.unexpected_exit() {
+                break exit_status;
+            }
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "Conductor did not exit"
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+
+        assert_eq!(Some(3), exit_status.code());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn conductor_crash_is_detected_while_watching() {
+        use crate::conductor_pool::{ConductorPool, watch_for_unexpected_exit};
+
+        let tmp = tempdir().unwrap();
+        let mut runner =
+            fake_conductor_runner(&tmp, "", "sleep 0.5; exit 3", Duration::from_secs(10));
+        runner.run().await.unwrap();
+        let pool = ConductorPool::default();
+        let conductor = pool.get_or_start("agent-0", false, || Ok(runner)).unwrap();
+
+        let (exit_tx, exit_rx) = tokio::sync::oneshot::channel();
+        let watcher = tokio::spawn(watch_for_unexpected_exit(
+            Arc::downgrade(&conductor),
+            move |exit_status| exit_tx.send(exit_status).unwrap(),
+        ));
+
+        let exit_status = tokio::time::timeout(Duration::from_secs(5), exit_rx)
+            .await
+            .expect("Conductor exit was not detected")
+            .unwrap();
+        assert_eq!(Some(3), exit_status.code());
+        watcher.await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn watching_stops_when_conductor_is_dropped() {
+        use crate::conductor_pool::{ConductorPool, watch_for_unexpected_exit};
+
+        let tmp = tempdir().unwrap();
+        let mut runner = fake_conductor_runner(
+            &tmp,
+            "trap 'exit 0' TERM",
+            "while true; do sleep 0.1; done",
+            Duration::from_secs(10),
+        );
+        runner.run().await.unwrap();
+        let pool = ConductorPool::default();
+        let conductor = pool.get_or_start("agent-0", false, || Ok(runner)).unwrap();
+
+        let watcher = tokio::spawn(watch_for_unexpected_exit(
+            Arc::downgrade(&conductor),
+            |exit_status| panic!("Conductor shut down by the agent reported as {exit_status}"),
+        ));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        drop(conductor);
+
+        tokio::time::timeout(Duration::from_secs(5), watcher)
+            .await
+            .expect("Watcher did not stop")
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn conductor_output_is_written_to_log_files() {
+        let tmp = tempdir().unwrap();
+        let mut runner = fake_conductor_runner(
+            &tmp,
+            "trap 'exit 0' TERM",
+            "echo 'still running'\necho ' ERROR something failed' >&2\nwhile true; do sleep 0.1; done",
+            Duration::from_secs(10),
+        );
+        runner.run().await.unwrap();
+
+        let stderr_log = tmp.path().join("logs").join("agent-0.stderr.log");
+        let started = Instant::now();
+        while fs::read_to_string(&stderr_log)
+            .unwrap_or_default()
+            .is_empty()
+        {
+            assert!(started.elapsed() < Duration::from_secs(5), "Nothing logged");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        runner.shutdown().await.unwrap();
+
+        assert_eq!(
+            " ERROR something failed\n",
+            fs::read_to_string(stderr_log).unwrap()
+        );
+        let stdout_log =
+            fs::read_to_string(tmp.path().join("logs").join("agent-0.stdout.log")).unwrap();
+        assert!(stdout_log.starts_with("Conductor ready.\n"), "{stdout_log}");
+    }
 }
@@ -2,6 +2,10 @@ mod common;
 
 mod bin_path;
 mod build_info;
+mod conductor_config;
+mod conductor_log;
+mod conductor_pool;
+mod conductor_resources;
 mod context;
 mod holochain_runner;
 mod macros;
@@ -14,6 +18,9 @@ pub mod prelude {
     pub use crate::common::*;
 
     pub use crate::bin_path::WT_HOLOCHAIN_PATH_ENV;
+    pub use crate::conductor_config::WT_CONDUCTOR_CONFIG_ENV;
+    pub use crate::conductor_log::{CONDUCTOR_LOG_LINES_COUNTER, LogPattern};
+    pub use crate::conductor_pool::WT_AGENTS_PER_CONDUCTOR_ENV;
     pub use crate::context::HolochainAgentContext;
     pub use crate::holochain_runner::HolochainRunner;
     pub use crate::runner_context::HolochainRunnerContext;
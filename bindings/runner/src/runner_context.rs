@@ -1,7 +1,24 @@
 use wind_tunnel_runner::prelude::UserValuesConstraint;
 
+use crate::conductor_pool::ConductorPool;
+
 /// Holochain-specific context values for the [wind_tunnel_runner::prelude::RunnerContext].
 #[derive(Default, Debug)]
-pub struct HolochainRunnerContext {}
+pub struct HolochainRunnerContext {
+    /// The conductors that are run locally for the agents, and the agents assigned to each.
+    pub(crate) conductors: ConductorPool,
+}
+
+impl HolochainRunnerContext {
+    /// The name of the locally run conductor that an agent was assigned to by
+    /// [`run_holochain_conductor`](crate::prelude::run_holochain_conductor), if any.
+    ///
+    /// Agents that share a conductor, see
+    /// [`WT_AGENTS_PER_CONDUCTOR_ENV`](crate::prelude::WT_AGENTS_PER_CONDUCTOR_ENV), are assigned to
+    /// the same conductor name. Otherwise each conductor is named after its agent.
+    pub fn conductor_for_agent(&self, agent_name: &str) -> Option<String> {
+        self.conductors.conductor_for_agent(agent_name)
+    }
+}
 
 impl UserValuesConstraint for HolochainRunnerContext {}
@@ -277,9 +277,11 @@
               runtimeInputs = [
                 lp-tool
                 pkgs.gnused
+                pkgs.gzip
                 pkgs.influxdb2-cli
                 pkgs.jq
                 pkgs.yq
+                pkgs.zstd
               ];
               text = ''
                 set -euo pipefail
@@ -290,7 +292,7 @@
                 use_influx
                 import_lp_metrics
 
-                rm -f ./telegraf/metrics/*.influx 2>/dev/null || true
+                rm -f ./telegraf/metrics/*.influx ./telegraf/metrics/*.influx.gz ./telegraf/metrics/*.influx.zst ./telegraf/metrics/*.manifest.json 2>/dev/null || true
               '';
             };
             check-scripts = pkgs.writeShellApplication {
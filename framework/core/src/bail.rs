@@ -8,6 +8,13 @@ pub struct AgentBailError {
     msg: String,
 }
 
+impl AgentBailError {
+    /// Create an error with a message that explains why the agent is bailing.
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self { msg: msg.into() }
+    }
+}
+
 impl Default for AgentBailError {
     fn default() -> Self {
         Self {
@@ -1,38 +1,76 @@
 use crate::report::ReportCollector;
-use parking_lot::RwLock;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::stats::OperationStats;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use wind_tunnel_core::prelude::DelegatedShutdownListener;
+use std::time::{Duration, SystemTime};
+use warmup::WarmupWindow;
+use wind_tunnel_core::prelude::ShutdownHandle;
 
+mod metrics;
+mod operation_error;
 mod report;
+mod stats;
+mod transaction;
+mod warmup;
+
+pub use metrics::{Counter, CounterTotal, Gauge, Histogram};
+pub use operation_error::{ClassifiedError, MAX_ERROR_MESSAGE_LEN, OperationError};
+pub use transaction::{TRANSACTION_OPERATION_PREFIX, Transaction};
+
+/// The attribute, or tag, used to record which phase of a run an operation or metric belongs to.
+pub const PHASE_ATTR: &str = "phase";
+
+/// The value of [PHASE_ATTR] for operations and metrics recorded during the warmup period.
+pub const WARMUP_PHASE: &str = "warmup";
+
+/// The tag used to record the kind of error for a failed operation.
+pub const ERROR_KIND_ATTR: &str = "error_kind";
+
+/// The field used to record the offset of the reporting machine's clock, see
+/// [Reporter::set_clock_offset].
+pub const CLOCK_OFFSET_FIELD: &str = "clock_offset_ns";
 
 pub mod prelude {
-    pub use crate::report::{ReportCollector, ReportMetric};
+    pub use crate::report::{
+        BackpressurePolicy, FileCompression, InfluxClientConfig, InfluxFileConfig, OtlpConfig,
+        OtlpProtocol, PipelineStats, ReportCollector, ReportMetric,
+    };
+    pub use crate::stats::OperationStats;
+    pub use crate::{CLOCK_OFFSET_FIELD, ERROR_KIND_ATTR, PHASE_ATTR, WARMUP_PHASE};
+    pub use crate::{ClassifiedError, OperationError};
+    pub use crate::{Counter, Gauge, Histogram};
     pub use crate::{OperationRecord, ReportConfig, Reporter, report_operation};
+    pub use crate::{TRANSACTION_OPERATION_PREFIX, Transaction};
 }
 
 #[derive(Debug)]
 pub struct ReportConfig {
-    pub dir: Option<PathBuf>,
     pub run_id: String,
     pub scenario_name: String,
     pub enable_in_memory: bool,
     pub enable_in_memory_with_custom_metrics: bool,
     pub enable_influx_client: bool,
+    pub influx_client: Option<report::InfluxClientConfig>,
     pub enable_influx_file: bool,
+    pub influx_file: Option<report::InfluxFileConfig>,
+    pub otlp: Option<report::OtlpConfig>,
+    pub enable_operation_stats: bool,
 }
 
 impl ReportConfig {
     pub fn new(run_id: String, scenario_name: String) -> Self {
         ReportConfig {
-            dir: None,
             run_id,
             scenario_name,
             enable_in_memory: false,
             enable_in_memory_with_custom_metrics: false,
             enable_influx_client: false,
+            influx_client: None,
             enable_influx_file: false,
+            influx_file: None,
+            otlp: None,
+            enable_operation_stats: false,
         }
     }
 
@@ -46,21 +84,35 @@ impl ReportConfig {
         self
     }
 
-    pub fn enable_influx_client(mut self) -> Self {
+    pub fn enable_influx_client(mut self, config: report::InfluxClientConfig) -> Self {
+        self.influx_client = Some(config);
         self.enable_influx_client = true;
         self
     }
 
-    pub fn enable_influx_file(mut self, dir: PathBuf) -> Self {
-        self.dir = Some(dir);
+    pub fn enable_influx_file(mut self, config: report::InfluxFileConfig) -> Self {
+        self.influx_file = Some(config);
         self.enable_influx_file = true;
         self
     }
 
+    /// Export metrics to an OpenTelemetry collector using OTLP.
+    pub fn enable_otlp(mut self, config: report::OtlpConfig) -> Self {
+        self.otlp = Some(config);
+        self
+    }
+
+    /// Keep [OperationStats] for each operation ID in-process, so that they can be read with
+    /// [Reporter::operation_stats] at the end of the run.
+    pub fn enable_operation_stats(mut self) -> Self {
+        self.enable_operation_stats = true;
+        self
+    }
+
     pub fn init_reporter(
         self,
         runtime: &tokio::runtime::Handle,
-        shutdown_listener: DelegatedShutdownListener,
+        shutdown_handle: &ShutdownHandle,
     ) -> anyhow::Result<Reporter> {
         if self.enable_influx_client && self.enable_influx_file {
             log::warn!(
@@ -68,6 +120,7 @@ impl ReportConfig {
             );
         }
 
+        let warmup = WarmupWindow::default();
         Ok(Reporter {
             inner: [
                 self.enable_in_memory.then(|| {
@@ -78,11 +131,40 @@ impl ReportConfig {
                     RwLock::new(Box::new(report::InMemoryWithCustomMetricsReporter::new())
                         as Box<dyn ReportCollector + Send + Sync>)
                 }),
+                if self.enable_influx_client {
+                    let influx_client_reporter = report::InfluxClientReportCollector::new(
+                        runtime,
+                        shutdown_handle.new_listener(),
+                        self.influx_client.unwrap(),
+                        self.run_id.clone(),
+                        self.scenario_name.clone(),
+                    );
+                    Some(RwLock::new(
+                        Box::new(influx_client_reporter) as Box<dyn ReportCollector + Send + Sync>
+                    ))
+                } else {
+                    None
+                },
+                self.otlp
+                    .map(|config| {
+                        report::OtlpReportCollector::new(
+                            runtime,
+                            config,
+                            self.run_id.clone(),
+                            self.scenario_name.clone(),
+                        )
+                        .map(|otlp_reporter| {
+                            RwLock::new(
+                                Box::new(otlp_reporter) as Box<dyn ReportCollector + Send + Sync>
+                            )
+                        })
+                    })
+                    .transpose()?,
                 if self.enable_influx_file {
                     let influx_file_reporter = report::InfluxFileReportCollector::new(
                         runtime,
-                        shutdown_listener,
-                        self.dir.unwrap(),
+                        shutdown_handle.new_listener(),
+                        self.influx_file.unwrap(),
                         self.run_id,
                         self.scenario_name,
                     );
@@ -96,22 +178,142 @@ impl ReportConfig {
             .into_iter()
             .flatten()
             .collect(),
+            warmup: warmup.clone(),
+            operation_stats: self
+                .enable_operation_stats
+                .then(|| Mutex::new(HashMap::new())),
+            operation_errors: Mutex::new(BTreeMap::new()),
+            metrics: metrics::MetricRegistry::new(warmup),
+            clock_offset_ns: RwLock::new(None),
         })
     }
 }
 
 pub struct Reporter {
     inner: Vec<RwLock<Box<dyn ReportCollector + Send + Sync>>>,
+    warmup: WarmupWindow,
+    operation_stats: Option<Mutex<HashMap<String, OperationStats>>>,
+    operation_errors: Mutex<BTreeMap<String, BTreeMap<String, u64>>>,
+    metrics: metrics::MetricRegistry,
+    clock_offset_ns: RwLock<Option<i64>>,
 }
 
 impl Reporter {
-    fn add_operation(&self, operation_record: &OperationRecord) {
+    /// Start a warmup period that lasts for `duration` from now.
+    ///
+    /// Operations that start during the warmup, and custom metrics and typed metric values recorded
+    /// during the warmup, are tagged with [PHASE_ATTR]`=`[WARMUP_PHASE], even if they are reported
+    /// after it has ended. The in-memory reporters leave them out of their summaries.
+    pub fn start_warmup(&self, duration: Duration) {
+        self.warmup.start(duration);
+    }
+
+    /// Whether the warmup period is in progress.
+    pub fn in_warmup(&self) -> bool {
+        self.warmup.in_progress()
+    }
+
+    /// The statistics for each operation ID recorded so far, excluding the warmup period.
+    ///
+    /// Empty unless operation stats were enabled with [ReportConfig::enable_operation_stats].
+    pub fn operation_stats(&self) -> HashMap<String, OperationStats> {
+        self.operation_stats
+            .as_ref()
+            .map(|stats| stats.lock().clone())
+            .unwrap_or_default()
+    }
+
+    /// The number of failed operations for each operation ID and kind of error, excluding the
+    /// warmup period.
+    pub fn operation_errors(&self) -> BTreeMap<String, BTreeMap<String, u64>> {
+        self.operation_errors.lock().clone()
+    }
+
+    /// Set the offset of this machine's clock from a reference clock, in nanoseconds.
+    ///
+    /// The offset is the amount to add to this machine's clock to get the reference time. Once it
+    /// is set, operations are reported with a `clock_offset_ns` field so that their start times can
+    /// be corrected when comparing timings across machines.
+    pub fn set_clock_offset(&self, offset_ns: i64) {
+        *self.clock_offset_ns.write() = Some(offset_ns);
+    }
+
+    /// Get the [Counter] with this name and tags, creating it if it does not exist yet.
+    ///
+    /// Typed metrics are aggregated in memory and reported as custom metrics, one per name and set
+    /// of tags, when [Reporter::flush_metrics] is called.
+    pub fn counter(&self, name: &str, tags: &[(&str, &str)]) -> Counter {
+        self.metrics.counter(name, tags)
+    }
+
+    /// Get the [Gauge] with this name and tags, creating it if it does not exist yet.
+    pub fn gauge(&self, name: &str, tags: &[(&str, &str)]) -> Gauge {
+        self.metrics.gauge(name, tags)
+    }
+
+    /// Get the [Histogram] with this name and tags, creating it if it does not exist yet.
+    pub fn histogram(&self, name: &str, tags: &[(&str, &str)]) -> Histogram {
+        self.metrics.histogram(name, tags)
+    }
+
+    /// The total of each [Counter] that has been incremented.
+    pub fn counter_totals(&self) -> Vec<CounterTotal> {
+        self.metrics.counter_totals()
+    }
+
+    /// Report the values recorded by typed metrics since the last flush.
+    ///
+    /// Metrics that have not been updated since the last flush are not reported. The runner
+    /// flushes metrics periodically and before the reporters are shut down.
+    pub fn flush_metrics(&self) {
+        // Typed metrics are already tagged with the phase that their values were recorded in.
+        for metric in self.metrics.take() {
+            self.write_custom(metric);
+        }
+    }
+
+    fn add_operation(&self, mut operation_record: OperationRecord) {
+        if let Some(offset_ns) = *self.clock_offset_ns.read() {
+            operation_record.add_field(CLOCK_OFFSET_FIELD, offset_ns);
+        }
+
+        if self.warmup.includes(operation_record.started) {
+            operation_record.add_attr(PHASE_ATTR, WARMUP_PHASE.to_string());
+        } else {
+            if let Some(stats) = &self.operation_stats {
+                stats
+                    .lock()
+                    .entry(operation_record.operation_id.clone())
+                    .or_default()
+                    .record(&operation_record);
+            }
+            if let Some(error_kind) = &operation_record.error_kind {
+                *self
+                    .operation_errors
+                    .lock()
+                    .entry(operation_record.operation_id.clone())
+                    .or_default()
+                    .entry(error_kind.clone())
+                    .or_default() += 1;
+            }
+        }
+
         for collector in &self.inner {
-            collector.write().add_operation(operation_record);
+            collector.write().add_operation(&operation_record);
         }
     }
 
     pub fn add_custom(&self, metric: report::ReportMetric) {
+        let metric = if self.in_warmup() {
+            metric.with_tag(PHASE_ATTR, WARMUP_PHASE)
+        } else {
+            metric
+        };
+
+        self.write_custom(metric);
+    }
+
+    fn write_custom(&self, metric: report::ReportMetric) {
         for collector in &self.inner {
             collector.write().add_custom(metric.clone());
         }
@@ -122,6 +324,16 @@ impl Reporter {
             collector.write().finalize();
         }
     }
+
+    /// Counts of the metrics that could not be written, across all collectors.
+    pub fn pipeline_stats(&self) -> report::PipelineStats {
+        self.inner
+            .iter()
+            .map(|collector| collector.read().pipeline_stats())
+            .fold(report::PipelineStats::default(), |total, stats| {
+                total + stats
+            })
+    }
 }
 
 impl std::fmt::Debug for Reporter {
@@ -136,21 +348,33 @@ pub struct OperationRecord {
     operation_id: String,
     /// The instant when the operation started
     started: std::time::Instant,
+    /// The wall-clock time when the operation started
+    started_at: SystemTime,
     /// Extra attributes to be reported
     attr: HashMap<String, String>,
+    /// Extra values to be reported, that vary too much to be attributes
+    fields: HashMap<String, influxive_core::DataType>,
     /// Elapsed time of the operation
     elapsed: Option<std::time::Duration>,
     /// Whether the operation failed
     is_error: bool,
+    /// The kind of error, if the operation failed
+    error_kind: Option<String>,
+    /// A description of the error, truncated to [MAX_ERROR_MESSAGE_LEN]
+    error_message: Option<String>,
 }
 
 impl std::fmt::Debug for OperationRecord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("OperationRecord")
             .field("operation_id", &self.operation_id)
+            .field("started_at", &self.started_at)
             .field("attr", &self.attr)
+            .field("fields", &self.fields)
             .field("elapsed", &self.elapsed)
             .field("is_error", &self.is_error)
+            .field("error_kind", &self.error_kind)
+            .field("error_message", &self.error_message)
             .finish()
     }
 }
@@ -160,9 +384,13 @@ impl OperationRecord {
         Self {
             operation_id,
             started: std::time::Instant::now(),
+            started_at: SystemTime::now(),
             attr: HashMap::new(),
+            fields: HashMap::new(),
             elapsed: None,
             is_error: false,
+            error_kind: None,
+            error_message: None,
         }
     }
 
@@ -170,25 +398,61 @@ impl OperationRecord {
         self.attr.insert(key.to_string(), value);
     }
 
+    /// Add a value to be reported with the operation, such as the size of a response or the number
+    /// of records returned.
+    ///
+    /// Unlike attributes, fields are not used to group operations, so they can take any value.
+    pub fn add_field(&mut self, key: &str, value: impl Into<influxive_core::DataType>) {
+        self.fields.insert(key.to_string(), value.into());
+    }
+
+    /// The wall-clock time when the operation started, by this machine's clock.
+    pub fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+
     pub fn duration(&self) -> Option<std::time::Duration> {
         self.elapsed
     }
 
+    /// The kind of error, if the operation failed.
+    pub fn error_kind(&self) -> Option<&str> {
+        self.error_kind.as_deref()
+    }
+
+    /// A description of the error, if the operation failed and the error could be described.
+    pub fn error_message(&self) -> Option<&str> {
+        self.error_message.as_deref()
+    }
+
+    /// Whether the operation was recorded during the warmup period.
+    pub fn is_warmup(&self) -> bool {
+        self.attr
+            .get(PHASE_ATTR)
+            .is_some_and(|phase| phase == WARMUP_PHASE)
+    }
+
     fn finish(&mut self) {
         self.elapsed = Some(self.started.elapsed());
     }
 
-    fn set_error(&mut self, is_error: bool) {
-        self.is_error = is_error;
+    fn set_error(&mut self, error: &impl OperationError) {
+        self.is_error = true;
+        self.error_kind = Some(error.error_kind().into_owned());
+        self.error_message = error.error_message().map(operation_error::truncate_message);
     }
 }
 
-pub fn report_operation<T, E>(
+/// Finish the operation and report it, with the kind of error and a description of the error if
+/// the operation failed.
+pub fn report_operation<T, E: OperationError>(
     reporter: Arc<Reporter>,
     mut operation_record: OperationRecord,
     response: &Result<T, E>,
 ) {
     operation_record.finish();
-    operation_record.set_error(response.is_err());
-    reporter.add_operation(&operation_record);
+    if let Err(error) = response {
+        operation_record.set_error(error);
+    }
+    reporter.add_operation(operation_record);
 }
@@ -0,0 +1,448 @@
+use crate::report::ReportMetric;
+use crate::warmup::WarmupWindow;
+use crate::{PHASE_ATTR, WARMUP_PHASE};
+use hdrhistogram::Histogram as HdrHistogram;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Histogram values are kept in millionths, so that fractional values such as durations in
+/// seconds can be recorded.
+const HISTOGRAM_SCALE: f64 = 1_000_000.0;
+
+/// The percentiles reported for a [Histogram], with the field that each is reported as.
+const HISTOGRAM_PERCENTILES: [(&str, f64); 3] = [("p50", 50.0), ("p95", 95.0), ("p99", 99.0)];
+
+/// Create an empty HDR histogram that keeps values to 2 significant figures, and grows to fit the
+/// largest value recorded.
+pub(crate) fn new_hdr_histogram() -> HdrHistogram<u64> {
+    HdrHistogram::new(2).expect("2 significant figures is a valid precision")
+}
+
+/// Record a value in an HDR histogram created with [new_hdr_histogram].
+pub(crate) fn record_hdr_value(histogram: &mut HdrHistogram<u64>, value: u64) {
+    // Recording only fails for values too large for the histogram to grow to fit
+    if let Err(e) = histogram.record(value) {
+        log::warn!("Failed to record {value} in histogram: {e}");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MetricKey {
+    kind: MetricKind,
+    name: String,
+    tags: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+enum AggregateState {
+    Counter { total: u64, count: u64 },
+    Gauge { value: f64, updated: bool },
+    Histogram(HdrHistogram<u64>),
+}
+
+/// The values recorded for one metric, with one set of tags, since it was last reported.
+#[derive(Debug)]
+struct Aggregate {
+    name: String,
+    tags: Vec<(String, String)>,
+    warmup: WarmupWindow,
+    values: Mutex<AggregateValues>,
+}
+
+#[derive(Debug)]
+struct AggregateValues {
+    state: AggregateState,
+    /// Whether the values in `state` were recorded during the warmup
+    in_warmup: bool,
+    /// Values from the warmup that were set aside when the first value after the warmup was
+    /// recorded, waiting to be reported
+    finished: Option<ReportMetric>,
+}
+
+impl Aggregate {
+    /// Record a value, setting aside the values recorded so far if the warmup has ended since.
+    fn update(&self, record: impl FnOnce(&mut AggregateState)) {
+        let in_warmup = self.warmup.in_progress();
+        let mut values = self.values.lock();
+        if values.in_warmup != in_warmup {
+            let was_warmup = std::mem::replace(&mut values.in_warmup, in_warmup);
+            if let Some(metric) = self.report(&mut values.state, was_warmup) {
+                values.finished = Some(metric);
+            }
+            // Counter totals start again after the warmup, so that they exclude it
+            if let AggregateState::Counter { total, .. } = &mut values.state {
+                *total = 0;
+            }
+        }
+        record(&mut values.state);
+    }
+
+    /// Take the values recorded since the last report, one metric per phase of the run that values
+    /// were recorded in.
+    fn take(&self) -> Vec<ReportMetric> {
+        let mut values = self.values.lock();
+        let in_warmup = values.in_warmup;
+        let mut metrics = values.finished.take().into_iter().collect::<Vec<_>>();
+        metrics.extend(self.report(&mut values.state, in_warmup));
+        metrics
+    }
+
+    /// Report the values in `state`, if any were recorded, and reset them.
+    fn report(&self, state: &mut AggregateState, in_warmup: bool) -> Option<ReportMetric> {
+        let metric = self
+            .tags
+            .iter()
+            .fold(ReportMetric::new(&self.name), |metric, (key, value)| {
+                metric.with_tag(key.clone(), value.clone())
+            });
+        let metric = if in_warmup {
+            metric.with_tag(PHASE_ATTR, WARMUP_PHASE)
+        } else {
+            metric
+        };
+
+        match state {
+            AggregateState::Counter { total, count } => {
+                if *count == 0 {
+                    return None;
+                }
+                let metric = metric
+                    .with_field("count", std::mem::take(count))
+                    .with_field("total", *total);
+                Some(metric)
+            }
+            AggregateState::Gauge { value, updated } => {
+                if !std::mem::take(updated) {
+                    return None;
+                }
+                Some(metric.with_field("value", *value))
+            }
+            AggregateState::Histogram(histogram) => {
+                if histogram.is_empty() {
+                    return None;
+                }
+                let scaled = |value: u64| value as f64 / HISTOGRAM_SCALE;
+                let count = histogram.len();
+                let mean = histogram.mean() / HISTOGRAM_SCALE;
+                let mut metric = metric
+                    .with_field("count", count)
+                    .with_field("sum", mean * count as f64)
+                    .with_field("mean", mean)
+                    .with_field("min", scaled(histogram.min()))
+                    .with_field("max", scaled(histogram.max()));
+                for (field, percentile) in HISTOGRAM_PERCENTILES {
+                    metric =
+                        metric.with_field(field, scaled(histogram.value_at_percentile(percentile)));
+                }
+                histogram.reset();
+                Some(metric)
+            }
+        }
+    }
+}
+
+/// The typed metrics that have been created for a reporter, which are aggregated in memory and
+/// reported together when they are flushed.
+#[derive(Debug, Default)]
+pub(crate) struct MetricRegistry {
+    aggregates: Mutex<HashMap<MetricKey, Arc<Aggregate>>>,
+    warmup: WarmupWindow,
+}
+
+impl MetricRegistry {
+    pub(crate) fn new(warmup: WarmupWindow) -> Self {
+        Self {
+            aggregates: Mutex::default(),
+            warmup,
+        }
+    }
+
+    fn get_or_create(
+        &self,
+        kind: MetricKind,
+        name: &str,
+        tags: &[(&str, &str)],
+        state: impl FnOnce() -> AggregateState,
+    ) -> Arc<Aggregate> {
+        let mut tags = tags
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        tags.sort();
+
+        let key = MetricKey {
+            kind,
+            name: name.to_string(),
+            tags,
+        };
+        self.aggregates
+            .lock()
+            .entry(key)
+            .or_insert_with_key(|key| {
+                Arc::new(Aggregate {
+                    name: key.name.clone(),
+                    tags: key.tags.clone(),
+                    warmup: self.warmup.clone(),
+                    values: Mutex::new(AggregateValues {
+                        state: state(),
+                        in_warmup: false,
+                        finished: None,
+                    }),
+                })
+            })
+            .clone()
+    }
+
+    pub(crate) fn counter(&self, name: &str, tags: &[(&str, &str)]) -> Counter {
+        Counter(self.get_or_create(MetricKind::Counter, name, tags, || {
+            AggregateState::Counter { total: 0, count: 0 }
+        }))
+    }
+
+    pub(crate) fn gauge(&self, name: &str, tags: &[(&str, &str)]) -> Gauge {
+        Gauge(
+            self.get_or_create(MetricKind::Gauge, name, tags, || AggregateState::Gauge {
+                value: 0.0,
+                updated: false,
+            }),
+        )
+    }
+
+    pub(crate) fn histogram(&self, name: &str, tags: &[(&str, &str)]) -> Histogram {
+        Histogram(self.get_or_create(MetricKind::Histogram, name, tags, || {
+            AggregateState::Histogram(new_hdr_histogram())
+        }))
+    }
+
+    /// The total of each counter that has been incremented since the warmup.
+    pub(crate) fn counter_totals(&self) -> Vec<CounterTotal> {
+        let aggregates = self.aggregates.lock().values().cloned().collect::<Vec<_>>();
+        let mut totals = aggregates
+            .iter()
+            .filter_map(|aggregate| match &*aggregate.values.lock() {
+                AggregateValues {
+                    state: AggregateState::Counter { total, .. },
+                    in_warmup: false,
+                    ..
+                } if *total > 0 => Some(CounterTotal {
+                    name: aggregate.name.clone(),
+                    tags: aggregate.tags.clone(),
+                    total: *total,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        totals.sort();
+        totals
+    }
+
+    /// Take the values recorded since the last flush, as one custom metric per metric and set of
+    /// tags that has been updated.
+    pub(crate) fn take(&self) -> Vec<ReportMetric> {
+        let aggregates = self.aggregates.lock().values().cloned().collect::<Vec<_>>();
+        aggregates
+            .iter()
+            .flat_map(|aggregate| aggregate.take())
+            .collect()
+    }
+}
+
+/// A count of events, such as messages sent.
+///
+/// Each report has a `count` field with the increase since the last report and a `total` field
+/// with the count since the metric was created. The total starts again from zero when the warmup
+/// ends, so that reports after the warmup only count what happened after it.
+#[derive(Debug, Clone)]
+pub struct Counter(Arc<Aggregate>);
+
+impl Counter {
+    /// Increase the count by one.
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    /// Increase the count by `value`.
+    pub fn add(&self, value: u64) {
+        self.0.update(|state| {
+            if let AggregateState::Counter { total, count } = state {
+                *total += value;
+                *count += value;
+            }
+        });
+    }
+}
+
+/// The total of a [Counter] since it was created, excluding the warmup.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CounterTotal {
+    pub name: String,
+    /// The tags of the counter, sorted by key
+    pub tags: Vec<(String, String)>,
+    pub total: u64,
+}
+
+/// A value that goes up and down, such as the number of open connections.
+///
+/// Each report has a `value` field with the last value that was set.
+#[derive(Debug, Clone)]
+pub struct Gauge(Arc<Aggregate>);
+
+impl Gauge {
+    pub fn set(&self, new_value: impl Into<f64>) {
+        let new_value = new_value.into();
+        self.0.update(|state| {
+            if let AggregateState::Gauge { value, updated } = state {
+                *value = new_value;
+                *updated = true;
+            }
+        });
+    }
+}
+
+/// The distribution of a value, such as sync lag in seconds.
+///
+/// Each report has `count`, `sum`, `mean`, `min`, `max`, `p50`, `p95` and `p99` fields for the
+/// values recorded since the last report. Values are kept to 2 significant figures and to a
+/// millionth, and negative values are recorded as zero.
+#[derive(Debug, Clone)]
+pub struct Histogram(Arc<Aggregate>);
+
+impl Histogram {
+    pub fn record(&self, value: impl Into<f64>) {
+        let scaled = (value.into() * HISTOGRAM_SCALE).round().max(0.0) as u64;
+        self.0.update(|state| {
+            if let AggregateState::Histogram(histogram) = state {
+                record_hdr_value(histogram, scaled);
+            }
+        });
+    }
+
+    /// Record a duration, in seconds.
+    pub fn record_duration(&self, duration: std::time::Duration) {
+        self.record(duration.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(metric: &ReportMetric, name: &str) -> Option<influxive_core::DataType> {
+        metric
+            .fields
+            .iter()
+            .find(|(key, _)| key.clone().into_string() == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    #[test]
+    fn metrics_with_the_same_name_and_tags_are_aggregated() {
+        let registry = MetricRegistry::default();
+        registry.counter("sent", &[("agent", "a")]).inc();
+        registry.counter("sent", &[("agent", "a")]).add(2);
+        registry.counter("sent", &[("agent", "b")]).inc();
+
+        let metrics = registry.take();
+        assert_eq!(2, metrics.len());
+        let totals = metrics
+            .iter()
+            .filter_map(|metric| match field(metric, "total") {
+                Some(influxive_core::DataType::U64(total)) => Some(total),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(totals.contains(&3));
+        assert!(totals.contains(&1));
+
+        // Nothing has changed since the last flush
+        assert!(registry.take().is_empty());
+    }
+
+    #[test]
+    fn counter_totals_include_every_flush() {
+        let registry = MetricRegistry::default();
+        registry.counter("sent", &[("agent", "a")]).add(2);
+        registry.take();
+        registry.counter("sent", &[("agent", "a")]).inc();
+        registry.counter("unused", &[]);
+        registry.gauge("open", &[]).set(3);
+
+        assert_eq!(
+            vec![CounterTotal {
+                name: "sent".to_string(),
+                tags: vec![("agent".to_string(), "a".to_string())],
+                total: 3
+            }],
+            registry.counter_totals()
+        );
+    }
+
+    #[test]
+    fn values_recorded_during_the_warmup_are_reported_separately() {
+        let warmup = WarmupWindow::default();
+        let registry = MetricRegistry::new(warmup.clone());
+        warmup.start(std::time::Duration::from_millis(100));
+        let counter = registry.counter("sent", &[]);
+        counter.add(2);
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        counter.inc();
+
+        // Both are flushed after the warmup, but the first values were recorded during it
+        let metrics = registry.take();
+        assert_eq!(2, metrics.len());
+        assert!(metrics[0].is_warmup());
+        assert!(matches!(
+            field(&metrics[0], "count"),
+            Some(influxive_core::DataType::U64(2))
+        ));
+        assert!(!metrics[1].is_warmup());
+        assert!(matches!(
+            field(&metrics[1], "count"),
+            Some(influxive_core::DataType::U64(1))
+        ));
+        assert!(matches!(
+            field(&metrics[1], "total"),
+            Some(influxive_core::DataType::U64(1))
+        ));
+        assert_eq!(
+            vec![CounterTotal {
+                name: "sent".to_string(),
+                tags: Vec::new(),
+                total: 1
+            }],
+            registry.counter_totals()
+        );
+    }
+
+    #[test]
+    fn histograms_report_their_distribution() {
+        let registry = MetricRegistry::default();
+        let histogram = registry.histogram("lag", &[]);
+        for value in 1..=100 {
+            histogram.record(value as f64 / 100.0);
+        }
+
+        let metrics = registry.take();
+        assert_eq!(1, metrics.len());
+        assert!(matches!(
+            field(&metrics[0], "count"),
+            Some(influxive_core::DataType::U64(100))
+        ));
+        let Some(influxive_core::DataType::F64(p50)) = field(&metrics[0], "p50") else {
+            panic!("Missing p50");
+        };
+        assert!((p50 - 0.5).abs() < 0.01, "p50 was {p50}");
+        let Some(influxive_core::DataType::F64(max)) = field(&metrics[0], "max") else {
+            panic!("Missing max");
+        };
+        assert!((max - 1.0).abs() < 0.01, "max was {max}");
+    }
+}
@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+use wind_tunnel_core::prelude::AgentBailError;
+
+/// The longest error message, in characters, that is reported for a failed operation.
+pub const MAX_ERROR_MESSAGE_LEN: usize = 256;
+
+/// The error from a failed operation, described so that errors can be broken down by cause.
+pub trait OperationError {
+    /// The kind of error, such as `timeout` or `ribosome`.
+    ///
+    /// Kinds are reported as tags, so they should come from a small, fixed set of values rather
+    /// than include details of the error.
+    fn error_kind(&self) -> Cow<'static, str>;
+
+    /// A description of the error, which is truncated to [MAX_ERROR_MESSAGE_LEN] when reported.
+    fn error_message(&self) -> Option<String> {
+        None
+    }
+}
+
+/// An error with a known kind, for errors that cannot be classified by their type.
+///
+/// Bindings can return this, or add it to the chain of an [anyhow::Error], so that the kind is
+/// reported for failed operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassifiedError {
+    kind: Cow<'static, str>,
+    message: String,
+}
+
+impl ClassifiedError {
+    pub fn new(kind: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+}
+
+impl std::fmt::Display for ClassifiedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ClassifiedError {}
+
+impl OperationError for ClassifiedError {
+    fn error_kind(&self) -> Cow<'static, str> {
+        self.kind.clone()
+    }
+
+    fn error_message(&self) -> Option<String> {
+        Some(self.message.clone())
+    }
+}
+
+/// Errors are classified by the first recognised error in the chain, as the kind of a
+/// [ClassifiedError], or as `bail`, `timeout`, `io` or `other`.
+impl OperationError for anyhow::Error {
+    fn error_kind(&self) -> Cow<'static, str> {
+        for cause in self.chain() {
+            if let Some(classified) = cause.downcast_ref::<ClassifiedError>() {
+                return classified.error_kind();
+            }
+            if cause.is::<AgentBailError>() {
+                return "bail".into();
+            }
+            if cause.is::<tokio::time::error::Elapsed>() {
+                return "timeout".into();
+            }
+            if let Some(io_error) = cause.downcast_ref::<std::io::Error>() {
+                return io_error.error_kind();
+            }
+        }
+
+        "other".into()
+    }
+
+    fn error_message(&self) -> Option<String> {
+        Some(format!("{self:#}"))
+    }
+}
+
+impl OperationError for std::io::Error {
+    fn error_kind(&self) -> Cow<'static, str> {
+        match self.kind() {
+            std::io::ErrorKind::TimedOut => "timeout".into(),
+            _ => "io".into(),
+        }
+    }
+
+    fn error_message(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl OperationError for tokio::time::error::Elapsed {
+    fn error_kind(&self) -> Cow<'static, str> {
+        "timeout".into()
+    }
+}
+
+/// For operations that do not describe their errors.
+impl OperationError for () {
+    fn error_kind(&self) -> Cow<'static, str> {
+        "other".into()
+    }
+}
+
+/// Truncate an error message to at most [MAX_ERROR_MESSAGE_LEN] characters.
+pub(crate) fn truncate_message(mut message: String) -> String {
+    if let Some((index, _)) = message.char_indices().nth(MAX_ERROR_MESSAGE_LEN) {
+        message.truncate(index);
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anyhow_errors_are_classified_by_chain() {
+        let classified = anyhow::Error::new(ClassifiedError::new("ribosome", "Ribosome error"))
+            .context("Failed to call zome");
+        assert_eq!("ribosome", classified.error_kind());
+        assert_eq!(
+            Some("Failed to call zome: Ribosome error".to_string()),
+            classified.error_message()
+        );
+
+        let bail = anyhow::Error::new(AgentBailError::default());
+        assert_eq!("bail", bail.error_kind());
+
+        let timeout = anyhow::Error::new(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "timed out",
+        ));
+        assert_eq!("timeout", timeout.error_kind());
+
+        assert_eq!("other", anyhow::anyhow!("Something failed").error_kind());
+    }
+
+    #[test]
+    fn long_messages_are_truncated() {
+        assert_eq!("short", truncate_message("short".to_string()));
+        assert_eq!(
+            MAX_ERROR_MESSAGE_LEN,
+            truncate_message("é".repeat(1000)).chars().count()
+        );
+    }
+}
@@ -1,7 +1,11 @@
 mod in_memory_reporter;
 mod in_memory_with_custom_metrics_reporter;
+mod influx_client_reporter;
 mod influx_file_reporter;
+mod influx_file_segments;
 mod influx_reporter_base;
+mod metrics_queue;
+mod otlp_reporter;
 
 use crate::OperationRecord;
 use influxive_core::{Metric, StringType};
@@ -9,12 +13,17 @@ use std::ops::Deref;
 
 pub use in_memory_reporter::InMemoryReporter;
 pub use in_memory_with_custom_metrics_reporter::InMemoryWithCustomMetricsReporter;
-pub use influx_file_reporter::InfluxFileReportCollector;
+pub use influx_client_reporter::{InfluxClientConfig, InfluxClientReportCollector};
+pub use influx_file_reporter::{InfluxFileConfig, InfluxFileReportCollector};
+pub use influx_file_segments::FileCompression;
+pub use metrics_queue::{BackpressurePolicy, PipelineStats};
+pub use otlp_reporter::{OtlpConfig, OtlpProtocol, OtlpReportCollector};
 
 /// A simple, opinionated, newtype for the influxive_core::Metric type.
 ///
 /// The reported timestamp for the metric will be the current time when the metric is created.
 /// The name you choose will be transformed into `ws.instruments.custom.<name>`.
+#[derive(Debug)]
 pub struct ReportMetric(Metric);
 
 impl ReportMetric {
@@ -43,6 +52,17 @@ impl ReportMetric {
         self
     }
 
+    /// Whether the metric was recorded during the warmup period.
+    pub fn is_warmup(&self) -> bool {
+        self.tags.iter().any(|(key, value)| match value {
+            influxive_core::DataType::String(value) => {
+                key.clone().into_string() == crate::PHASE_ATTR
+                    && value.clone().into_string() == crate::WARMUP_PHASE
+            }
+            _ => false,
+        })
+    }
+
     pub(crate) fn into_inner(self) -> Metric {
         self.0
     }
@@ -77,4 +97,9 @@ pub trait ReportCollector {
     fn add_custom(&mut self, metric: ReportMetric);
 
     fn finalize(&self);
+
+    /// Counts of the metrics that this collector could not write.
+    fn pipeline_stats(&self) -> PipelineStats {
+        PipelineStats::default()
+    }
 }
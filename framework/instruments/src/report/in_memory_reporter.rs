@@ -3,71 +3,55 @@ mod operations_table;
 use crate::OperationRecord;
 use crate::report::ReportCollector;
 use crate::report::in_memory_reporter::operations_table::OperationRow;
-use std::collections::HashMap;
+use crate::stats::OperationStats;
+use std::collections::BTreeMap;
+use std::time::Duration;
 use tabled::Table;
 use tabled::settings::Style;
 
-/// A very basic reporter that is useful while developing scenarios. It keeps all of the operations
-/// in memory and prints a summary of the operations at the end of the run.
+/// A very basic reporter that is useful while developing scenarios. It keeps statistics for each
+/// operation ID in memory and prints a summary of the operations at the end of the run.
+///
+/// The summary includes latency percentiles, throughput and error counts by kind of error. Latencies
+/// are kept in histograms, so memory use stays constant however long the scenario runs.
+///
+/// Operations recorded during the warmup period are not included in the summary.
 pub struct InMemoryReporter {
-    operation_records: Vec<OperationRecord>,
+    operation_stats: BTreeMap<String, OperationStats>,
 }
 
 impl InMemoryReporter {
     pub fn new() -> Self {
         Self {
-            operation_records: Vec::new(),
+            operation_stats: BTreeMap::new(),
         }
     }
 
     pub(crate) fn print_summary_of_operations(&self) {
         println!("\nSummary of operations");
+        let as_ms = |duration: Option<Duration>| duration.map(|d| d.as_secs_f64() * 1000.0);
         let rows = self
-            .operation_records
+            .operation_stats
             .iter()
-            .fold(HashMap::new(), |mut acc, record| {
-                match acc.entry(record.operation_id.clone()) {
-                    std::collections::hash_map::Entry::Vacant(entry) => {
-                        entry.insert(vec![record.clone()]);
-                    }
-                    std::collections::hash_map::Entry::Occupied(mut entry) => {
-                        entry.get_mut().push(record.clone());
-                    }
-                }
-                acc
-            })
-            .into_iter()
-            .map(|(operation_id, operations)| {
-                let total_operations = operations.len();
-                let total_duration_micro = operations
+            .map(|(operation_id, stats)| OperationRow {
+                operation_id: operation_id.clone(),
+                total_operations: stats.count(),
+                errors: stats.error_count(),
+                error_kinds: stats
+                    .errors_by_kind()
                     .iter()
-                    .map(|record| record.duration().unwrap().as_micros())
-                    .sum::<u128>();
-
-                OperationRow {
-                    operation_id,
-                    total_operations,
-                    total_duration_ms: total_duration_micro as f64 / 1000.0,
-                    avg_time_ms: (total_duration_micro as f64 / total_operations as f64) / 1000.0,
-                    min_time_ms: operations
-                        .iter()
-                        .filter(|op| !op.is_error)
-                        .min_by(|a, b| a.duration().unwrap().cmp(&b.duration().unwrap()))
-                        .unwrap()
-                        .elapsed
-                        .unwrap()
-                        .as_micros() as f64
-                        / 1000.0,
-                    max_time_ms: operations
-                        .iter()
-                        .filter(|op| !op.is_error)
-                        .max_by(|a, b| a.duration().unwrap().cmp(&b.duration().unwrap()))
-                        .unwrap()
-                        .elapsed
-                        .unwrap()
-                        .as_micros() as f64
-                        / 1000.0,
-                }
+                    .map(|(kind, count)| format!("{kind}: {count}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                throughput_per_s: stats.throughput(),
+                avg_time_ms: as_ms(stats.mean()),
+                min_time_ms: as_ms(stats.min()),
+                p50_ms: as_ms(stats.percentile(50.0)),
+                p90_ms: as_ms(stats.percentile(90.0)),
+                p95_ms: as_ms(stats.percentile(95.0)),
+                p99_ms: as_ms(stats.percentile(99.0)),
+                p99_9_ms: as_ms(stats.percentile(99.9)),
+                max_time_ms: as_ms(stats.max()),
             })
             .collect::<Vec<_>>();
 
@@ -80,7 +64,14 @@ impl InMemoryReporter {
 
 impl ReportCollector for InMemoryReporter {
     fn add_operation(&mut self, operation_record: &OperationRecord) {
-        self.operation_records.push(operation_record.clone());
+        if operation_record.is_warmup() {
+            return;
+        }
+
+        self.operation_stats
+            .entry(operation_record.operation_id.clone())
+            .or_default()
+            .record(operation_record);
     }
 
     fn add_custom(&mut self, _metric: crate::report::ReportMetric) {
@@ -3,17 +3,34 @@ use tabled::Tabled;
 #[derive(Tabled)]
 pub struct OperationRow {
     pub operation_id: String,
-    #[tabled(display = "float2")]
-    pub avg_time_ms: f64,
-    #[tabled(display = "float2")]
-    pub min_time_ms: f64,
-    #[tabled(display = "float2")]
-    pub max_time_ms: f64,
-    pub total_operations: usize,
-    #[tabled(display = "float2")]
-    pub total_duration_ms: f64,
+    pub total_operations: u64,
+    pub errors: u64,
+    pub error_kinds: String,
+    #[tabled(display = "optional_float2")]
+    pub throughput_per_s: Option<f64>,
+    #[tabled(display = "optional_float2")]
+    pub avg_time_ms: Option<f64>,
+    #[tabled(display = "optional_float2")]
+    pub min_time_ms: Option<f64>,
+    #[tabled(display = "optional_float2")]
+    pub p50_ms: Option<f64>,
+    #[tabled(display = "optional_float2")]
+    pub p90_ms: Option<f64>,
+    #[tabled(display = "optional_float2")]
+    pub p95_ms: Option<f64>,
+    #[tabled(display = "optional_float2")]
+    pub p99_ms: Option<f64>,
+    #[tabled(rename = "p99.9_ms", display = "optional_float2")]
+    pub p99_9_ms: Option<f64>,
+    #[tabled(display = "optional_float2")]
+    pub max_time_ms: Option<f64>,
 }
 
-fn float2(n: &f64) -> String {
-    format!("{n:.2}")
+/// Values that could not be measured, such as latencies when every operation failed, are shown as
+/// `-`.
+fn optional_float2(n: &Option<f64>) -> String {
+    match n {
+        Some(n) => format!("{n:.2}"),
+        None => "-".to_string(),
+    }
 }
@@ -4,8 +4,11 @@ use crate::OperationRecord;
 use crate::report::in_memory_with_custom_metrics_reporter::custom_metrics_table::CustomMetricsTableBuilder;
 use crate::report::{InMemoryReporter, ReportCollector, ReportMetric};
 
-/// A very basic reporter that is useful while developing scenarios. It keeps all of the operations
-/// and custom metrics in memory and prints a summary of the operations at the end of the run.
+/// A very basic reporter that is useful while developing scenarios. It keeps statistics for each
+/// operation ID and all of the custom metrics in memory and prints a summary of them at the end of
+/// the run.
+///
+/// Operations and custom metrics recorded during the warmup period are not included in the summary.
 pub struct InMemoryWithCustomMetricsReporter {
     in_memory_reporter: InMemoryReporter,
     custom_metrics: Vec<ReportMetric>,
@@ -34,6 +37,10 @@ impl ReportCollector for InMemoryWithCustomMetricsReporter {
     }
 
     fn add_custom(&mut self, metric: crate::report::ReportMetric) {
+        if metric.is_warmup() {
+            return;
+        }
+
         self.custom_metrics.push(metric);
     }
 
@@ -0,0 +1,450 @@
+use crate::OperationRecord;
+use crate::report::influx_reporter_base::InfluxReporterBase;
+use crate::report::metrics_queue::{
+    BackpressurePolicy, DEFAULT_QUEUE_CAPACITY, PipelineCounters, PipelineStats,
+};
+use crate::report::{ReportCollector, ReportMetric};
+
+use influxdb::{Query, WriteQuery};
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::select;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+use wind_tunnel_core::prelude::DelegatedShutdownListener;
+
+/// The maximum number of lines to send to InfluxDB in a single write.
+const MAX_BATCH_LINES: usize = 5000;
+
+/// The longest wait between retries of a write.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// The longest wait to connect to InfluxDB, which is shorter than the request timeout so that an
+/// unreachable server is noticed quickly.
+const MAX_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Once a write has failed on every attempt, how long to spill metrics straight to disk before
+/// trying InfluxDB again.
+const UNREACHABLE_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Configuration for writing metrics to an InfluxDB v2 HTTP endpoint.
+#[derive(Debug, Clone)]
+pub struct InfluxClientConfig {
+    /// The URL of the InfluxDB server, for example `http://localhost:8087`
+    pub url: String,
+    pub org: String,
+    pub bucket: String,
+    pub token: Option<String>,
+    /// The directory to write metrics to, in line protocol format, if they cannot be sent to
+    /// InfluxDB.
+    pub spill_dir: PathBuf,
+    /// The number of times to try each write before spilling it to disk
+    pub max_attempts: usize,
+    /// The wait before the first retry of a write, which doubles for each further retry
+    pub initial_backoff: Duration,
+    /// The longest wait for InfluxDB to respond to a write. This is also how long the reporter
+    /// keeps writing once the run is shutting down, after which any metrics that have not been
+    /// sent are spilled to disk.
+    pub request_timeout: Duration,
+    /// How often to send the metrics that have been batched so far
+    pub flush_interval: Duration,
+    /// The number of metrics that can wait to be sent before the backpressure policy applies
+    pub queue_capacity: usize,
+    /// Defaults to [BackpressurePolicy::Drop], because writes are retried with backoff while the
+    /// queue waits, and blocking agents for that long would skew the latencies being measured.
+    pub backpressure: BackpressurePolicy,
+}
+
+impl InfluxClientConfig {
+    pub fn new(url: String, org: String, bucket: String, spill_dir: PathBuf) -> Self {
+        Self {
+            url,
+            org,
+            bucket,
+            token: None,
+            spill_dir,
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            request_timeout: Duration::from_secs(10),
+            flush_interval: Duration::from_secs(1),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            backpressure: BackpressurePolicy::Drop,
+        }
+    }
+
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    pub fn with_retry(mut self, max_attempts: usize, initial_backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub fn with_queue(mut self, capacity: usize, backpressure: BackpressurePolicy) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self.backpressure = backpressure;
+        self
+    }
+}
+
+/// Write metrics to InfluxDB over HTTP while the scenario is running, so that runs can be watched
+/// live on dashboards.
+///
+/// Metrics are sent in batches. Writes that fail because InfluxDB is unreachable or overloaded are
+/// retried with backoff. If a write still fails, its metrics are spilled to a file in line protocol
+/// format so that they can be imported later.
+///
+/// A write that is in progress when the run shuts down is stopped and its metrics are spilled, and
+/// the remaining metrics get one request timeout to be sent, so that a stalled InfluxDB cannot hold
+/// up the end of the run.
+pub struct InfluxClientReportCollector {
+    inner: InfluxReporterBase,
+}
+
+impl InfluxClientReportCollector {
+    pub fn new(
+        runtime: &tokio::runtime::Handle,
+        shutdown_listener: DelegatedShutdownListener,
+        config: InfluxClientConfig,
+        run_id: String,
+        scenario_name: String,
+    ) -> Self {
+        let flush_complete = Arc::new(AtomicBool::new(false));
+        let counters = Arc::new(PipelineCounters::default());
+        let backpressure = config.backpressure;
+        let (join_handle, writer) = start_metrics_client_write_task(
+            runtime,
+            shutdown_listener,
+            config,
+            scenario_name.clone(),
+            counters.clone(),
+            flush_complete.clone(),
+        );
+
+        Self {
+            inner: InfluxReporterBase::new(
+                run_id,
+                scenario_name,
+                join_handle,
+                writer,
+                backpressure,
+                counters,
+                flush_complete,
+            ),
+        }
+    }
+}
+
+impl ReportCollector for InfluxClientReportCollector {
+    fn add_operation(&mut self, operation_record: &OperationRecord) {
+        self.inner.add_operation(operation_record);
+    }
+
+    fn add_custom(&mut self, metric: ReportMetric) {
+        self.inner.add_custom(metric);
+    }
+
+    fn finalize(&self) {
+        self.inner.finalize();
+    }
+
+    fn pipeline_stats(&self) -> PipelineStats {
+        self.inner.pipeline_stats()
+    }
+}
+
+fn start_metrics_client_write_task(
+    runtime: &tokio::runtime::Handle,
+    mut shutdown_listener: DelegatedShutdownListener,
+    config: InfluxClientConfig,
+    scenario_name: String,
+    counters: Arc<PipelineCounters>,
+    flush_complete: Arc<AtomicBool>,
+) -> (JoinHandle<()>, Sender<WriteQuery>) {
+    let (writer, mut receiver) = tokio::sync::mpsc::channel(config.queue_capacity);
+    let join_handle = runtime.spawn(async move {
+        log::debug!("Influx client reporter starting, writing to {}", config.url);
+        let mut flush_interval = tokio::time::interval(config.flush_interval);
+        let mut client = InfluxWriteClient::new(config, scenario_name, counters.clone());
+        let mut batch = Vec::new();
+
+        // Listen and write metrics until shutdown
+        loop {
+            let write = select! {
+                _ = shutdown_listener.wait_for_shutdown() => {
+                    log::debug!("Shutting down influx client reporter");
+                    break;
+                }
+                _ = flush_interval.tick() => true,
+                query = receiver.recv() => {
+                    if let Some(query) = query {
+                        add_to_batch(&mut batch, query, &counters);
+                        batch.len() >= MAX_BATCH_LINES
+                    } else {
+                        break;
+                    }
+                }
+            };
+
+            if write
+                && client
+                    .write_batch_until(&mut batch, shutdown_listener.wait_for_shutdown())
+                    .await
+            {
+                log::debug!("Shutting down influx client reporter during a write");
+                break;
+            }
+        }
+
+        log::debug!("Draining any remaining metrics before shutting down...");
+        let mut drain_count = 0;
+        let drain_deadline = tokio::time::Instant::now() + client.config.request_timeout;
+
+        // Drain remaining metrics before shutting down
+        while let Ok(query) = receiver.try_recv() {
+            add_to_batch(&mut batch, query, &counters);
+            if batch.len() >= MAX_BATCH_LINES {
+                client
+                    .write_batch_until(&mut batch, tokio::time::sleep_until(drain_deadline))
+                    .await;
+            }
+            drain_count += 1;
+        }
+        client
+            .write_batch_until(&mut batch, tokio::time::sleep_until(drain_deadline))
+            .await;
+        client.flush_spill().await;
+
+        log::debug!("Drained {drain_count} remaining metrics");
+
+        // Signal the 'finalize' method that the write task has finished.
+        flush_complete.store(true, Ordering::Relaxed);
+    });
+
+    (join_handle, writer)
+}
+
+fn add_to_batch(batch: &mut Vec<String>, query: WriteQuery, counters: &PipelineCounters) {
+    match query.build() {
+        Ok(query) => batch.push(query.get()),
+        Err(e) => {
+            counters.add_failed(1);
+            log::warn!("Failed to build metric: {e}");
+        }
+    }
+}
+
+enum WriteError {
+    /// InfluxDB could not be reached or could not handle the write right now, so it is worth
+    /// retrying.
+    Unavailable(String),
+    /// InfluxDB refused the write, so retrying will not help.
+    Rejected(String),
+}
+
+struct InfluxWriteClient {
+    http: reqwest::Client,
+    config: InfluxClientConfig,
+    write_url: String,
+    scenario_name: String,
+    spill_file: Option<File>,
+    unreachable_until: Option<Instant>,
+    counters: Arc<PipelineCounters>,
+}
+
+impl InfluxWriteClient {
+    fn new(
+        config: InfluxClientConfig,
+        scenario_name: String,
+        counters: Arc<PipelineCounters>,
+    ) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .connect_timeout(config.request_timeout.min(MAX_CONNECT_TIMEOUT))
+            .build()
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to configure the InfluxDB client, using the defaults: {e}");
+                reqwest::Client::new()
+            });
+
+        Self {
+            http,
+            write_url: format!("{}/api/v2/write", config.url.trim_end_matches('/')),
+            config,
+            scenario_name,
+            spill_file: None,
+            unreachable_until: None,
+            counters,
+        }
+    }
+
+    /// Send the batch with [Self::write], unless `stop` completes first, in which case the batch is
+    /// spilled to disk instead of waiting for InfluxDB.
+    ///
+    /// Returns whether the write was stopped. Once a write has been stopped, InfluxDB is treated as
+    /// unreachable so that later batches are spilled straight away.
+    async fn write_batch_until(
+        &mut self,
+        batch: &mut Vec<String>,
+        stop: impl Future<Output = ()>,
+    ) -> bool {
+        if batch.is_empty() {
+            return false;
+        }
+        let body = batch.join("\n");
+        let lines = batch.len() as u64;
+        batch.clear();
+
+        let stopped = select! {
+            _ = self.write(&body, lines) => false,
+            _ = stop => true,
+        };
+        if stopped {
+            log::warn!("Stopped writing metrics to InfluxDB, spilling them to disk");
+            self.unreachable_until = Some(Instant::now() + UNREACHABLE_COOLDOWN);
+            self.spill(&body, lines).await;
+        }
+
+        stopped
+    }
+
+    /// Send metrics to InfluxDB, retrying with backoff, and spill them to disk if they cannot be
+    /// sent.
+    async fn write(&mut self, body: &str, lines: u64) {
+        if self
+            .unreachable_until
+            .is_some_and(|until| Instant::now() < until)
+        {
+            self.spill(body, lines).await;
+            return;
+        }
+
+        let mut backoff = self.config.initial_backoff;
+        for attempt in 1..=self.config.max_attempts {
+            match self.post(body).await {
+                Ok(()) => {
+                    self.unreachable_until = None;
+                    return;
+                }
+                Err(WriteError::Rejected(e)) => {
+                    log::error!("InfluxDB rejected metrics, spilling them to disk: {e}");
+                    self.spill(body, lines).await;
+                    return;
+                }
+                Err(WriteError::Unavailable(e)) if attempt < self.config.max_attempts => {
+                    log::warn!(
+                        "Failed to write metrics to InfluxDB on attempt {attempt}, retrying in {backoff:?}: {e}"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                }
+                Err(WriteError::Unavailable(e)) => {
+                    log::error!(
+                        "Failed to write metrics to InfluxDB after {attempt} attempts, spilling metrics to disk for the next {UNREACHABLE_COOLDOWN:?}: {e}"
+                    );
+                }
+            }
+        }
+
+        self.unreachable_until = Some(Instant::now() + UNREACHABLE_COOLDOWN);
+        self.spill(body, lines).await;
+    }
+
+    async fn post(&self, body: &str) -> Result<(), WriteError> {
+        let mut request = self
+            .http
+            .post(&self.write_url)
+            .query(&[
+                ("org", self.config.org.as_str()),
+                ("bucket", self.config.bucket.as_str()),
+                ("precision", "ns"),
+            ])
+            .body(body.to_string());
+        if let Some(token) = &self.config.token {
+            request = request.header("Authorization", format!("Token {token}"));
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| WriteError::Unavailable(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let message = format!(
+            "{status}: {}",
+            response.text().await.unwrap_or_default().trim()
+        );
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            Err(WriteError::Unavailable(message))
+        } else {
+            Err(WriteError::Rejected(message))
+        }
+    }
+
+    /// Append the metrics to the spill file, creating it if this is the first spill.
+    async fn spill(&mut self, body: &str, lines: u64) {
+        if let Err(e) = self.try_spill(body).await {
+            self.counters.add_failed(lines);
+            log::error!("Failed to spill metrics to disk, metrics have been lost: {e:?}");
+        }
+    }
+
+    async fn try_spill(&mut self, body: &str) -> anyhow::Result<()> {
+        let file = match &mut self.spill_file {
+            Some(file) => file,
+            None => {
+                tokio::fs::create_dir_all(&self.config.spill_dir).await?;
+                let spill_path = self.config.spill_dir.join(format!(
+                    "{}-{}-spilled.influx",
+                    self.scenario_name,
+                    SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
+                ));
+                log::warn!("Spilling metrics that could not be sent to InfluxDB to {spill_path:?}");
+                self.spill_file.insert(
+                    File::options()
+                        .create(true)
+                        .append(true)
+                        .open(spill_path)
+                        .await?,
+                )
+            }
+        };
+
+        file.write_all(body.as_bytes()).await?;
+        file.write_all(b"\n").await?;
+
+        Ok(())
+    }
+
+    /// Ensure everything that has been spilled is written to disk.
+    async fn flush_spill(&mut self) {
+        if let Some(file) = &mut self.spill_file
+            && let Err(e) = file.flush().await
+        {
+            log::error!("Failed to flush spilled metrics: {e:?}");
+        }
+    }
+}
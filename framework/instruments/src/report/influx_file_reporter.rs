@@ -1,26 +1,89 @@
 use crate::OperationRecord;
+use crate::report::influx_file_segments::{FileCompression, SegmentWriter};
 use crate::report::influx_reporter_base::InfluxReporterBase;
+use crate::report::metrics_queue::{
+    BackpressurePolicy, DEFAULT_QUEUE_CAPACITY, PipelineCounters, PipelineStats,
+};
 use crate::report::{ReportCollector, ReportMetric};
 
 use influxdb::{Query, WriteQuery};
 
-use std::fmt::Debug;
-
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use std::time::Duration;
 use tokio::select;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::task::JoinHandle;
 use wind_tunnel_core::prelude::DelegatedShutdownListener;
 
+/// Configuration for writing metrics to disk in the InfluxDB line protocol format.
+#[derive(Debug, Clone)]
+pub struct InfluxFileConfig {
+    /// The directory to write the metrics file to
+    pub dir: PathBuf,
+    /// How often to flush buffered metrics to the file
+    pub flush_interval: Duration,
+    /// The number of metrics that can wait to be written before the backpressure policy applies
+    pub queue_capacity: usize,
+    pub backpressure: BackpressurePolicy,
+    /// Start a new metrics file once the current one has grown to this many bytes
+    pub max_file_size: Option<u64>,
+    /// Start a new metrics file once the current one has been open for this long
+    pub rotate_interval: Option<Duration>,
+    pub compression: FileCompression,
+}
+
+impl InfluxFileConfig {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            flush_interval: Duration::from_secs(1),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            backpressure: BackpressurePolicy::default(),
+            max_file_size: None,
+            rotate_interval: None,
+            compression: FileCompression::default(),
+        }
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub fn with_queue(mut self, capacity: usize, backpressure: BackpressurePolicy) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self.backpressure = backpressure;
+        self
+    }
+
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    pub fn with_rotate_interval(mut self, rotate_interval: Duration) -> Self {
+        self.rotate_interval = Some(rotate_interval);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: FileCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
 /// Write metrics to disk in the InfluxDB line protocol format.
 /// Metrics can then be sent to InfluxDB by Telegraf.
 ///
 /// This is the recommended reporter to use when running distributed tests.
+///
+/// Metrics can be split across several files by size or age, and closed files can be compressed.
+/// A manifest listing the files for the run is kept next to them, see [InfluxFileConfig].
+///
+/// If the metrics file cannot be written, metrics are counted as failed and the scenario carries
+/// on running.
 pub struct InfluxFileReportCollector {
     inner: InfluxReporterBase,
 }
@@ -29,16 +92,20 @@ impl InfluxFileReportCollector {
     pub fn new(
         runtime: &tokio::runtime::Handle,
         shutdown_listener: DelegatedShutdownListener,
-        dir: PathBuf,
+        config: InfluxFileConfig,
         run_id: String,
         scenario_name: String,
     ) -> Self {
         let flush_complete = Arc::new(AtomicBool::new(false));
+        let counters = Arc::new(PipelineCounters::default());
+        let backpressure = config.backpressure;
         let (join_handle, writer) = start_metrics_file_write_task(
             runtime,
             shutdown_listener,
-            dir,
+            config,
+            run_id.clone(),
             scenario_name.clone(),
+            counters.clone(),
             flush_complete.clone(),
         );
 
@@ -48,6 +115,8 @@ impl InfluxFileReportCollector {
                 scenario_name,
                 join_handle,
                 writer,
+                backpressure,
+                counters,
                 flush_complete,
             ),
         }
@@ -66,36 +135,40 @@ impl ReportCollector for InfluxFileReportCollector {
     fn finalize(&self) {
         self.inner.finalize();
     }
+
+    fn pipeline_stats(&self) -> PipelineStats {
+        self.inner.pipeline_stats()
+    }
 }
 
 fn start_metrics_file_write_task(
     runtime: &tokio::runtime::Handle,
     mut shutdown_listener: DelegatedShutdownListener,
-    dir: PathBuf,
+    config: InfluxFileConfig,
+    run_id: String,
     scenario_name: String,
+    counters: Arc<PipelineCounters>,
     flush_complete: Arc<AtomicBool>,
-) -> (JoinHandle<()>, UnboundedSender<WriteQuery>) {
-    let (writer, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+) -> (JoinHandle<()>, Sender<WriteQuery>) {
+    let (writer, mut receiver) = tokio::sync::mpsc::channel(config.queue_capacity);
     let join_handle = runtime.spawn(async move {
-        if !dir.exists() {
-            tokio::fs::create_dir_all(&dir).await.unwrap();
-        }
-
-        let out_path = dir.join(format!(
-            "{}-{}.influx",
+        let mut file = match SegmentWriter::create(
+            config.dir,
+            run_id,
             scenario_name,
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        ));
-        log::debug!("Influx file reporter starting, using file {out_path:?}");
-        let mut file = File::options()
-            .create_new(true)
-            .write(true)
-            .open(out_path)
-            .await
-            .unwrap();
+            config.max_file_size,
+            config.rotate_interval,
+            config.compression,
+        )
+        .await
+        {
+            Ok(file) => Some(file),
+            Err(e) => {
+                log::error!("Failed to open metrics file, metrics will not be recorded: {e:?}");
+                None
+            }
+        };
+        let mut flush_interval = tokio::time::interval(config.flush_interval);
 
         // Listen and write metrics until shutdown
         loop {
@@ -104,9 +177,12 @@ fn start_metrics_file_write_task(
                     log::debug!("Shutting down influx file reporter");
                     break;
                 }
+                _ = flush_interval.tick() => {
+                    flush(&mut file).await;
+                }
                 query = receiver.recv() => {
                     if let Some(query) = query {
-                        write_query(&mut file, query).await.unwrap()
+                        write_query(&mut file, query, &counters).await;
                     } else {
                         break;
                     }
@@ -115,21 +191,16 @@ fn start_metrics_file_write_task(
         }
 
         log::debug!("Draining any remaining metrics before shutting down...");
-        let mut drain_count = 0;
+        let drain_count = drain(&mut receiver, &mut file, &counters).await;
 
-        // Drain remaining metrics before shutting down
-        while let Ok(query) = receiver.try_recv() {
-            write_query(&mut file, query).await.unwrap();
-            drain_count += 1;
-
-            if drain_count % 1000 == 0 {
-                log::debug!("Drained {drain_count} remaining metrics");
-            }
+        // Ensure everything that's buffered has been written to disk, and the last file is
+        // compressed.
+        if let Some(file) = &mut file
+            && let Err(e) = file.close().await
+        {
+            log::error!("Failed to close metrics file: {e:?}");
         }
 
-        // Ensure everything that's buffered has been written to disk.
-        file.flush().await.unwrap();
-
         log::debug!("Drained {drain_count} remaining metrics");
 
         // Signal the 'finalize' method that the write task has finished.
@@ -139,14 +210,51 @@ fn start_metrics_file_write_task(
     (join_handle, writer)
 }
 
+async fn drain(
+    receiver: &mut Receiver<WriteQuery>,
+    file: &mut Option<SegmentWriter>,
+    counters: &PipelineCounters,
+) -> usize {
+    let mut drain_count = 0;
+    while let Ok(query) = receiver.try_recv() {
+        write_query(file, query, counters).await;
+        drain_count += 1;
+
+        if drain_count % 1000 == 0 {
+            log::debug!("Drained {drain_count} remaining metrics");
+        }
+    }
+
+    drain_count
+}
+
+/// Write the query to the file, counting it as failed if it cannot be written.
+async fn write_query(
+    file: &mut Option<SegmentWriter>,
+    query: WriteQuery,
+    counters: &PipelineCounters,
+) {
+    let Some(file) = file else {
+        counters.add_failed(1);
+        return;
+    };
+
+    if let Err(e) = try_write_query(file, query).await {
+        counters.add_failed(1);
+        log::warn!("Failed to write metric: {e:?}");
+    }
+}
+
 #[inline]
-async fn write_query<W>(writer: &mut W, query: WriteQuery) -> anyhow::Result<()>
-where
-    W: AsyncWriteExt + Unpin + Debug,
-{
+async fn try_write_query(file: &mut SegmentWriter, query: WriteQuery) -> anyhow::Result<()> {
     let query_str = query.build()?.get();
-    writer.write_all(query_str.as_bytes()).await?;
-    writer.write_all(b"\n").await?;
+    file.write_line(&query_str).await
+}
 
-    Ok(())
+async fn flush(file: &mut Option<SegmentWriter>) {
+    if let Some(file) = file
+        && let Err(e) = file.flush().await
+    {
+        log::error!("Failed to flush metrics file: {e:?}");
+    }
 }
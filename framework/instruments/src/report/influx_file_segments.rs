@@ -0,0 +1,328 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, BufWriter as StdBufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::task::JoinSet;
+
+/// Compression to apply to a metrics file once it has been closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileCompression {
+    #[default]
+    None,
+    /// Compress closed files with gzip, adding a `.gz` extension
+    Gzip,
+    /// Compress closed files with zstd, adding a `.zst` extension
+    Zstd,
+}
+
+impl FileCompression {
+    fn extension(self) -> Option<&'static str> {
+        match self {
+            FileCompression::None => None,
+            FileCompression::Gzip => Some("gz"),
+            FileCompression::Zstd => Some("zst"),
+        }
+    }
+}
+
+/// The manifest of the metrics files written for a run, so that uploads can tell which files are
+/// complete and resume where they left off.
+#[derive(Debug, Serialize)]
+struct Manifest {
+    run_id: String,
+    scenario_name: String,
+    segments: Vec<SegmentEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct SegmentEntry {
+    /// The file name of the segment, relative to the manifest.
+    file: String,
+    /// The number of metrics written to the segment.
+    lines: u64,
+    /// The size of the segment on disk, once it is complete.
+    bytes: u64,
+    /// Whether the segment has been closed, and compressed if compression is enabled.
+    complete: bool,
+}
+
+struct OpenSegment {
+    index: usize,
+    path: PathBuf,
+    file: BufWriter<File>,
+    opened_at: Instant,
+    bytes: u64,
+}
+
+/// Writes metrics to a sequence of files, starting a new file when the current one is too large or
+/// too old, and keeps the run's manifest up to date.
+///
+/// Without rotation, metrics are written to `<scenario>-<unix secs>.influx` as a single file.
+/// With rotation, each file gets a sequence number, `<scenario>-<unix secs>-<n>.influx`. The
+/// manifest is written next to them as `<scenario>-<unix secs>.manifest.json`.
+pub(crate) struct SegmentWriter {
+    dir: PathBuf,
+    base_name: String,
+    max_file_size: Option<u64>,
+    rotate_interval: Option<Duration>,
+    compression: FileCompression,
+    manifest: Manifest,
+    current: Option<OpenSegment>,
+    compressing: JoinSet<(usize, anyhow::Result<PathBuf>)>,
+}
+
+impl SegmentWriter {
+    pub(crate) async fn create(
+        dir: PathBuf,
+        run_id: String,
+        scenario_name: String,
+        max_file_size: Option<u64>,
+        rotate_interval: Option<Duration>,
+        compression: FileCompression,
+    ) -> anyhow::Result<Self> {
+        if !dir.exists() {
+            tokio::fs::create_dir_all(&dir).await?;
+        }
+
+        let base_name = format!(
+            "{}-{}",
+            scenario_name,
+            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
+        );
+
+        let mut writer = Self {
+            dir,
+            base_name,
+            max_file_size,
+            rotate_interval,
+            compression,
+            manifest: Manifest {
+                run_id,
+                scenario_name,
+                segments: Vec::new(),
+            },
+            current: None,
+            compressing: JoinSet::new(),
+        };
+        writer.open_segment().await?;
+
+        Ok(writer)
+    }
+
+    fn rotates(&self) -> bool {
+        self.max_file_size.is_some() || self.rotate_interval.is_some()
+    }
+
+    /// Write a line of line protocol, starting a new segment first if the current one is full.
+    pub(crate) async fn write_line(&mut self, line: &str) -> anyhow::Result<()> {
+        if self.current.is_none() {
+            self.open_segment().await?;
+        } else if self.should_rotate() {
+            self.rotate().await?;
+        }
+
+        let segment = self.current.as_mut().context("No metrics file is open")?;
+        segment.file.write_all(line.as_bytes()).await?;
+        segment.file.write_all(b"\n").await?;
+        segment.bytes += line.len() as u64 + 1;
+        self.manifest.segments[segment.index].lines += 1;
+
+        Ok(())
+    }
+
+    /// Flush buffered metrics to disk, and rotate the current segment if it has been open for
+    /// longer than the rotation interval.
+    pub(crate) async fn flush(&mut self) -> anyhow::Result<()> {
+        self.collect_compressed(false).await?;
+
+        if self.should_rotate() {
+            return self.rotate().await;
+        }
+
+        if let Some(segment) = &mut self.current {
+            segment.file.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Close the current segment and wait for every segment to be compressed.
+    pub(crate) async fn close(&mut self) -> anyhow::Result<()> {
+        let closed = self.close_segment().await;
+        let compressed = self.collect_compressed(true).await;
+        closed.and(compressed)
+    }
+
+    fn should_rotate(&self) -> bool {
+        let Some(segment) = &self.current else {
+            return false;
+        };
+
+        // Never rotate to leave an empty segment behind.
+        if self.manifest.segments[segment.index].lines == 0 {
+            return false;
+        }
+
+        self.max_file_size
+            .is_some_and(|max_file_size| segment.bytes >= max_file_size)
+            || self
+                .rotate_interval
+                .is_some_and(|interval| segment.opened_at.elapsed() >= interval)
+    }
+
+    async fn rotate(&mut self) -> anyhow::Result<()> {
+        self.close_segment().await?;
+        self.open_segment().await
+    }
+
+    async fn open_segment(&mut self) -> anyhow::Result<()> {
+        let index = self.manifest.segments.len();
+        let file_name = if self.rotates() {
+            format!("{}-{:04}.influx", self.base_name, index + 1)
+        } else {
+            format!("{}.influx", self.base_name)
+        };
+        let path = self.dir.join(&file_name);
+        log::debug!("Influx file reporter writing to file {path:?}");
+
+        let file = File::options()
+            .create_new(true)
+            .write(true)
+            .open(&path)
+            .await?;
+
+        self.current = Some(OpenSegment {
+            index,
+            path,
+            file: BufWriter::new(file),
+            opened_at: Instant::now(),
+            bytes: 0,
+        });
+        self.manifest.segments.push(SegmentEntry {
+            file: file_name,
+            lines: 0,
+            bytes: 0,
+            complete: false,
+        });
+
+        self.write_manifest().await
+    }
+
+    async fn close_segment(&mut self) -> anyhow::Result<()> {
+        let Some(mut segment) = self.current.take() else {
+            return Ok(());
+        };
+
+        segment.file.flush().await?;
+        segment.file.into_inner().sync_all().await?;
+
+        match self.compression {
+            FileCompression::None => {
+                let entry = &mut self.manifest.segments[segment.index];
+                entry.bytes = segment.bytes;
+                entry.complete = true;
+                self.write_manifest().await?;
+            }
+            compression => {
+                // Compress in the background so that writing metrics to the next segment is not
+                // held up.
+                let index = segment.index;
+                self.compressing
+                    .spawn_blocking(move || (index, compress_segment(&segment.path, compression)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record segments that have finished compressing in the manifest, waiting for all of them if
+    /// `wait` is set.
+    async fn collect_compressed(&mut self, wait: bool) -> anyhow::Result<()> {
+        let mut result = Ok(());
+        let mut updated = false;
+        loop {
+            let joined = if wait {
+                self.compressing.join_next().await
+            } else {
+                self.compressing.try_join_next()
+            };
+            let Some(joined) = joined else {
+                break;
+            };
+
+            match joined {
+                Ok((index, Ok(path))) => {
+                    let entry = &mut self.manifest.segments[index];
+                    entry.bytes = tokio::fs::metadata(&path).await?.len();
+                    entry.file = file_name(&path);
+                    entry.complete = true;
+                    updated = true;
+                }
+                Ok((index, Err(e))) => {
+                    log::error!(
+                        "Failed to compress metrics file {}: {e:?}",
+                        self.manifest.segments[index].file
+                    );
+                    result = Err(e);
+                }
+                Err(e) => result = Err(e.into()),
+            }
+        }
+
+        if updated {
+            self.write_manifest().await?;
+        }
+
+        result
+    }
+
+    async fn write_manifest(&self) -> anyhow::Result<()> {
+        let path = self.dir.join(format!("{}.manifest.json", self.base_name));
+        let tmp_path = path.with_extension("json.tmp");
+
+        // Replace the manifest in one step, so that it can be read at any time.
+        tokio::fs::write(&tmp_path, serde_json::to_vec_pretty(&self.manifest)?).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+
+        Ok(())
+    }
+}
+
+/// Compress a closed segment next to the original, then remove the original.
+fn compress_segment(path: &Path, compression: FileCompression) -> anyhow::Result<PathBuf> {
+    let Some(extension) = compression.extension() else {
+        return Ok(path.to_path_buf());
+    };
+    let compressed_path = PathBuf::from(format!("{}.{extension}", path.display()));
+
+    let mut input = BufReader::new(std::fs::File::open(path)?);
+    let output = StdBufWriter::new(std::fs::File::create(&compressed_path)?);
+    let output = match compression {
+        FileCompression::None => unreachable!("Uncompressed segments have no extension"),
+        FileCompression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            std::io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?
+        }
+        FileCompression::Zstd => {
+            let mut encoder = zstd::Encoder::new(output, 0)?;
+            std::io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?
+        }
+    };
+    output.into_inner()?.sync_all()?;
+
+    std::fs::remove_file(path)?;
+
+    Ok(compressed_path)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
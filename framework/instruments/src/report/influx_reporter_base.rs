@@ -1,5 +1,8 @@
-use crate::OperationRecord;
+use crate::report::metrics_queue::{
+    BackpressurePolicy, PipelineCounters, PipelineStats, send_with_policy,
+};
 use crate::report::{ReportCollector, ReportMetric};
+use crate::{ERROR_KIND_ATTR, OperationRecord};
 
 use influxdb::{InfluxDbWriteable, Timestamp, WriteQuery};
 use influxive_core::DataType;
@@ -7,15 +10,20 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::SystemTime;
 
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::task::JoinHandle;
 
 pub(crate) struct InfluxReporterBase {
     run_id: String,
     scenario_name: String,
     join_handle: JoinHandle<()>,
-    writer: UnboundedSender<WriteQuery>,
+    writer: Sender<WriteQuery>,
+    backpressure: BackpressurePolicy,
+    counters: Arc<PipelineCounters>,
     flush_complete: Arc<AtomicBool>,
+    warned_full: AtomicBool,
+    warned_stopped: AtomicBool,
 }
 
 impl InfluxReporterBase {
@@ -23,7 +31,9 @@ impl InfluxReporterBase {
         run_id: String,
         scenario_name: String,
         join_handle: JoinHandle<()>,
-        writer: UnboundedSender<WriteQuery>,
+        writer: Sender<WriteQuery>,
+        backpressure: BackpressurePolicy,
+        counters: Arc<PipelineCounters>,
         flush_complete: Arc<AtomicBool>,
     ) -> Self {
         Self {
@@ -31,31 +41,39 @@ impl InfluxReporterBase {
             scenario_name,
             join_handle,
             writer,
+            backpressure,
+            counters,
             flush_complete,
+            warned_full: AtomicBool::new(false),
+            warned_stopped: AtomicBool::new(false),
         }
     }
 
     fn try_send(&self, query: WriteQuery) {
-        if let Err(e) = self.writer.send(query) {
-            if self.flush_complete.load(Ordering::Relaxed) {
-                log::info!("Failed to record metric because the write task has finished: {e}");
-            } else {
-                log::warn!("Failed to record metric: {e}");
+        match send_with_policy(&self.writer, query, self.backpressure) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.counters.add_dropped(1);
+                if !self.warned_full.swap(true, Ordering::Relaxed) {
+                    log::warn!(
+                        "Dropping metrics because they are being reported faster than they can be written"
+                    );
+                }
+            }
+            Err(TrySendError::Closed(_)) => {
+                self.counters.add_dropped(1);
+                if self.flush_complete.load(Ordering::Relaxed) {
+                    log::info!("Failed to record metric because the write task has finished");
+                } else if !self.warned_stopped.swap(true, Ordering::Relaxed) {
+                    log::error!("Dropping metrics because the write task has stopped");
+                }
             }
-        }
-    }
-
-    fn crash_if_reporting_task_finished(&self) {
-        if self.join_handle.is_finished() {
-            panic!("Reporter cannot be used because the write task has finished");
         }
     }
 }
 
 impl ReportCollector for InfluxReporterBase {
     fn add_operation(&mut self, operation_record: &OperationRecord) {
-        self.crash_if_reporting_task_finished();
-
         let mut query = Timestamp::Nanoseconds(
             SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)
@@ -70,21 +88,38 @@ impl ReportCollector for InfluxReporterBase {
                 .expect("OperationRecord must have an elapsed time")
                 .as_secs_f64(),
         )
+        .add_field(
+            "started_at_ns",
+            operation_record
+                .started_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .expect("SystemTime before UNIX_EPOCH")
+                .as_nanos() as u64,
+        )
         .add_tag("run_id", self.run_id.clone())
         .add_tag("scenario_name", self.scenario_name.clone())
         .add_tag("operation_id", operation_record.operation_id.to_string())
         .add_tag("is_error", operation_record.is_error.to_string());
 
+        if let Some(error_kind) = &operation_record.error_kind {
+            query = query.add_tag(ERROR_KIND_ATTR, error_kind.clone());
+        }
+        if let Some(error_message) = &operation_record.error_message {
+            query = query.add_field("error_message", error_message.clone());
+        }
+
         for (k, v) in &operation_record.attr {
             query = query.add_tag(k, v.to_string());
         }
 
+        for (k, v) in &operation_record.fields {
+            query = query.add_field(k, v.clone().into_type());
+        }
+
         self.try_send(query);
     }
 
     fn add_custom(&mut self, metric: ReportMetric) {
-        self.crash_if_reporting_task_finished();
-
         let metric = metric.into_inner();
 
         let mut query = Timestamp::Nanoseconds(
@@ -136,6 +171,10 @@ impl ReportCollector for InfluxReporterBase {
             wait_started.elapsed().as_secs()
         );
     }
+
+    fn pipeline_stats(&self) -> PipelineStats {
+        self.counters.stats()
+    }
 }
 
 trait DataTypeExt {
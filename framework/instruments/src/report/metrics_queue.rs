@@ -0,0 +1,90 @@
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::runtime::RuntimeFlavor;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// The default number of metrics that can wait in a reporter's queue to be written.
+pub(crate) const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+
+/// What a reporter does with a new metric when its queue of metrics waiting to be written is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackpressurePolicy {
+    /// Drop the metric, so that agents are never slowed down by reporting.
+    Drop,
+    /// Wait for space in the queue, so that no metrics are lost but agents are slowed down to the
+    /// rate that metrics can be written.
+    ///
+    /// On a single-threaded runtime, waiting would stop the queue from being drained, so metrics
+    /// are dropped instead.
+    #[default]
+    Block,
+}
+
+/// Counts of the metrics that a reporter could not write.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineStats {
+    /// Metrics that were never queued, because the queue was full or the reporter had stopped.
+    pub dropped: u64,
+    /// Metrics that were queued but could not be written.
+    pub failed: u64,
+}
+
+impl std::ops::Add for PipelineStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            dropped: self.dropped + rhs.dropped,
+            failed: self.failed + rhs.failed,
+        }
+    }
+}
+
+/// Counters for [PipelineStats], shared between a reporter and its write task.
+#[derive(Debug, Default)]
+pub(crate) struct PipelineCounters {
+    dropped: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl PipelineCounters {
+    pub(crate) fn add_dropped(&self, count: u64) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_failed(&self, count: u64) {
+        self.failed.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> PipelineStats {
+        PipelineStats {
+            dropped: self.dropped.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Send a value to a bounded queue, following the backpressure policy if the queue is full.
+pub(crate) fn send_with_policy<T>(
+    sender: &Sender<T>,
+    value: T,
+    policy: BackpressurePolicy,
+) -> Result<(), TrySendError<T>> {
+    match policy {
+        BackpressurePolicy::Drop => sender.try_send(value),
+        BackpressurePolicy::Block => match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                // Hand this worker's other tasks to another thread while waiting, so that the
+                // write task can still run.
+                tokio::task::block_in_place(|| sender.blocking_send(value))
+                    .map_err(|e| TrySendError::Closed(e.0))
+            }
+            Ok(_) => sender.try_send(value),
+            Err(_) => sender
+                .blocking_send(value)
+                .map_err(|e| TrySendError::Closed(e.0)),
+        },
+    }
+}
@@ -0,0 +1,227 @@
+use crate::report::{ReportCollector, ReportMetric};
+use crate::{ERROR_KIND_ATTR, OperationRecord};
+
+use anyhow::Context;
+use influxive_core::DataType;
+use opentelemetry::metrics::{Gauge, Histogram, Meter, MeterProvider};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_otlp::{MetricExporter, Protocol, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::metrics::periodic_reader_with_async_runtime::PeriodicReader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Bucket boundaries, in seconds, for the operation duration histogram.
+const OPERATION_DURATION_BOUNDARIES: [f64; 16] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 60.0, 120.0,
+];
+
+/// The transport to send OTLP metrics over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OtlpProtocol {
+    Grpc,
+    #[serde(rename = "http/protobuf")]
+    HttpProtobuf,
+}
+
+/// Configuration for exporting metrics to an OpenTelemetry collector.
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    pub protocol: OtlpProtocol,
+    /// The base URL of the collector, for example `http://localhost:4318`.
+    ///
+    /// If not set, the standard `OTEL_EXPORTER_OTLP_ENDPOINT` and
+    /// `OTEL_EXPORTER_OTLP_METRICS_ENDPOINT` environment variables are used, falling back to the
+    /// default port for the protocol on localhost.
+    pub endpoint: Option<String>,
+    /// How often to export metrics to the collector
+    pub export_interval: Duration,
+}
+
+impl OtlpConfig {
+    pub fn new(protocol: OtlpProtocol) -> Self {
+        Self {
+            protocol,
+            endpoint: None,
+            export_interval: Duration::from_secs(5),
+        }
+    }
+
+    pub fn with_endpoint(mut self, endpoint: String) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    pub fn with_export_interval(mut self, export_interval: Duration) -> Self {
+        self.export_interval = export_interval;
+        self
+    }
+}
+
+/// Export metrics to an OpenTelemetry collector using OTLP.
+///
+/// Operation durations are recorded, in seconds, to the `wt.instruments.operation_duration`
+/// histogram, with the operation's attributes but not its fields. Each numeric field of a custom
+/// metric is recorded to a gauge, named after the metric for a field called `value` and
+/// `<metric>.<field>` for any other field. String fields are not exported. Tags become attributes,
+/// and every data point is given `run_id` and `scenario_name` attributes.
+///
+/// Metrics are aggregated and exported periodically, so the timestamp of each custom metric is not
+/// kept.
+pub struct OtlpReportCollector {
+    provider: SdkMeterProvider,
+    meter: Meter,
+    operation_duration: Histogram<f64>,
+    gauges: HashMap<String, Gauge<f64>>,
+    run_id: String,
+    scenario_name: String,
+}
+
+impl OtlpReportCollector {
+    pub fn new(
+        runtime: &tokio::runtime::Handle,
+        config: OtlpConfig,
+        run_id: String,
+        scenario_name: String,
+    ) -> anyhow::Result<Self> {
+        // The exporter and the export task must be created inside the runtime.
+        let _guard = runtime.enter();
+
+        let exporter = match config.protocol {
+            OtlpProtocol::Grpc => {
+                let mut builder = MetricExporter::builder().with_tonic();
+                if let Some(endpoint) = &config.endpoint {
+                    builder = builder.with_endpoint(endpoint);
+                }
+                builder.build()
+            }
+            OtlpProtocol::HttpProtobuf => {
+                // The client is set explicitly because no default is picked when other crates in
+                // the build also enable the blocking reqwest client feature.
+                let mut builder = MetricExporter::builder()
+                    .with_http()
+                    .with_http_client(reqwest::Client::new())
+                    .with_protocol(Protocol::HttpBinary);
+                if let Some(endpoint) = &config.endpoint {
+                    builder = builder
+                        .with_endpoint(format!("{}/v1/metrics", endpoint.trim_end_matches('/')));
+                }
+                builder.build()
+            }
+        }
+        .context("Failed to create OTLP metrics exporter")?;
+
+        let reader = PeriodicReader::builder(exporter, opentelemetry_sdk::runtime::Tokio)
+            .with_interval(config.export_interval)
+            .build();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(Resource::builder().with_service_name("wind-tunnel").build())
+            .build();
+
+        let meter = provider.meter("wind_tunnel");
+        let operation_duration = meter
+            .f64_histogram("wt.instruments.operation_duration")
+            .with_unit("s")
+            .with_description("The duration of operations")
+            .with_boundaries(OPERATION_DURATION_BOUNDARIES.to_vec())
+            .build();
+
+        Ok(Self {
+            provider,
+            meter,
+            operation_duration,
+            gauges: HashMap::new(),
+            run_id,
+            scenario_name,
+        })
+    }
+
+    fn common_attributes(&self) -> Vec<KeyValue> {
+        vec![
+            KeyValue::new("run_id", self.run_id.clone()),
+            KeyValue::new("scenario_name", self.scenario_name.clone()),
+        ]
+    }
+}
+
+impl ReportCollector for OtlpReportCollector {
+    fn add_operation(&mut self, operation_record: &OperationRecord) {
+        let mut attributes = self.common_attributes();
+        attributes.push(KeyValue::new(
+            "operation_id",
+            operation_record.operation_id.clone(),
+        ));
+        attributes.push(KeyValue::new(
+            "is_error",
+            operation_record.is_error.to_string(),
+        ));
+        // The error message is left out because attributes should have a small set of values.
+        if let Some(error_kind) = &operation_record.error_kind {
+            attributes.push(KeyValue::new(ERROR_KIND_ATTR, error_kind.clone()));
+        }
+        for (k, v) in &operation_record.attr {
+            attributes.push(KeyValue::new(k.clone(), v.clone()));
+        }
+
+        self.operation_duration.record(
+            operation_record
+                .elapsed
+                .expect("OperationRecord must have an elapsed time")
+                .as_secs_f64(),
+            &attributes,
+        );
+    }
+
+    fn add_custom(&mut self, metric: ReportMetric) {
+        let metric = metric.into_inner();
+        let name = metric.name.into_string();
+
+        let mut attributes = self.common_attributes();
+        for (k, v) in metric.tags {
+            attributes.push(KeyValue::new(k.into_string(), into_value(v)));
+        }
+
+        for (field, value) in metric.fields {
+            let value = match value {
+                DataType::Bool(b) => f64::from(u8::from(b)),
+                DataType::F64(f) => f,
+                DataType::I64(i) => i as f64,
+                DataType::U64(u) => u as f64,
+                DataType::String(_) => continue,
+            };
+
+            let field = field.into_string();
+            let gauge_name = if field == "value" {
+                name.clone()
+            } else {
+                format!("{name}.{field}")
+            };
+            let meter = &self.meter;
+            self.gauges
+                .entry(gauge_name)
+                .or_insert_with_key(|gauge_name| meter.f64_gauge(gauge_name.clone()).build())
+                .record(value, &attributes);
+        }
+    }
+
+    fn finalize(&self) {
+        // Shutting down the provider exports any metrics that have not been exported yet.
+        if let Err(e) = self.provider.shutdown() {
+            log::error!("Failed to export metrics to the OTLP collector: {e}");
+        }
+    }
+}
+
+fn into_value(value: DataType) -> Value {
+    match value {
+        DataType::Bool(b) => Value::Bool(b),
+        DataType::F64(f) => Value::F64(f),
+        DataType::I64(i) => Value::I64(i),
+        DataType::U64(u) => Value::String(u.to_string().into()),
+        DataType::String(s) => Value::String(s.into_string().into()),
+    }
+}
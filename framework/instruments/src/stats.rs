@@ -0,0 +1,115 @@
+use crate::OperationRecord;
+use crate::metrics::{new_hdr_histogram, record_hdr_value};
+use hdrhistogram::Histogram;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Statistics for the operations recorded with a single operation ID.
+///
+/// Latencies are kept in a histogram of microseconds, so the memory used does not grow with the
+/// number of operations. Latencies are only recorded for operations that succeeded.
+#[derive(Debug, Clone)]
+pub struct OperationStats {
+    latency_micros: Histogram<u64>,
+    error_count: u64,
+    errors_by_kind: BTreeMap<String, u64>,
+    /// When the first operation started and the last operation finished
+    window: Option<(Instant, Instant)>,
+}
+
+impl Default for OperationStats {
+    fn default() -> Self {
+        Self {
+            latency_micros: new_hdr_histogram(),
+            error_count: 0,
+            errors_by_kind: BTreeMap::new(),
+            window: None,
+        }
+    }
+}
+
+impl OperationStats {
+    pub(crate) fn record(&mut self, operation_record: &OperationRecord) {
+        let elapsed = operation_record.elapsed.unwrap_or_default();
+        if operation_record.is_error {
+            self.error_count += 1;
+            if let Some(error_kind) = &operation_record.error_kind {
+                *self.errors_by_kind.entry(error_kind.clone()).or_default() += 1;
+            }
+        } else {
+            record_hdr_value(&mut self.latency_micros, elapsed.as_micros() as u64);
+        }
+
+        let started = operation_record.started;
+        let finished = started + elapsed;
+        self.window = Some(match self.window {
+            Some((first, last)) => (first.min(started), last.max(finished)),
+            None => (started, finished),
+        });
+    }
+
+    /// The total number of operations, including errors.
+    pub fn count(&self) -> u64 {
+        self.latency_micros.len() + self.error_count
+    }
+
+    /// The number of operations that failed.
+    pub fn error_count(&self) -> u64 {
+        self.error_count
+    }
+
+    /// The number of operations that failed, by the kind of error.
+    pub fn errors_by_kind(&self) -> &BTreeMap<String, u64> {
+        &self.errors_by_kind
+    }
+
+    /// The fraction of operations that failed, between 0 and 1.
+    pub fn error_rate(&self) -> f64 {
+        match self.count() {
+            0 => 0.0,
+            count => self.error_count as f64 / count as f64,
+        }
+    }
+
+    /// The number of operations per second, from the start of the first operation to the end of
+    /// the last.
+    ///
+    /// Returns [None] if there is not enough data to measure over.
+    pub fn throughput(&self) -> Option<f64> {
+        let (first, last) = self.window?;
+        let window = last.duration_since(first).as_secs_f64();
+
+        (window > 0.0).then(|| self.count() as f64 / window)
+    }
+
+    /// The duration below which `percentile` percent of the successful operations completed.
+    ///
+    /// Returns [None] if no operations succeeded.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        self.latencies()
+            .map(|latencies| Duration::from_micros(latencies.value_at_percentile(percentile)))
+    }
+
+    /// The mean duration of the successful operations.
+    pub fn mean(&self) -> Option<Duration> {
+        self.latencies()
+            .map(|latencies| Duration::from_secs_f64(latencies.mean() / 1_000_000.0))
+    }
+
+    /// The shortest duration of the successful operations.
+    pub fn min(&self) -> Option<Duration> {
+        self.latencies()
+            .map(|latencies| Duration::from_micros(latencies.min()))
+    }
+
+    /// The longest duration of the successful operations.
+    pub fn max(&self) -> Option<Duration> {
+        self.latencies()
+            .map(|latencies| Duration::from_micros(latencies.max()))
+    }
+
+    /// The latencies of the successful operations, if any succeeded.
+    fn latencies(&self) -> Option<&Histogram<u64>> {
+        (!self.latency_micros.is_empty()).then_some(&self.latency_micros)
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{OperationError, OperationRecord, Reporter, report_operation};
+use std::sync::Arc;
+
+/// The prefix of the operation ID that transactions are reported with.
+pub const TRANSACTION_OPERATION_PREFIX: &str = "transaction.";
+
+/// A group of operations, such as the calls that make up a user journey, that is reported as one
+/// operation with an end-to-end duration.
+///
+/// The operations inside the transaction are still reported individually. The transaction itself
+/// is reported with the operation ID `transaction.<name>` when it is finished, and is not reported
+/// if it is dropped without being finished.
+#[derive(Debug)]
+pub struct Transaction {
+    reporter: Arc<Reporter>,
+    operation_record: OperationRecord,
+}
+
+impl Transaction {
+    /// Start timing a transaction.
+    pub fn start(reporter: Arc<Reporter>, name: &str) -> Self {
+        Self {
+            reporter,
+            operation_record: OperationRecord::new(format!("{TRANSACTION_OPERATION_PREFIX}{name}")),
+        }
+    }
+
+    /// Add an attribute to be reported with the transaction.
+    pub fn add_attr(&mut self, key: &str, value: String) {
+        self.operation_record.add_attr(key, value);
+    }
+
+    /// Add a field to be reported with the transaction, see [OperationRecord::add_field].
+    pub fn add_field(&mut self, key: &str, value: impl Into<influxive_core::DataType>) {
+        self.operation_record.add_field(key, value);
+    }
+
+    /// Finish the transaction and report it, as failed if the result is an error.
+    pub fn finish<T, E: OperationError>(self, result: &Result<T, E>) {
+        report_operation(self.reporter, self.operation_record, result);
+    }
+}
@@ -0,0 +1,55 @@
+use parking_lot::RwLock;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The warmup period of a run, shared between a reporter and its typed metrics.
+///
+/// Operations and metrics are assigned to the warmup by when they started or were recorded, not by
+/// when they are reported, so that an operation that starts during the warmup and finishes after
+/// it is still counted as part of the warmup.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WarmupWindow(Arc<RwLock<Option<Range<Instant>>>>);
+
+impl WarmupWindow {
+    /// Start a warmup period that lasts for `duration` from now.
+    pub(crate) fn start(&self, duration: Duration) {
+        let now = Instant::now();
+        *self.0.write() = Some(now..now + duration);
+    }
+
+    /// Whether `instant` is within the warmup period.
+    pub(crate) fn includes(&self, instant: Instant) -> bool {
+        self.0
+            .read()
+            .as_ref()
+            .is_some_and(|window| window.contains(&instant))
+    }
+
+    /// Whether the warmup period is in progress.
+    pub(crate) fn in_progress(&self) -> bool {
+        self.includes(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instants_are_checked_against_the_warmup_after_it_ends() {
+        let warmup = WarmupWindow::default();
+        let before = Instant::now();
+        assert!(!warmup.includes(before));
+
+        warmup.start(Duration::from_millis(50));
+        let during = Instant::now();
+        assert!(warmup.in_progress());
+        std::thread::sleep(Duration::from_millis(100));
+
+        assert!(!warmup.in_progress());
+        assert!(warmup.includes(during));
+        assert!(!warmup.includes(before));
+        assert!(!warmup.includes(Instant::now()));
+    }
+}
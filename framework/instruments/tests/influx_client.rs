@@ -0,0 +1,240 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use wind_tunnel_core::prelude::ShutdownHandle;
+use wind_tunnel_instruments::prelude::{
+    InfluxClientConfig, OperationRecord, ReportConfig, ReportMetric, report_operation,
+};
+
+#[derive(Debug, Clone)]
+struct WriteRequest {
+    request_line: String,
+    authorization: Option<String>,
+    body: String,
+}
+
+/// A stand-in for the InfluxDB write endpoint, which responds to each request with the next status
+/// in `statuses`, or 204 once they have all been used.
+async fn start_fake_influx(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<WriteRequest>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let statuses = Arc::new(Mutex::new(statuses.into_iter()));
+
+    tokio::spawn({
+        let requests = requests.clone();
+        async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let requests = requests.clone();
+                let statuses = statuses.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    loop {
+                        let mut request_line = String::new();
+                        if stream.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+
+                        let mut content_length = 0;
+                        let mut authorization = None;
+                        loop {
+                            let mut header = String::new();
+                            stream.read_line(&mut header).await.unwrap();
+                            let header = header.trim_end();
+                            if header.is_empty() {
+                                break;
+                            }
+                            let (name, value) = header.split_once(": ").unwrap();
+                            match name.to_lowercase().as_str() {
+                                "content-length" => content_length = value.parse().unwrap(),
+                                "authorization" => authorization = Some(value.to_string()),
+                                _ => {}
+                            }
+                        }
+
+                        let mut body = vec![0; content_length];
+                        stream.read_exact(&mut body).await.unwrap();
+                        requests.lock().unwrap().push(WriteRequest {
+                            request_line: request_line.trim_end().to_string(),
+                            authorization,
+                            body: String::from_utf8(body).unwrap(),
+                        });
+
+                        let status = statuses.lock().unwrap().next().unwrap_or(204);
+                        let response =
+                            format!("HTTP/1.1 {status} Status\r\nContent-Length: 0\r\n\r\n");
+                        stream.write_all(response.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        }
+    });
+
+    (url, requests)
+}
+
+fn report_metrics(config: InfluxClientConfig, runtime: &tokio::runtime::Runtime) {
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = Arc::new(
+        ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+            .enable_influx_client(config)
+            .init_reporter(runtime.handle(), &shutdown_handle)
+            .unwrap(),
+    );
+
+    reporter.add_custom(ReportMetric::new("test_metric").with_field("value", 1));
+    report_operation(
+        reporter.clone(),
+        OperationRecord::new("test_operation".to_string()),
+        &Ok::<(), ()>(()),
+    );
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+}
+
+#[test]
+fn metrics_are_written_to_influx() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let (url, requests) = runtime.block_on(start_fake_influx(vec![]));
+    let spill_dir = tempfile::tempdir().unwrap();
+
+    let config = InfluxClientConfig::new(
+        url,
+        "test-org".to_string(),
+        "test-bucket".to_string(),
+        spill_dir.path().to_path_buf(),
+    )
+    .with_token("test-token".to_string());
+    report_metrics(config, &runtime);
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(1, requests.len());
+    assert!(
+        requests[0]
+            .request_line
+            .starts_with("POST /api/v2/write?org=test-org&bucket=test-bucket&precision=ns ")
+    );
+    assert_eq!(
+        Some("Token test-token"),
+        requests[0].authorization.as_deref()
+    );
+
+    let lines = requests[0].body.lines().collect::<Vec<_>>();
+    assert_eq!(2, lines.len());
+    assert!(lines[0].starts_with("wt.custom.test_metric,"));
+    assert!(lines[1].starts_with("wt.instruments.operation_duration,"));
+    assert!(lines.iter().all(|line| line.contains("run_id=test-run")));
+
+    // Nothing was spilled to disk.
+    assert_eq!(0, std::fs::read_dir(spill_dir.path()).unwrap().count());
+}
+
+#[test]
+fn failed_writes_are_retried() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let (url, requests) = runtime.block_on(start_fake_influx(vec![503, 503]));
+    let spill_dir = tempfile::tempdir().unwrap();
+
+    let config = InfluxClientConfig::new(
+        url,
+        "test-org".to_string(),
+        "test-bucket".to_string(),
+        spill_dir.path().to_path_buf(),
+    )
+    .with_retry(3, Duration::from_millis(10));
+    report_metrics(config, &runtime);
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(3, requests.len());
+    assert!(requests.iter().all(|r| r.body == requests[0].body));
+    assert_eq!(0, std::fs::read_dir(spill_dir.path()).unwrap().count());
+}
+
+#[test]
+fn metrics_are_spilled_to_disk_when_influx_is_unreachable() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // Find a free port, then stop listening on it so that connections are refused.
+    let url = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let spill_dir = tempfile::tempdir().unwrap();
+
+    let config = InfluxClientConfig::new(
+        url,
+        "test-org".to_string(),
+        "test-bucket".to_string(),
+        spill_dir.path().to_path_buf(),
+    )
+    .with_retry(2, Duration::from_millis(10));
+    report_metrics(config, &runtime);
+
+    let spilled = std::fs::read_dir(spill_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(1, spilled.len());
+    assert!(
+        spilled[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("test-scenario-")
+    );
+
+    let content = std::fs::read_to_string(&spilled[0]).unwrap();
+    assert_eq!(2, content.lines().count());
+    assert!(content.contains("wt.custom.test_metric"));
+}
+
+#[test]
+fn stalled_influx_does_not_hold_up_shutdown() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // Accept connections but never respond to them.
+    let url = runtime.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                connections.push(stream);
+            }
+        });
+        url
+    });
+    let spill_dir = tempfile::tempdir().unwrap();
+
+    let config = InfluxClientConfig::new(
+        url,
+        "test-org".to_string(),
+        "test-bucket".to_string(),
+        spill_dir.path().to_path_buf(),
+    )
+    // Without a limit on the time spent writing at shutdown, the retries would take over 10s
+    .with_retry(20, Duration::from_millis(10))
+    .with_request_timeout(Duration::from_millis(500));
+    let started = std::time::Instant::now();
+    report_metrics(config, &runtime);
+
+    assert!(
+        started.elapsed() < Duration::from_secs(5),
+        "Shutdown took {:?}",
+        started.elapsed()
+    );
+    let spilled = std::fs::read_dir(spill_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(1, spilled.len());
+    assert_eq!(
+        2,
+        std::fs::read_to_string(&spilled[0])
+            .unwrap()
+            .lines()
+            .count()
+    );
+}
@@ -0,0 +1,370 @@
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+use wind_tunnel_core::prelude::ShutdownHandle;
+use wind_tunnel_instruments::prelude::{
+    BackpressurePolicy, ClassifiedError, FileCompression, InfluxFileConfig, OperationRecord,
+    PipelineStats, ReportConfig, ReportMetric, report_operation,
+};
+
+fn metrics_files(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut files = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| !path.to_string_lossy().ends_with(".manifest.json"))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+fn manifest(dir: &std::path::Path) -> serde_json::Value {
+    let path = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().ends_with(".manifest.json"))
+        .expect("No manifest written");
+    serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn blocking_queue_writes_every_metric() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+        .enable_influx_file(
+            InfluxFileConfig::new(dir.path().to_path_buf())
+                .with_queue(1, BackpressurePolicy::Block),
+        )
+        .init_reporter(runtime.handle(), &shutdown_handle)
+        .unwrap();
+
+    for i in 0..500 {
+        reporter.add_custom(ReportMetric::new("test_metric").with_field("value", i));
+    }
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let files = metrics_files(dir.path());
+    assert_eq!(1, files.len());
+    assert_eq!(
+        500,
+        std::fs::read_to_string(&files[0]).unwrap().lines().count()
+    );
+    assert_eq!(PipelineStats::default(), reporter.pipeline_stats());
+}
+
+#[test]
+fn files_are_rotated_by_size_and_compressed() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+        .enable_influx_file(
+            InfluxFileConfig::new(dir.path().to_path_buf())
+                .with_max_file_size(1024)
+                .with_compression(FileCompression::Gzip),
+        )
+        .init_reporter(runtime.handle(), &shutdown_handle)
+        .unwrap();
+
+    for i in 0..500 {
+        reporter.add_custom(ReportMetric::new("test_metric").with_field("value", i));
+    }
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let files = metrics_files(dir.path());
+    assert!(files.len() > 1, "Expected several files, got {files:?}");
+
+    let mut lines = 0;
+    for file in &files {
+        assert!(file.to_string_lossy().ends_with(".influx.gz"));
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(file).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        lines += content.lines().count();
+    }
+    assert_eq!(500, lines);
+
+    let manifest = manifest(dir.path());
+    assert_eq!("test-run", manifest["run_id"]);
+    let segments = manifest["segments"].as_array().unwrap();
+    assert_eq!(files.len(), segments.len());
+    for (segment, file) in segments.iter().zip(&files) {
+        assert_eq!(
+            file.file_name().unwrap().to_string_lossy(),
+            segment["file"].as_str().unwrap()
+        );
+        assert_eq!(true, segment["complete"]);
+        assert_eq!(std::fs::metadata(file).unwrap().len(), segment["bytes"]);
+    }
+    assert_eq!(
+        500,
+        segments
+            .iter()
+            .map(|segment| segment["lines"].as_u64().unwrap())
+            .sum::<u64>()
+    );
+}
+
+#[test]
+fn files_are_rotated_by_interval() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+        .enable_influx_file(
+            InfluxFileConfig::new(dir.path().to_path_buf())
+                .with_flush_interval(Duration::from_millis(10))
+                .with_rotate_interval(Duration::from_millis(50))
+                .with_compression(FileCompression::Zstd),
+        )
+        .init_reporter(runtime.handle(), &shutdown_handle)
+        .unwrap();
+
+    reporter.add_custom(ReportMetric::new("test_metric").with_field("value", 1));
+    std::thread::sleep(Duration::from_millis(200));
+    reporter.add_custom(ReportMetric::new("test_metric").with_field("value", 2));
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let files = metrics_files(dir.path());
+    assert_eq!(2, files.len(), "Expected two files, got {files:?}");
+    for file in &files {
+        assert!(file.to_string_lossy().ends_with(".influx.zst"));
+        let content = zstd::decode_all(std::fs::File::open(file).unwrap()).unwrap();
+        assert_eq!(1, String::from_utf8(content).unwrap().lines().count());
+    }
+}
+
+#[test]
+fn failed_operations_are_written_with_error_kind() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = Arc::new(
+        ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+            .enable_influx_file(InfluxFileConfig::new(dir.path().to_path_buf()))
+            .init_reporter(runtime.handle(), &shutdown_handle)
+            .unwrap(),
+    );
+
+    report_operation(
+        reporter.clone(),
+        OperationRecord::new("test_operation".to_string()),
+        &Err::<(), _>(anyhow::Error::new(ClassifiedError::new(
+            "ribosome",
+            "Ribosome error",
+        ))),
+    );
+    report_operation(
+        reporter.clone(),
+        OperationRecord::new("test_operation".to_string()),
+        &Ok::<_, anyhow::Error>(()),
+    );
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let files = metrics_files(dir.path());
+    let content = std::fs::read_to_string(&files[0]).unwrap();
+    let lines = content.lines().collect::<Vec<_>>();
+    assert_eq!(2, lines.len());
+    assert!(lines[0].contains("error_kind=ribosome"), "{}", lines[0]);
+    assert!(
+        lines[0].contains(r#"error_message="Ribosome error""#),
+        "{}",
+        lines[0]
+    );
+    assert!(!lines[1].contains("error_kind"), "{}", lines[1]);
+
+    assert_eq!(
+        std::collections::BTreeMap::from([(
+            "test_operation".to_string(),
+            std::collections::BTreeMap::from([("ribosome".to_string(), 1)])
+        )]),
+        reporter.operation_errors()
+    );
+}
+
+#[test]
+fn full_queue_drops_metrics() {
+    // The write task never runs on this runtime, so nothing is taken off the queue.
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let reporter = ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+        .enable_influx_file(
+            InfluxFileConfig::new(dir.path().to_path_buf()).with_queue(2, BackpressurePolicy::Drop),
+        )
+        .init_reporter(runtime.handle(), &ShutdownHandle::new())
+        .unwrap();
+
+    for i in 0..5 {
+        reporter.add_custom(ReportMetric::new("test_metric").with_field("value", i));
+    }
+
+    assert_eq!(
+        PipelineStats {
+            dropped: 3,
+            failed: 0
+        },
+        reporter.pipeline_stats()
+    );
+}
+
+#[test]
+fn metrics_reported_after_shutdown_are_dropped() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = Arc::new(
+        ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+            .enable_influx_file(InfluxFileConfig::new(dir.path().to_path_buf()))
+            .init_reporter(runtime.handle(), &shutdown_handle)
+            .unwrap(),
+    );
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    reporter.add_custom(ReportMetric::new("test_metric").with_field("value", 1));
+
+    assert_eq!(
+        PipelineStats {
+            dropped: 1,
+            failed: 0
+        },
+        reporter.pipeline_stats()
+    );
+}
+
+#[test]
+fn metrics_that_cannot_be_written_are_counted_as_failed() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // A file where the metrics directory should be, so that the directory cannot be created.
+    let not_a_dir = tempfile::NamedTempFile::new().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+        .enable_influx_file(InfluxFileConfig::new(not_a_dir.path().join("metrics")))
+        .init_reporter(runtime.handle(), &shutdown_handle)
+        .unwrap();
+
+    reporter.add_custom(ReportMetric::new("test_metric").with_field("value", 1));
+    reporter.add_custom(ReportMetric::new("test_metric").with_field("value", 2));
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    assert_eq!(
+        PipelineStats {
+            dropped: 0,
+            failed: 2
+        },
+        reporter.pipeline_stats()
+    );
+}
+
+#[test]
+fn typed_metrics_are_aggregated_before_they_are_written() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+        .enable_influx_file(InfluxFileConfig::new(dir.path().to_path_buf()))
+        .init_reporter(runtime.handle(), &shutdown_handle)
+        .unwrap();
+
+    let counter = reporter.counter("sent", &[("agent", "agent-0")]);
+    let histogram = reporter.histogram("lag_s", &[]);
+    for i in 0..1000 {
+        counter.inc();
+        histogram.record(i as f64 / 1000.0);
+    }
+    reporter.gauge("open_connections", &[]).set(3);
+    reporter.flush_metrics();
+
+    // Only metrics that have changed are written on the next flush
+    counter.add(5);
+    reporter.flush_metrics();
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let files = metrics_files(dir.path());
+    let content = std::fs::read_to_string(&files[0]).unwrap();
+    let lines = content.lines().collect::<Vec<_>>();
+    assert_eq!(4, lines.len(), "{content}");
+
+    let sent = lines
+        .iter()
+        .filter(|line| line.starts_with("wt.custom.sent,"))
+        .collect::<Vec<_>>();
+    assert_eq!(2, sent.len());
+    assert!(sent[0].contains("agent=agent-0"));
+    assert!(sent[0].contains("count=1000i"), "{}", sent[0]);
+    assert!(sent[1].contains("count=5i"), "{}", sent[1]);
+    assert!(sent[1].contains("total=1005i"), "{}", sent[1]);
+
+    let lag = lines
+        .iter()
+        .find(|line| line.starts_with("wt.custom.lag_s"))
+        .unwrap();
+    for field in ["count=1000i", "p50=", "p95=", "p99=", "min=0", "max="] {
+        assert!(lag.contains(field), "{field} missing from {lag}");
+    }
+
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("wt.custom.open_connections") && line.contains("value=3"))
+    );
+}
+
+#[test]
+fn operations_are_written_with_start_time_and_clock_offset() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = Arc::new(
+        ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+            .enable_influx_file(InfluxFileConfig::new(dir.path().to_path_buf()))
+            .init_reporter(runtime.handle(), &shutdown_handle)
+            .unwrap(),
+    );
+    reporter.set_clock_offset(-1500);
+
+    let operation_record = OperationRecord::new("test_operation".to_string());
+    let started_at = operation_record
+        .started_at()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    report_operation(reporter.clone(), operation_record, &Ok::<(), ()>(()));
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let files = metrics_files(dir.path());
+    let content = std::fs::read_to_string(&files[0]).unwrap();
+    assert!(
+        content.contains(&format!("started_at_ns={started_at}")),
+        "{content}"
+    );
+    assert!(content.contains("clock_offset_ns=-1500i"), "{content}");
+}
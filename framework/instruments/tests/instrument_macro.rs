@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use wind_tunnel_core::prelude::ShutdownHandle;
+use wind_tunnel_instruments::prelude::{InfluxFileConfig, OperationRecord, ReportConfig, Reporter};
+use wind_tunnel_instruments_derive::wind_tunnel_instrument;
+
+struct Client {
+    reporter: Arc<Reporter>,
+}
+
+impl Client {
+    #[wind_tunnel_instrument(prefix = "client_", post_hook = post_fetch)]
+    async fn fetch(&self, count: usize) -> anyhow::Result<Vec<u8>> {
+        Ok(vec![0; count])
+    }
+}
+
+fn post_fetch(operation_record: &mut OperationRecord, result: &anyhow::Result<Vec<u8>>) {
+    if let Ok(response) = result {
+        operation_record.add_field("response_bytes", response.len() as u64);
+    }
+}
+
+#[wind_tunnel_instrument(reporter = reporter.clone())]
+fn parse(reporter: Arc<Reporter>, input: &str) -> anyhow::Result<u32> {
+    Ok(input.parse()?)
+}
+
+#[test]
+fn instrumented_functions_are_reported() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = Arc::new(
+        ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+            .enable_influx_file(InfluxFileConfig::new(dir.path().to_path_buf()))
+            .enable_operation_stats()
+            .init_reporter(runtime.handle(), &shutdown_handle)
+            .unwrap(),
+    );
+
+    let client = Client {
+        reporter: reporter.clone(),
+    };
+    runtime.block_on(client.fetch(42)).unwrap();
+    parse(reporter.clone(), "1").unwrap();
+    parse(reporter.clone(), "not a number").unwrap_err();
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let operation_stats = reporter.operation_stats();
+    assert_eq!(1, operation_stats["client_fetch"].count());
+    assert_eq!(2, operation_stats["parse"].count());
+    assert_eq!(1, operation_stats["parse"].error_count());
+
+    let metrics_file = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "influx"))
+        .unwrap();
+    let content = std::fs::read_to_string(metrics_file).unwrap();
+    let fetch = content
+        .lines()
+        .find(|line| line.contains("operation_id=client_fetch"))
+        .unwrap();
+    assert!(fetch.contains("response_bytes=42"), "{fetch}");
+}
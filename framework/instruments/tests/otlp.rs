@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use wind_tunnel_core::prelude::ShutdownHandle;
+use wind_tunnel_instruments::prelude::{
+    OperationRecord, OtlpConfig, OtlpProtocol, ReportConfig, ReportMetric, report_operation,
+};
+
+#[derive(Debug, Clone)]
+struct ExportRequest {
+    request_line: String,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+/// A stand-in for the OTLP/HTTP endpoint of an OpenTelemetry collector, which accepts every export.
+async fn start_fake_collector() -> (String, Arc<Mutex<Vec<ExportRequest>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    tokio::spawn({
+        let requests = requests.clone();
+        async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let requests = requests.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    loop {
+                        let mut request_line = String::new();
+                        if stream.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+
+                        let mut content_length = 0;
+                        let mut content_type = None;
+                        loop {
+                            let mut header = String::new();
+                            stream.read_line(&mut header).await.unwrap();
+                            let header = header.trim_end();
+                            if header.is_empty() {
+                                break;
+                            }
+                            let (name, value) = header.split_once(": ").unwrap();
+                            match name.to_lowercase().as_str() {
+                                "content-length" => content_length = value.parse().unwrap(),
+                                "content-type" => content_type = Some(value.to_string()),
+                                _ => {}
+                            }
+                        }
+
+                        let mut body = vec![0; content_length];
+                        stream.read_exact(&mut body).await.unwrap();
+                        requests.lock().unwrap().push(ExportRequest {
+                            request_line: request_line.trim_end().to_string(),
+                            content_type,
+                            body,
+                        });
+
+                        stream
+                            .write_all(
+                                b"HTTP/1.1 200 OK\r\nContent-Type: application/x-protobuf\r\nContent-Length: 0\r\n\r\n",
+                            )
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        }
+    });
+
+    (url, requests)
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+}
+
+#[test]
+fn metrics_are_exported_to_collector() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let (url, requests) = runtime.block_on(start_fake_collector());
+
+    let shutdown_handle = ShutdownHandle::new();
+    let reporter = Arc::new(
+        ReportConfig::new("test-run".to_string(), "test-scenario".to_string())
+            .enable_otlp(
+                OtlpConfig::new(OtlpProtocol::HttpProtobuf)
+                    .with_endpoint(url)
+                    .with_export_interval(Duration::from_secs(60)),
+            )
+            .init_reporter(runtime.handle(), &shutdown_handle)
+            .unwrap(),
+    );
+
+    reporter.add_custom(
+        ReportMetric::new("test_metric")
+            .with_tag("agent", "agent-1")
+            .with_field("value", 1)
+            .with_field("count", 2.5),
+    );
+    report_operation(
+        reporter.clone(),
+        OperationRecord::new("test_operation".to_string()),
+        &Ok::<(), ()>(()),
+    );
+
+    // Nothing is exported until the export interval or the reporter is finalized.
+    assert!(requests.lock().unwrap().is_empty());
+
+    shutdown_handle.shutdown();
+    reporter.finalize();
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(1, requests.len());
+    assert!(requests[0].request_line.starts_with("POST /v1/metrics "));
+    assert_eq!(
+        Some("application/x-protobuf"),
+        requests[0].content_type.as_deref()
+    );
+
+    let body = &requests[0].body;
+    for expected in [
+        "wt.instruments.operation_duration",
+        "test_operation",
+        "wt.custom.test_metric",
+        "wt.custom.test_metric.count",
+        "agent-1",
+        "test-run",
+        "test-scenario",
+    ] {
+        assert!(contains(body, expected), "Export is missing {expected}");
+    }
+}
@@ -9,6 +9,10 @@ struct InstrumentArgs {
     /// Prefix to apply to the function name when reporting the instrumented function
     prefix: Option<String>,
     pre_hook: Option<Ident>,
+    /// Called with the operation record and the result, before the operation is reported
+    post_hook: Option<Ident>,
+    /// Expression for the reporter to use, instead of `self.reporter`
+    reporter: Option<Expr>,
 }
 
 impl Parse for InstrumentArgs {
@@ -31,6 +35,14 @@ impl Parse for InstrumentArgs {
                     input.parse::<Token![=]>()?;
                     args.pre_hook = Some(input.parse()?);
                 }
+                "post_hook" => {
+                    input.parse::<Token![=]>()?;
+                    args.post_hook = Some(input.parse()?);
+                }
+                "reporter" => {
+                    input.parse::<Token![=]>()?;
+                    args.reporter = Some(input.parse()?);
+                }
                 _ => {
                     return Err(syn::Error::new(
                         key.span(),
@@ -55,21 +67,61 @@ impl Parse for InstrumentArgs {
     }
 }
 
+/// Report the duration and result of a function as an operation, named after the function.
+///
+/// Arguments:
+/// - `prefix = "..."`: a prefix for the operation ID.
+/// - `pre_hook = fn_name`: called with `&mut OperationRecord` and a reference to each argument,
+///   other than `self`, before the function body runs.
+/// - `post_hook = fn_name`: called with `&mut OperationRecord` and a reference to the result,
+///   before the operation is reported. Use this to add attributes or fields derived from the
+///   response.
+/// - `reporter = expr`: the `Arc<Reporter>` to report to. Defaults to `self.reporter.clone()`,
+///   so it must be set for free functions and types without a `reporter` field.
+///
+/// The function can be sync or async, and must return a `Result` whose error type implements
+/// `OperationError`. The operation is reported however the function returns, including early
+/// returns and `?`.
 #[proc_macro_attribute]
 pub fn wind_tunnel_instrument(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut input = parse_macro_input!(input as ItemFn);
 
     // Use a syntax tree traversal to transform the function body.
-    let InstrumentArgs { prefix, pre_hook } = parse_macro_input!(args as InstrumentArgs);
+    let InstrumentArgs {
+        prefix,
+        pre_hook,
+        post_hook,
+        reporter,
+    } = parse_macro_input!(args as InstrumentArgs);
+
+    let reporter = match reporter {
+        Some(reporter) => reporter,
+        None if input.sig.receiver().is_some() => parse_quote!(self.reporter.clone()),
+        None => {
+            return syn::Error::new(
+                input.sig.ident.span(),
+                "#[wind_tunnel_instrument] needs a `reporter = ...` argument for functions without `self`",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
 
     let target_name = prefix.unwrap_or("".to_string()) + &input.sig.ident.to_string();
 
-    input.block.stmts.insert(
-        0,
-        syn::parse_quote! {
-            let mut wt_operation_record = wind_tunnel_instruments::OperationRecord::new(#target_name.to_string());
-        },
-    );
+    let body = std::mem::take(&mut input.block.stmts);
+    let output: syn::Type = match &input.sig.output {
+        syn::ReturnType::Default => parse_quote!(()),
+        syn::ReturnType::Type(_, ty) => *ty.clone(),
+    };
+
+    input.block.stmts.push(syn::parse_quote! {
+        let mut wt_operation_record = wind_tunnel_instruments::OperationRecord::new(#target_name.to_string());
+    });
+    // Take the reporter before the body runs, in case the body moves what it is taken from.
+    input.block.stmts.push(syn::parse_quote! {
+        let wt_reporter: std::sync::Arc<wind_tunnel_instruments::Reporter> = #reporter;
+    });
 
     if let Some(pre_hook) = pre_hook {
         let args = input
@@ -103,20 +155,29 @@ pub fn wind_tunnel_instrument(args: TokenStream, input: TokenStream) -> TokenStr
                 },
             );
 
-        input.block.stmts.insert(
-            1,
-            syn::parse_quote! {
-                #pre_hook(&mut wt_operation_record, #args);
-            },
-        );
+        input.block.stmts.push(syn::parse_quote! {
+            #pre_hook(&mut wt_operation_record, #args);
+        });
     }
 
-    let result = input.block.stmts.pop().unwrap();
-    input.block.stmts.push(syn::parse_quote! {
-        let wt_result = #result;
-    });
+    // Run the body in its own block, so that the result is reported however the body returns,
+    // including early returns and `?`.
+    if input.sig.asyncness.is_some() {
+        input.block.stmts.push(syn::parse_quote! {
+            let wt_result: #output = async { #(#body)* }.await;
+        });
+    } else {
+        input.block.stmts.push(syn::parse_quote! {
+            let wt_result: #output = (|| -> #output { #(#body)* })();
+        });
+    }
+    if let Some(post_hook) = post_hook {
+        input.block.stmts.push(syn::parse_quote! {
+            #post_hook(&mut wt_operation_record, &wt_result);
+        });
+    }
     input.block.stmts.push(syn::parse_quote! {
-        wind_tunnel_instruments::report_operation(self.reporter.clone(), wt_operation_record, &wt_result);
+        wind_tunnel_instruments::report_operation(wt_reporter, wt_operation_record, &wt_result);
     });
     input.block.stmts.push(syn::parse_quote! {
         return wt_result;
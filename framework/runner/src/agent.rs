@@ -0,0 +1,480 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::barrier::SetupBarrier;
+use crate::context::{AgentContext, UserValuesConstraint};
+use crate::definition::{AgentHook, HookResult};
+use crate::failure::FailureTracker;
+use crate::schedule::{ArrivalSchedule, ScheduledSlot, wait_until, wait_until_async};
+use wind_tunnel_core::prelude::{AgentBailError, DelegatedShutdownListener, ShutdownSignalError};
+use wind_tunnel_instruments::Reporter;
+use wind_tunnel_instruments::prelude::{OperationRecord, ReportMetric, report_operation};
+use wind_tunnel_summary_model::{AgentOutcome, SetupOutcome};
+
+/// An agent and everything it needs to run, from activation to teardown.
+///
+/// Agents with a blocking behaviour are run on their own thread with [AgentRun::run_blocking].
+/// Agents with an async behaviour are run as a task on the shared runtime with [AgentRun::run_async].
+pub(crate) struct AgentRun<RV: UserValuesConstraint, V: UserValuesConstraint> {
+    pub(crate) context: AgentContext<RV, V>,
+    pub(crate) setup: Option<AgentHook<RV, V>>,
+    pub(crate) behaviour: Option<AgentHook<RV, V>>,
+    pub(crate) teardown: Option<AgentHook<RV, V>>,
+    /// When the agent is activated, and when it is drained if that is before the end of the run.
+    pub(crate) active_window: Option<(Instant, Option<Instant>)>,
+    pub(crate) schedule: Option<Arc<Mutex<ArrivalSchedule>>>,
+    pub(crate) setup_barrier: Option<Arc<SetupBarrier>>,
+    pub(crate) reporter: Arc<Reporter>,
+    pub(crate) failures: Arc<FailureTracker>,
+    /// For us to check if the agent should shut down between behaviour cycles
+    pub(crate) cycle_shutdown_receiver: DelegatedShutdownListener,
+    pub(crate) agents_run_to_completion: Arc<AtomicUsize>,
+    /// What has happened to the agent so far, returned when it stops
+    pub(crate) outcome: AgentOutcome,
+}
+
+/// What an agent should do after a behaviour iteration.
+enum IterationOutcome {
+    Continue,
+    /// Wait before running the behaviour again
+    Backoff(Duration),
+    /// Stop running the behaviour, for this reason
+    Bail(String),
+}
+
+/// When an agent should start its next behaviour iteration.
+enum NextIteration {
+    /// Straight away, because the behaviour is not scheduled
+    Now,
+    /// At the slot claimed from the behaviour's arrival schedule
+    At(ScheduledSlot),
+    /// Never, because the arrival schedule has ended
+    Finished,
+}
+
+impl<RV: UserValuesConstraint, V: UserValuesConstraint> AgentRun<RV, V> {
+    pub(crate) fn run_blocking(mut self) -> AgentOutcome {
+        let agent_name = self.context.agent_name().to_string();
+
+        if let Some((activate_at, _)) = self.active_window
+            && !wait_until(activate_at, &mut self.cycle_shutdown_receiver)
+        {
+            log::debug!("Agent {agent_name} stopped before it was activated");
+            return self.outcome;
+        }
+
+        let setup_started = Instant::now();
+        let setup_result = self
+            .setup
+            .as_ref()
+            .map(|hook| hook.call_blocking(&mut self.context));
+        if !self.complete_setup(setup_started, setup_result) {
+            self.teardown_blocking();
+            return self.outcome;
+        }
+
+        let released = self.setup_barrier.as_ref().is_none_or(|setup_barrier| {
+            setup_barrier
+                .wait(&mut self.cycle_shutdown_receiver)
+                .is_some()
+        });
+
+        let mut behaviour_ran_to_complete = true;
+        if !released {
+            log::debug!("Agent {agent_name} stopped while waiting for the setup barrier");
+            self.outcome.stopped_before_behaviour = true;
+            behaviour_ran_to_complete = false;
+        } else if let Some(behaviour) = &self.behaviour {
+            let mut consecutive_errors = 0;
+            loop {
+                if self.cycle_shutdown_receiver.should_shutdown() {
+                    log::debug!("Stopping agent {agent_name}");
+                    break;
+                }
+
+                if self.is_drained() {
+                    log::debug!("Draining agent {agent_name}");
+                    break;
+                }
+
+                if let Some(reason) = self.context.bail_reason() {
+                    self.outcome.bail_reason =
+                        Some(self.record_handle_bail(reason, consecutive_errors));
+                    behaviour_ran_to_complete = false;
+                    break;
+                }
+
+                let slot = match self.claim_slot() {
+                    NextIteration::Now => None,
+                    NextIteration::At(slot) => {
+                        if !wait_until(slot.intended_start, &mut self.cycle_shutdown_receiver) {
+                            log::debug!("Stopping agent {agent_name}");
+                            break;
+                        }
+                        Some((slot, Instant::now()))
+                    }
+                    NextIteration::Finished => {
+                        log::debug!("Arrival schedule ended for agent {agent_name}");
+                        break;
+                    }
+                };
+
+                let operation_record = self.start_iteration();
+                let result = behaviour.call_blocking(&mut self.context);
+                self.report_iteration(operation_record, &result);
+                if !interrupted_by_shutdown(&result) {
+                    self.outcome.iterations += 1;
+                }
+
+                if let Some((slot, started)) = slot {
+                    self.report_slot_iteration(slot, started);
+                }
+
+                match self.check_behaviour_result(result, &mut consecutive_errors) {
+                    IterationOutcome::Continue => {}
+                    IterationOutcome::Backoff(backoff) => {
+                        if !wait_until(Instant::now() + backoff, &mut self.cycle_shutdown_receiver)
+                        {
+                            log::debug!("Stopping agent {agent_name}");
+                            break;
+                        }
+                    }
+                    IterationOutcome::Bail(reason) => {
+                        self.outcome.bail_reason = Some(reason);
+                        behaviour_ran_to_complete = false;
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.teardown_blocking();
+        self.complete(behaviour_ran_to_complete);
+        self.outcome
+    }
+
+    pub(crate) async fn run_async(mut self) -> AgentOutcome {
+        let agent_name = self.context.agent_name().to_string();
+
+        if let Some((activate_at, _)) = self.active_window
+            && !wait_until_async(activate_at, &mut self.cycle_shutdown_receiver).await
+        {
+            log::debug!("Agent {agent_name} stopped before it was activated");
+            return self.outcome;
+        }
+
+        let setup_started = Instant::now();
+        let setup_result = match &self.setup {
+            Some(hook) => Some(hook.call(&mut self.context).await),
+            None => None,
+        };
+        if !self.complete_setup(setup_started, setup_result) {
+            self.teardown_async().await;
+            return self.outcome;
+        }
+
+        let released = match &self.setup_barrier {
+            Some(setup_barrier) => setup_barrier
+                .wait_async(&mut self.cycle_shutdown_receiver)
+                .await
+                .is_some(),
+            None => true,
+        };
+
+        let mut behaviour_ran_to_complete = true;
+        if !released {
+            log::debug!("Agent {agent_name} stopped while waiting for the setup barrier");
+            self.outcome.stopped_before_behaviour = true;
+            behaviour_ran_to_complete = false;
+        } else if let Some(behaviour) = &self.behaviour {
+            let mut consecutive_errors = 0;
+            loop {
+                if self.cycle_shutdown_receiver.should_shutdown() {
+                    log::debug!("Stopping agent {agent_name}");
+                    break;
+                }
+
+                if self.is_drained() {
+                    log::debug!("Draining agent {agent_name}");
+                    break;
+                }
+
+                if let Some(reason) = self.context.bail_reason() {
+                    self.outcome.bail_reason =
+                        Some(self.record_handle_bail(reason, consecutive_errors));
+                    behaviour_ran_to_complete = false;
+                    break;
+                }
+
+                let slot = match self.claim_slot() {
+                    NextIteration::Now => None,
+                    NextIteration::At(slot) => {
+                        if !wait_until_async(slot.intended_start, &mut self.cycle_shutdown_receiver)
+                            .await
+                        {
+                            log::debug!("Stopping agent {agent_name}");
+                            break;
+                        }
+                        Some((slot, Instant::now()))
+                    }
+                    NextIteration::Finished => {
+                        log::debug!("Arrival schedule ended for agent {agent_name}");
+                        break;
+                    }
+                };
+
+                // Cancel the behaviour if the runner shuts down, as `execute_in_place` would for a
+                // blocking behaviour.
+                let operation_record = self.start_iteration();
+                let result = tokio::select! {
+                    result = behaviour.call(&mut self.context) => result,
+                    _ = self.cycle_shutdown_receiver.wait_for_shutdown() => {
+                        log::debug!("Stopping agent {agent_name}");
+                        break;
+                    }
+                };
+                self.report_iteration(operation_record, &result);
+                if !interrupted_by_shutdown(&result) {
+                    self.outcome.iterations += 1;
+                }
+
+                if let Some((slot, started)) = slot {
+                    self.report_slot_iteration(slot, started);
+                }
+
+                match self.check_behaviour_result(result, &mut consecutive_errors) {
+                    IterationOutcome::Continue => {}
+                    IterationOutcome::Backoff(backoff) => {
+                        if !wait_until_async(
+                            Instant::now() + backoff,
+                            &mut self.cycle_shutdown_receiver,
+                        )
+                        .await
+                        {
+                            log::debug!("Stopping agent {agent_name}");
+                            break;
+                        }
+                    }
+                    IterationOutcome::Bail(reason) => {
+                        self.outcome.bail_reason = Some(reason);
+                        behaviour_ran_to_complete = false;
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.teardown_async().await;
+        self.complete(behaviour_ran_to_complete);
+        self.outcome
+    }
+
+    /// Record the outcome of the agent setup, arriving at the setup barrier if there is one.
+    ///
+    /// Returns `false` if the setup failed and the agent should not continue.
+    fn complete_setup(&mut self, setup_started: Instant, setup_result: Option<HookResult>) -> bool {
+        let agent_name = self.context.agent_name();
+
+        if let Some(setup_barrier) = &self.setup_barrier {
+            let setup_duration = setup_started.elapsed();
+            let arrived = setup_barrier.arrive(setup_duration);
+            if !arrived {
+                log::warn!(
+                    "Agent {agent_name} finished setup after the setup barrier was released"
+                );
+            }
+            self.reporter.add_custom(
+                ReportMetric::new("agent_setup")
+                    .with_tag("agent", agent_name.to_string())
+                    .with_field("duration_s", setup_duration.as_secs_f64())
+                    .with_field("success", !matches!(setup_result, Some(Err(_))))
+                    .with_field("missed_barrier", !arrived),
+            );
+        }
+
+        if let Some(Err(e)) = setup_result {
+            log::error!("Agent setup failed for agent {agent_name}: {e:?}");
+            self.outcome.setup = SetupOutcome::Failed(format!("{e:#}"));
+            return false;
+        }
+
+        self.outcome.setup = SetupOutcome::Succeeded;
+        true
+    }
+
+    fn is_drained(&self) -> bool {
+        matches!(self.active_window, Some((_, Some(drain_at))) if Instant::now() >= drain_at)
+    }
+
+    /// Claim the next slot from the open-model schedule, if the behaviour has one.
+    fn claim_slot(&self) -> NextIteration {
+        let Some(schedule) = &self.schedule else {
+            return NextIteration::Now;
+        };
+        let Some(slot) = schedule
+            .lock()
+            .expect("Arrival schedule lock poisoned")
+            .claim(Instant::now())
+        else {
+            return NextIteration::Finished;
+        };
+        if slot.missed > 0 {
+            self.report_missed_slots(slot.missed);
+        }
+
+        NextIteration::At(slot)
+    }
+
+    /// Handle the result of a behaviour iteration, counting and reporting errors and applying the
+    /// failure policy.
+    fn check_behaviour_result(
+        &self,
+        result: HookResult,
+        consecutive_errors: &mut usize,
+    ) -> IterationOutcome {
+        let agent_name = self.context.agent_name();
+        let e = match result {
+            Ok(()) => {
+                self.failures.record_success();
+                *consecutive_errors = 0;
+                return IterationOutcome::Continue;
+            }
+            Err(e) if e.is::<ShutdownSignalError>() => {
+                // Do nothing, this is expected if the agent is being shutdown.
+                // The check at the top of the loop will catch this and break out.
+                return IterationOutcome::Continue;
+            }
+            Err(e) => e,
+        };
+
+        *consecutive_errors += 1;
+        let kind = self.failures.record_error(&e);
+        self.report_behaviour_error(&kind, *consecutive_errors);
+
+        if e.is::<AgentBailError>() {
+            // A single agent has failed, we don't want to stop the whole
+            // scenario so warn and exit the loop.
+            log::warn!("Agent {agent_name} bailed: {e:?}");
+            return IterationOutcome::Bail(format!("{e:#}"));
+        }
+
+        log::error!(
+            "Agent behaviour [{}] failed for agent {agent_name}: {e:?}",
+            self.context.assigned_behaviour()
+        );
+
+        let policy = self.failures.policy();
+        if policy.should_bail(*consecutive_errors) {
+            log::warn!("Agent {agent_name} bailed after {consecutive_errors} consecutive errors");
+            return IterationOutcome::Bail(format!(
+                "{consecutive_errors} consecutive errors, the last was: {e:#}"
+            ));
+        }
+
+        match policy.backoff(*consecutive_errors) {
+            Some(backoff) => IterationOutcome::Backoff(backoff),
+            None => IterationOutcome::Continue,
+        }
+    }
+
+    fn teardown_blocking(&mut self) {
+        if let Some(hook) = &self.teardown
+            && let Err(e) = hook.call_blocking(&mut self.context)
+        {
+            log::error!(
+                "Agent teardown failed for agent {}: {e:?}",
+                self.context.agent_name()
+            );
+        }
+    }
+
+    async fn teardown_async(&mut self) {
+        if let Some(hook) = &self.teardown
+            && let Err(e) = hook.call(&mut self.context).await
+        {
+            log::error!(
+                "Agent teardown failed for agent {}: {e:?}",
+                self.context.agent_name()
+            );
+        }
+    }
+
+    fn complete(&self, behaviour_ran_to_complete: bool) {
+        if behaviour_ran_to_complete {
+            self.agents_run_to_completion.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Start timing a behaviour iteration, which is reported as the operation `behaviour.<name>`.
+    fn start_iteration(&self) -> OperationRecord {
+        let mut operation_record =
+            OperationRecord::new(format!("behaviour.{}", self.context.assigned_behaviour()));
+        operation_record.add_attr("agent", self.context.agent_name().to_string());
+        operation_record.add_attr("behaviour", self.context.assigned_behaviour().to_string());
+        operation_record
+    }
+
+    /// Report a behaviour iteration, unless it was interrupted by the runner shutting down.
+    fn report_iteration(&self, operation_record: OperationRecord, result: &HookResult) {
+        if interrupted_by_shutdown(result) {
+            return;
+        }
+
+        report_operation(self.reporter.clone(), operation_record, result);
+    }
+
+    /// Record an agent bailing through its [AgentBailHandle](crate::prelude::AgentBailHandle) as a
+    /// behaviour error, in the same way as a behaviour that returns an [AgentBailError].
+    fn record_handle_bail(&self, reason: String, consecutive_errors: usize) -> String {
+        let e = anyhow::Error::new(AgentBailError::new(reason));
+        let kind = self.failures.record_error(&e);
+        self.report_behaviour_error(&kind, consecutive_errors + 1);
+        log::warn!("Agent {} bailed: {e:?}", self.context.agent_name());
+
+        format!("{e:#}")
+    }
+
+    /// Report a behaviour error, with its kind and how many errors in a row the agent has had.
+    fn report_behaviour_error(&self, kind: &str, consecutive_errors: usize) {
+        self.reporter.add_custom(
+            ReportMetric::new("behaviour_error")
+                .with_tag("agent", self.context.agent_name().to_string())
+                .with_tag("behaviour", self.context.assigned_behaviour().to_string())
+                .with_tag("kind", kind.to_string())
+                .with_field("consecutive", consecutive_errors as u64),
+        );
+    }
+
+    /// Report an open-model iteration, measured against the time it was intended to start.
+    fn report_slot_iteration(&self, slot: ScheduledSlot, started: Instant) {
+        self.reporter.add_custom(
+            ReportMetric::new("open_model_iteration")
+                .with_tag("agent", self.context.agent_name().to_string())
+                .with_tag("behaviour", self.context.assigned_behaviour().to_string())
+                .with_field(
+                    "lateness_s",
+                    started
+                        .saturating_duration_since(slot.intended_start)
+                        .as_secs_f64(),
+                )
+                .with_field("latency_s", slot.intended_start.elapsed().as_secs_f64()),
+        );
+    }
+
+    /// Report open-model iterations that were dropped because they fell too far behind schedule.
+    fn report_missed_slots(&self, missed: usize) {
+        self.reporter.add_custom(
+            ReportMetric::new("open_model_missed")
+                .with_tag("agent", self.context.agent_name().to_string())
+                .with_tag("behaviour", self.context.assigned_behaviour().to_string())
+                .with_field("count", missed as u64),
+        );
+    }
+}
+
+/// Whether a behaviour iteration was interrupted by the runner shutting down, rather than
+/// finishing.
+fn interrupted_by_shutdown(result: &HookResult) -> bool {
+    matches!(result, Err(e) if e.is::<ShutdownSignalError>())
+}
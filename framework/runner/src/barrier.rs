@@ -0,0 +1,183 @@
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use wind_tunnel_core::prelude::DelegatedShutdownListener;
+
+/// Holds agents after their setup until every agent has finished its setup, or the timeout has
+/// passed.
+pub(crate) struct SetupBarrier {
+    expected: usize,
+    deadline: Instant,
+    state: Mutex<BarrierState>,
+    released: Condvar,
+    released_async: Notify,
+}
+
+#[derive(Default)]
+struct BarrierState {
+    setup_durations: Vec<Duration>,
+    released: bool,
+}
+
+/// The outcome of a [SetupBarrier] once it has been released.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BarrierRelease {
+    /// The setup durations of the agents that arrived before the barrier was released.
+    pub(crate) setup_durations: Vec<Duration>,
+    /// The number of agents that had not finished their setup when the barrier was released.
+    pub(crate) missed: usize,
+}
+
+impl SetupBarrier {
+    pub(crate) fn new(expected: usize, timeout: Duration) -> Self {
+        Self {
+            expected,
+            deadline: Instant::now() + timeout,
+            state: Mutex::new(BarrierState::default()),
+            released: Condvar::new(),
+            released_async: Notify::new(),
+        }
+    }
+
+    /// Record that an agent has finished its setup, whether it succeeded or failed.
+    ///
+    /// Returns `false` if the barrier had already been released, so the agent missed it.
+    pub(crate) fn arrive(&self, setup_duration: Duration) -> bool {
+        let mut state = self.state.lock().expect("Setup barrier lock poisoned");
+        if state.released {
+            return false;
+        }
+
+        state.setup_durations.push(setup_duration);
+        if state.setup_durations.len() >= self.expected {
+            self.release(&mut state);
+        }
+
+        true
+    }
+
+    /// Block the current thread until the barrier is released, checking for shutdown while
+    /// waiting.
+    ///
+    /// Returns [None] if the runner is shutting down before the barrier is released.
+    pub(crate) fn wait(
+        &self,
+        shutdown_listener: &mut DelegatedShutdownListener,
+    ) -> Option<BarrierRelease> {
+        let mut state = self.state.lock().expect("Setup barrier lock poisoned");
+        loop {
+            if let Some(release) = self.check_release(&mut state) {
+                return Some(release);
+            }
+
+            if shutdown_listener.should_shutdown() {
+                return None;
+            }
+
+            let timeout = self
+                .deadline
+                .saturating_duration_since(Instant::now())
+                .min(Duration::from_millis(100));
+            state = self
+                .released
+                .wait_timeout(state, timeout)
+                .expect("Setup barrier lock poisoned")
+                .0;
+        }
+    }
+
+    /// Wait from an agent task until the barrier is released, or until the runner is shutting down.
+    ///
+    /// Returns [None] if the runner is shutting down before the barrier is released.
+    pub(crate) async fn wait_async(
+        &self,
+        shutdown_listener: &mut DelegatedShutdownListener,
+    ) -> Option<BarrierRelease> {
+        loop {
+            // Register for the release before checking, so that a release in between is not missed.
+            let released = self.released_async.notified();
+            {
+                let mut state = self.state.lock().expect("Setup barrier lock poisoned");
+                if let Some(release) = self.check_release(&mut state) {
+                    return Some(release);
+                }
+            }
+
+            tokio::select! {
+                _ = released => {}
+                _ = tokio::time::sleep_until(self.deadline.into()) => {}
+                _ = shutdown_listener.wait_for_shutdown() => return None,
+            }
+        }
+    }
+
+    /// Check whether the barrier has been released, releasing it if the timeout has passed.
+    fn check_release(&self, state: &mut BarrierState) -> Option<BarrierRelease> {
+        if !state.released && Instant::now() >= self.deadline {
+            self.release(state);
+        }
+
+        state.released.then(|| BarrierRelease {
+            setup_durations: state.setup_durations.clone(),
+            missed: self.expected.saturating_sub(state.setup_durations.len()),
+        })
+    }
+
+    fn release(&self, state: &mut BarrierState) {
+        state.released = true;
+        self.released.notify_all();
+        self.released_async.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use wind_tunnel_core::prelude::ShutdownHandle;
+
+    #[test]
+    fn barrier_releases_when_all_agents_arrive() {
+        let shutdown_handle = ShutdownHandle::default();
+        let barrier = Arc::new(SetupBarrier::new(2, Duration::from_secs(10)));
+
+        assert!(barrier.arrive(Duration::from_millis(10)));
+        let waiter = std::thread::spawn({
+            let barrier = barrier.clone();
+            let mut listener = shutdown_handle.new_listener();
+            move || barrier.wait(&mut listener)
+        });
+        assert!(barrier.arrive(Duration::from_millis(20)));
+
+        let release = waiter.join().unwrap().unwrap();
+        assert_eq!(0, release.missed);
+        assert_eq!(
+            vec![Duration::from_millis(10), Duration::from_millis(20)],
+            release.setup_durations
+        );
+    }
+
+    #[test]
+    fn barrier_releases_at_timeout_and_counts_missed_agents() {
+        let shutdown_handle = ShutdownHandle::default();
+        let barrier = SetupBarrier::new(3, Duration::from_millis(200));
+
+        assert!(barrier.arrive(Duration::from_millis(10)));
+        let release = barrier.wait(&mut shutdown_handle.new_listener()).unwrap();
+        assert_eq!(2, release.missed);
+
+        // Agents that finish after the barrier has been released have missed it.
+        assert!(!barrier.arrive(Duration::from_millis(300)));
+    }
+
+    #[test]
+    fn barrier_wait_stops_on_shutdown() {
+        let shutdown_handle = ShutdownHandle::default();
+        let barrier = SetupBarrier::new(2, Duration::from_secs(10));
+
+        let mut listener = shutdown_handle.new_listener();
+        shutdown_handle.shutdown();
+
+        assert_eq!(None, barrier.wait(&mut listener));
+    }
+}
@@ -1,4 +1,7 @@
+use crate::profile::LoadStage;
 use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(about, long_about = None)]
@@ -30,6 +33,42 @@ pub struct WindTunnelScenarioCli {
     #[clap(long)]
     pub duration: Option<u64>,
 
+    /// The number of seconds at the start of the run to treat as a warmup.
+    ///
+    /// Operations and custom metrics recorded during the warmup are tagged with `phase=warmup` so that
+    /// they can be excluded from the results. The warmup is included in the duration of the run.
+    #[clap(long)]
+    pub warmup: Option<u64>,
+
+    /// Add a stage to the load profile, to activate agents gradually rather than all at once.
+    /// Specify the stage in the format `target_agents:ramp_s:hold_s`. For example `--load-stage=10:0:60`.
+    ///
+    /// Over the ramp, the number of active agents moves linearly from the target of the previous stage
+    /// to the target of this stage. The target is then held for the hold time. The ramp and hold times
+    /// are optional and default to 0.
+    ///
+    /// Specify the flag multiple times to build up a profile. For example
+    /// `--load-stage=10:0:60 --load-stage=50:120:300 --load-stage=0:30`.
+    ///
+    /// This overrides any load profile configured by the scenario. If no duration is set, then the
+    /// scenario runs for the total duration of the load profile.
+    #[clap(long, value_parser = parse_load_stage)]
+    pub load_stage: Vec<LoadStage>,
+
+    /// A TOML or JSON file of parameters for the scenario. The file is read as JSON if it has a
+    /// `.json` extension and as TOML otherwise.
+    ///
+    /// Only parameters that are declared by the scenario may be set.
+    #[clap(long)]
+    pub params: Option<PathBuf>,
+
+    /// Set a scenario parameter, in the format `key=value`. For example `--param=send_interval_ms=500`.
+    ///
+    /// Specify the flag multiple times to set multiple parameters. This overrides any value set in
+    /// the `--params` file.
+    #[clap(long, value_parser = parse_param)]
+    pub param: Vec<(String, String)>,
+
     /// Run this test as a soak test, ignoring any configured duration and continuing to run until stopped
     #[clap(long, default_value = "false")]
     pub soak: bool,
@@ -40,9 +79,35 @@ pub struct WindTunnelScenarioCli {
     #[clap(long, default_value = "false")]
     pub no_progress: bool,
 
-    /// The reporter to use.
-    #[arg(long, value_enum, default_value_t = ReporterOpt::InMemory)]
-    pub reporter: ReporterOpt,
+    /// The reporters to use. Specify the flag multiple times, or separate reporters with commas, to
+    /// enable several reporters at once. For example `--reporter=influx-file,in-memory`.
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [ReporterOpt::InMemory])]
+    pub reporter: Vec<ReporterOpt>,
+
+    /// A TOML or JSON file that lists the reporters to use, with options for each reporter. The file
+    /// is read as JSON if it has a `.json` extension and as TOML otherwise.
+    ///
+    /// This replaces the reporters selected with `--reporter`.
+    #[arg(long, conflicts_with = "reporter")]
+    pub reporter_config: Option<PathBuf>,
+
+    /// The directory to write metrics files to, for reporters that do not have a directory set in
+    /// the reporter config.
+    #[arg(long, env = "WT_METRICS_DIR")]
+    pub metrics_dir: Option<PathBuf>,
+
+    /// An NTP server to measure the offset of this machine's clock against, as `host` or
+    /// `host:port`, so that timings can be compared across machines.
+    ///
+    /// The offset is recorded in the run summary and on each reported operation.
+    #[arg(long, env = "WT_TIME_SERVER")]
+    pub time_server: Option<String>,
+
+    /// The file to append the summary of this run to.
+    ///
+    /// If not set, the summary is appended to `run_summary.jsonl` in the working directory.
+    #[arg(long, env = "RUN_SUMMARY_PATH")]
+    pub run_summary_path: Option<PathBuf>,
 
     /// Set the ID of this run
     ///
@@ -59,8 +124,12 @@ pub enum ReporterOpt {
     InMemory,
     /// Recommended during scenario development for quick feedback with no extra services needed. All metrics including custom metrics will be printed to the console.
     InMemoryWithCustomMetrics,
-    /// Recommended for running distributed tests. Metrics will be written to a local influx file that can later be imported to InfluxDB.
+    /// Recommended for running distributed tests. Metrics will be written to a local influx file in the metrics directory that can later be imported to InfluxDB.
     InfluxFile,
+    /// Metrics will be sent to InfluxDB over HTTP while the scenario runs, so that it can be watched live. Requires `INFLUX_HOST`, `INFLUX_BUCKET` and `INFLUX_TOKEN` to be set, and `INFLUX_ORG` if the organisation is not `holo`. Metrics that cannot be sent are written to a local influx file in the metrics directory.
+    InfluxClient,
+    /// Metrics will be exported to an OpenTelemetry collector using OTLP. The collector is configured with the standard `OTEL_EXPORTER_OTLP_ENDPOINT` and `OTEL_EXPORTER_OTLP_PROTOCOL` environment variables, where the protocol is `http/protobuf` (the default) or `grpc`.
+    Otlp,
 }
 
 pub fn parse_agent_behaviour(s: &str) -> anyhow::Result<(String, usize)> {
@@ -77,3 +146,121 @@ pub fn parse_agent_behaviour(s: &str) -> anyhow::Result<(String, usize)> {
 
     Ok((name, count))
 }
+
+pub fn parse_load_stage(s: &str) -> anyhow::Result<LoadStage> {
+    let mut parts = s.split(':');
+    let target_agents = parts
+        .next()
+        .ok_or(anyhow::anyhow!(
+            "No target agent count specified for load stage"
+        ))?
+        .parse::<usize>()
+        .map_err(|e| anyhow::anyhow!("Invalid target agent count for load stage: {e}"))?;
+
+    let mut parse_secs = |name: &str| -> anyhow::Result<Duration> {
+        parts
+            .next()
+            .map(|s| s.parse::<u64>())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid {name} time for load stage: {e}"))
+            .map(|secs| Duration::from_secs(secs.unwrap_or(0)))
+    };
+    let ramp = parse_secs("ramp")?;
+    let hold = parse_secs("hold")?;
+
+    Ok(LoadStage {
+        target_agents,
+        ramp,
+        hold,
+    })
+}
+
+pub fn parse_param(s: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or(anyhow::anyhow!("Parameter must be in the format key=value"))?;
+    if key.is_empty() {
+        anyhow::bail!("No name specified for parameter");
+    }
+
+    Ok((key.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_param_splits_on_first_equals() {
+        let (key, value) = parse_param("filter=a=b").unwrap();
+        assert_eq!("filter", key);
+        assert_eq!("a=b", value);
+    }
+
+    #[test]
+    fn parse_param_requires_key_and_value() {
+        assert!(parse_param("no_value").is_err());
+        assert!(parse_param("=1").is_err());
+    }
+
+    #[test]
+    fn parse_load_stage_full() {
+        let stage = parse_load_stage("10:30:60").unwrap();
+
+        assert_eq!(10, stage.target_agents);
+        assert_eq!(Duration::from_secs(30), stage.ramp);
+        assert_eq!(Duration::from_secs(60), stage.hold);
+    }
+
+    #[test]
+    fn parse_load_stage_defaults() {
+        let stage = parse_load_stage("5").unwrap();
+
+        assert_eq!(5, stage.target_agents);
+        assert_eq!(Duration::ZERO, stage.ramp);
+        assert_eq!(Duration::ZERO, stage.hold);
+    }
+
+    #[test]
+    fn parse_load_stage_invalid() {
+        assert!(parse_load_stage("").is_err());
+        assert!(parse_load_stage("ten:30").is_err());
+        assert!(parse_load_stage("10:thirty").is_err());
+    }
+
+    #[test]
+    fn reporters_can_be_combined() {
+        let cli =
+            WindTunnelScenarioCli::try_parse_from(["scenario", "--reporter=influx-file,in-memory"])
+                .unwrap();
+        assert!(cli.reporter == [ReporterOpt::InfluxFile, ReporterOpt::InMemory]);
+
+        let cli = WindTunnelScenarioCli::try_parse_from([
+            "scenario",
+            "--reporter=influx-file",
+            "--reporter=in-memory",
+        ])
+        .unwrap();
+        assert!(cli.reporter == [ReporterOpt::InfluxFile, ReporterOpt::InMemory]);
+
+        let cli = WindTunnelScenarioCli::try_parse_from(["scenario"]).unwrap();
+        assert!(cli.reporter == [ReporterOpt::InMemory]);
+
+        let cli =
+            WindTunnelScenarioCli::try_parse_from(["scenario", "--reporter=otlp,influx-file"])
+                .unwrap();
+        assert!(cli.reporter == [ReporterOpt::Otlp, ReporterOpt::InfluxFile]);
+    }
+
+    #[test]
+    fn reporter_config_replaces_reporter() {
+        assert!(
+            WindTunnelScenarioCli::try_parse_from([
+                "scenario",
+                "--reporter=influx-file",
+                "--reporter-config=reporters.toml",
+            ])
+            .is_err()
+        );
+    }
+}
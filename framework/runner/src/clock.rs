@@ -0,0 +1,190 @@
+use anyhow::Context;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wind_tunnel_summary_model::ClockOffset;
+
+/// The port that NTP servers listen on, used if the time server does not include a port.
+const NTP_PORT: u16 = 123;
+
+/// Seconds from the NTP epoch, 1900-01-01, to the Unix epoch.
+const NTP_UNIX_EPOCH_OFFSET_S: u64 = 2_208_988_800;
+
+/// The number of exchanges with the time server. The offset from the exchange with the shortest
+/// round trip is used, as it is the most accurate.
+const SAMPLES: usize = 4;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Measure the offset of this machine's clock from an NTP time server, with a simple (SNTP)
+/// exchange.
+pub(crate) fn measure_clock_offset(time_server: &str) -> anyhow::Result<ClockOffset> {
+    let address = resolve(time_server)?;
+    let socket = UdpSocket::bind(match address {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    })?;
+    socket.connect(address)?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+
+    let mut best: Option<(i64, u64)> = None;
+    let mut last_error = None;
+    for _ in 0..SAMPLES {
+        match exchange(&socket) {
+            Ok((offset_ns, round_trip_ns)) => {
+                if best.is_none_or(|(_, best_round_trip_ns)| round_trip_ns < best_round_trip_ns) {
+                    best = Some((offset_ns, round_trip_ns));
+                }
+            }
+            Err(e) => {
+                // Don't wait for every sample to time out if the server is unreachable.
+                last_error = Some(e);
+                break;
+            }
+        }
+    }
+
+    match (best, last_error) {
+        (Some((offset_ns, round_trip_ns)), _) => Ok(ClockOffset {
+            time_server: time_server.to_string(),
+            offset_ns,
+            round_trip_ns,
+        }),
+        (None, Some(e)) => Err(e),
+        (None, None) => anyhow::bail!("No exchanges with time server {time_server}"),
+    }
+}
+
+fn resolve(time_server: &str) -> anyhow::Result<SocketAddr> {
+    time_server
+        .to_socket_addrs()
+        .or_else(|_| (time_server, NTP_PORT).to_socket_addrs())
+        .with_context(|| format!("Could not resolve time server {time_server}"))?
+        .next()
+        .with_context(|| format!("No addresses found for time server {time_server}"))
+}
+
+/// Exchange one request and response with the time server, returning the clock offset and the
+/// round trip time in nanoseconds.
+fn exchange(socket: &UdpSocket) -> anyhow::Result<(i64, u64)> {
+    let mut request = [0u8; 48];
+    // Leap indicator 0, version 4, mode 3 (client)
+    request[0] = 0b00_100_011;
+    let sent_at = SystemTime::now();
+    let originate = to_ntp_timestamp(sent_at);
+    request[40..48].copy_from_slice(&originate.to_be_bytes());
+    socket.send(&request)?;
+
+    let mut response = [0u8; 48];
+    let received = socket.recv(&mut response)?;
+    let received_at = SystemTime::now();
+    if received < 48 {
+        anyhow::bail!("Time server response was too short");
+    }
+    if response[0] & 0b111 != 4 {
+        anyhow::bail!("Time server response was not from a server");
+    }
+    if response[1] == 0 {
+        anyhow::bail!("Time server refused the request");
+    }
+    if read_timestamp(&response, 24) != originate {
+        anyhow::bail!("Time server response does not match the request");
+    }
+
+    // t0 and t3 by this machine's clock, t1 and t2 by the server's clock
+    let t0 = unix_nanos(sent_at);
+    let t1 = from_ntp_timestamp(read_timestamp(&response, 32));
+    let t2 = from_ntp_timestamp(read_timestamp(&response, 40));
+    let t3 = unix_nanos(received_at);
+
+    let offset = ((t1 - t0) + (t2 - t3)) / 2;
+    let round_trip = ((t3 - t0) - (t2 - t1)).max(0);
+
+    Ok((offset as i64, round_trip as u64))
+}
+
+fn read_timestamp(packet: &[u8; 48], offset: usize) -> u64 {
+    u64::from_be_bytes(packet[offset..offset + 8].try_into().expect("8 bytes"))
+}
+
+fn unix_nanos(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    }
+}
+
+/// Convert a time to an NTP timestamp, with whole seconds in the upper 32 bits and the fraction of
+/// a second in the lower 32 bits.
+fn to_ntp_timestamp(time: SystemTime) -> u64 {
+    let since_unix_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_unix_epoch.as_secs() + NTP_UNIX_EPOCH_OFFSET_S;
+    let fraction = ((since_unix_epoch.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (seconds << 32) | fraction
+}
+
+/// Convert an NTP timestamp to nanoseconds since the Unix epoch.
+fn from_ntp_timestamp(timestamp: u64) -> i128 {
+    let seconds = (timestamp >> 32) as i128 - NTP_UNIX_EPOCH_OFFSET_S as i128;
+    let nanos = ((timestamp & 0xffff_ffff) as i128 * 1_000_000_000) >> 32;
+    seconds * 1_000_000_000 + nanos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a time server whose clock is `ahead` of this machine's clock, for one request per
+    /// sample.
+    fn run_time_server(ahead: Duration) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for _ in 0..SAMPLES {
+                let mut request = [0u8; 48];
+                let (_, client) = socket.recv_from(&mut request).unwrap();
+                let now = to_ntp_timestamp(SystemTime::now() + ahead);
+
+                let mut response = [0u8; 48];
+                // Version 4, mode 4 (server), stratum 1
+                response[0] = 0b00_100_100;
+                response[1] = 1;
+                response[24..32].copy_from_slice(&request[40..48]);
+                response[32..40].copy_from_slice(&now.to_be_bytes());
+                response[40..48].copy_from_slice(&now.to_be_bytes());
+                socket.send_to(&response, client).unwrap();
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn ntp_timestamps_round_trip() {
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 500_000_000);
+        let nanos = from_ntp_timestamp(to_ntp_timestamp(time));
+        assert!((nanos - unix_nanos(time)).abs() < 10, "{nanos}");
+    }
+
+    #[test]
+    fn measures_offset_from_time_server() {
+        let address = run_time_server(Duration::from_secs(5));
+
+        let clock_offset = measure_clock_offset(&address.to_string()).unwrap();
+
+        assert_eq!(address.to_string(), clock_offset.time_server);
+        let offset = Duration::from_nanos(clock_offset.offset_ns as u64);
+        assert!(
+            offset > Duration::from_millis(4_900) && offset < Duration::from_millis(5_100),
+            "{offset:?}"
+        );
+        assert!(clock_offset.round_trip_ns < Duration::from_secs(1).as_nanos() as u64);
+    }
+
+    #[test]
+    fn unreachable_time_server_fails() {
+        // Bind a socket that never replies, so the exchange times out.
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+
+        assert!(measure_clock_offset(&address.to_string()).is_err());
+    }
+}
@@ -1,10 +1,39 @@
 use crate::executor::Executor;
+use crate::params::ScenarioParams;
+use crate::shutdown::ShutdownReasonCell;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 use std::{fmt::Debug, sync::Arc};
 use wind_tunnel_core::prelude::{DelegatedShutdownListener, ShutdownHandle};
-use wind_tunnel_instruments::Reporter;
+use wind_tunnel_instruments::{Counter, Gauge, Histogram, Reporter, Transaction};
+use wind_tunnel_summary_model::ShutdownReason;
 
 pub trait UserValuesConstraint: Default + Debug + Send + Sync + 'static {}
 
+/// A handle that lets code running outside of an agent's hooks make the agent bail, for example a
+/// background task that watches a service the agent depends on.
+///
+/// The agent bails before its next behaviour iteration, in the same way as if the behaviour had
+/// returned an [AgentBailError](wind_tunnel_core::prelude::AgentBailError).
+#[derive(Debug, Clone, Default)]
+pub struct AgentBailHandle(Arc<Mutex<Option<String>>>);
+
+impl AgentBailHandle {
+    /// Make the agent bail, with a message that explains why.
+    ///
+    /// If this is called more than once, the first message is kept.
+    pub fn bail(&self, reason: impl Into<String>) {
+        self.0
+            .lock()
+            .expect("Agent bail lock poisoned")
+            .get_or_insert_with(|| reason.into());
+    }
+
+    pub(crate) fn reason(&self) -> Option<String> {
+        self.0.lock().expect("Agent bail lock poisoned").clone()
+    }
+}
+
 /// The context created by the runner for a scenario run. This context is visible to all agents
 /// so it is read-only from within agent hooks but can be modified by global hooks.
 ///
@@ -14,8 +43,11 @@ pub struct RunnerContext<RV: UserValuesConstraint> {
     executor: Arc<Executor>,
     reporter: Arc<Reporter>,
     shutdown_handle: ShutdownHandle,
+    shutdown_reason: ShutdownReasonCell,
     run_id: String,
     connection_string: Option<String>,
+    params: ScenarioParams,
+    config_overlays: Mutex<BTreeMap<String, serde_json::Value>>,
     value: RV,
 }
 
@@ -24,15 +56,20 @@ impl<RV: UserValuesConstraint> RunnerContext<RV> {
         executor: Arc<Executor>,
         reporter: Arc<Reporter>,
         shutdown_handle: ShutdownHandle,
+        shutdown_reason: ShutdownReasonCell,
         run_id: String,
         connection_string: Option<String>,
+        params: ScenarioParams,
     ) -> Self {
         Self {
             executor,
             reporter,
             shutdown_handle,
+            shutdown_reason,
             run_id,
             connection_string,
+            params,
+            config_overlays: Default::default(),
             value: Default::default(),
         }
     }
@@ -52,6 +89,25 @@ impl<RV: UserValuesConstraint> RunnerContext<RV> {
         self.reporter.clone()
     }
 
+    /// Get a [Counter] for the scenario, reported as the custom metric `name`.
+    ///
+    /// Typed metrics are aggregated by the reporter and reported periodically, so they are cheaper
+    /// to update at high rates than reporting a [ReportMetric](wind_tunnel_instruments::prelude::ReportMetric)
+    /// for every event. Use [AgentContext::counter] for a counter tagged with the agent.
+    pub fn counter(&self, name: &str) -> Counter {
+        self.reporter.counter(name, &[])
+    }
+
+    /// Get a [Gauge] for the scenario, reported as the custom metric `name`.
+    pub fn gauge(&self, name: &str) -> Gauge {
+        self.reporter.gauge(name, &[])
+    }
+
+    /// Get a [Histogram] for the scenario, reported as the custom metric `name`.
+    pub fn histogram(&self, name: &str) -> Histogram {
+        self.reporter.histogram(name, &[])
+    }
+
     /// Get a new shutdown listener that will be triggered when the runner is shutdown.
     ///
     /// This is provided in case you are doing something unexpected and need to hook into the shutdown process.
@@ -70,6 +126,33 @@ impl<RV: UserValuesConstraint> RunnerContext<RV> {
         self.connection_string.as_deref()
     }
 
+    /// The parameters for the scenario run, as declared by
+    /// [ScenarioDefinitionBuilder::add_param](crate::prelude::ScenarioDefinitionBuilder::add_param).
+    pub fn params(&self) -> &ScenarioParams {
+        &self.params
+    }
+
+    /// Record a configuration overlay that the scenario applied on top of its defaults, such as
+    /// overrides for the config of a service that the scenario starts.
+    ///
+    /// Overlays are recorded in the run summary by name and are part of its fingerprint, so that
+    /// runs with different configuration are not compared with each other. Recording an overlay
+    /// with the same name again replaces it.
+    pub fn record_config_overlay(&self, name: impl Into<String>, overlay: serde_json::Value) {
+        self.config_overlays
+            .lock()
+            .expect("Config overlays lock poisoned")
+            .insert(name.into(), overlay);
+    }
+
+    /// The configuration overlays recorded with [Self::record_config_overlay].
+    pub(crate) fn config_overlays(&self) -> BTreeMap<String, serde_json::Value> {
+        self.config_overlays
+            .lock()
+            .expect("Config overlays lock poisoned")
+            .clone()
+    }
+
     /// Get mutable access to the user-defined state for the runner.
     pub fn get_mut(&mut self) -> &mut RV {
         &mut self.value
@@ -80,13 +163,19 @@ impl<RV: UserValuesConstraint> RunnerContext<RV> {
         &self.value
     }
 
+    /// Why the scenario was shut down, once it has been.
+    pub(crate) fn shutdown_reason(&self) -> &ShutdownReasonCell {
+        &self.shutdown_reason
+    }
+
     /// Force stop the scenario.
     ///
     /// This will trigger shutdown of all agents and the runner. It is primarily exposed for testing
     /// but if you need to stop the scenario from within a hook, you can use this. It is a better
     /// alternative to using a panic if you really need the scenario to stop.
     pub fn force_stop_scenario(&self) {
-        self.shutdown_handle.shutdown();
+        self.shutdown_reason
+            .shutdown(&self.shutdown_handle, ShutdownReason::ForceStopped);
     }
 }
 
@@ -105,6 +194,7 @@ pub struct AgentContext<RV: UserValuesConstraint, V: UserValuesConstraint> {
     assigned_behaviour: String,
     runner_context: Arc<RunnerContext<RV>>,
     shutdown_listener: DelegatedShutdownListener,
+    bail_handle: AgentBailHandle,
     value: V,
 }
 
@@ -122,6 +212,7 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> AgentContext<RV, V> {
             assigned_behaviour,
             runner_context,
             shutdown_listener,
+            bail_handle: AgentBailHandle::default(),
             value: Default::default(),
         }
     }
@@ -147,6 +238,48 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> AgentContext<RV, V> {
         &self.assigned_behaviour
     }
 
+    /// Start a [Transaction], to report several operations as one with an end-to-end duration.
+    ///
+    /// The transaction is tagged with the agent name and assigned behaviour, and is reported as
+    /// `transaction.<name>` when it is finished.
+    pub fn start_transaction(&self, name: &str) -> Transaction {
+        let mut transaction = Transaction::start(self.runner_context.reporter(), name);
+        transaction.add_attr("agent", self.agent_name.clone());
+        transaction.add_attr("behaviour", self.assigned_behaviour.clone());
+        transaction
+    }
+
+    /// Get a [Counter] for this agent, reported as the custom metric `name` with `agent` and
+    /// `behaviour` tags.
+    pub fn counter(&self, name: &str) -> Counter {
+        self.runner_context
+            .reporter
+            .counter(name, &self.metric_tags())
+    }
+
+    /// Get a [Gauge] for this agent, reported as the custom metric `name` with `agent` and
+    /// `behaviour` tags.
+    pub fn gauge(&self, name: &str) -> Gauge {
+        self.runner_context
+            .reporter
+            .gauge(name, &self.metric_tags())
+    }
+
+    /// Get a [Histogram] for this agent, reported as the custom metric `name` with `agent` and
+    /// `behaviour` tags.
+    pub fn histogram(&self, name: &str) -> Histogram {
+        self.runner_context
+            .reporter
+            .histogram(name, &self.metric_tags())
+    }
+
+    fn metric_tags(&self) -> [(&str, &str); 2] {
+        [
+            ("agent", &self.agent_name),
+            ("behaviour", &self.assigned_behaviour),
+        ]
+    }
+
     /// A handle to the runner context for the scenario.
     pub fn runner_context(&self) -> &Arc<RunnerContext<RV>> {
         &self.runner_context
@@ -160,6 +293,16 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> AgentContext<RV, V> {
         &mut self.shutdown_listener
     }
 
+    /// Get a handle that can be used to make this agent bail from outside of its hooks.
+    pub fn bail_handle(&self) -> AgentBailHandle {
+        self.bail_handle.clone()
+    }
+
+    /// The reason the agent was asked to bail through its [AgentBailHandle], if it has been.
+    pub(crate) fn bail_reason(&self) -> Option<String> {
+        self.bail_handle.reason()
+    }
+
     /// Get mutable access to the user-defined state for the agent.
     pub fn get_mut(&mut self) -> &mut V {
         &mut self.value
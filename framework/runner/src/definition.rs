@@ -1,9 +1,18 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
 use wind_tunnel_summary_model::BuildInfo;
 
-use crate::cli::ReporterOpt;
+use crate::failure::{ErrorClassifier, FailurePolicy};
 use crate::init::init;
+use crate::params::{Param, ParamSpec, ScenarioParams};
+use crate::profile::LoadProfile;
+use crate::reporters::{ReporterConfig, resolve_reporters};
+use crate::schedule::{ArrivalRate, RateScope};
+use crate::thresholds::Threshold;
 use crate::{
     cli::WindTunnelScenarioCli,
     context::{AgentContext, RunnerContext, UserValuesConstraint},
@@ -12,12 +21,61 @@ use crate::{
 /// The result type that is required to be returned from all hooks.
 pub type HookResult = anyhow::Result<()>;
 
-pub type GlobalHookMut<RV> = fn(&mut RunnerContext<RV>) -> HookResult;
-pub type GlobalHook<RV> = fn(Arc<RunnerContext<RV>>) -> HookResult;
-pub type AgentHookMut<RV, V> = fn(&mut AgentContext<RV, V>) -> HookResult;
+/// The future that is required to be returned from async hooks.
+pub type HookFuture<'a> = Pin<Box<dyn Future<Output = HookResult> + Send + 'a>>;
+
+pub type GlobalHookMut<RV> = Box<dyn Fn(&mut RunnerContext<RV>) -> HookResult + Send + Sync>;
+pub type GlobalHook<RV> = Box<dyn Fn(Arc<RunnerContext<RV>>) -> HookResult + Send + Sync>;
+pub type AgentHookMut<RV, V> = Arc<dyn Fn(&mut AgentContext<RV, V>) -> HookResult + Send + Sync>;
+pub type AsyncAgentHookMut<RV, V> =
+    Arc<dyn for<'a> Fn(&'a mut AgentContext<RV, V>) -> HookFuture<'a> + Send + Sync>;
 
 /// The function to gather build info specific to the scenario binding
-pub type BuildInfoFn<RV> = fn(Arc<RunnerContext<RV>>) -> anyhow::Result<Option<BuildInfo>>;
+pub type BuildInfoFn<RV> =
+    Box<dyn Fn(Arc<RunnerContext<RV>>) -> anyhow::Result<Option<BuildInfo>> + Send + Sync>;
+
+/// An agent hook, which is either a blocking function or an async function.
+pub(crate) enum AgentHook<RV: UserValuesConstraint, V: UserValuesConstraint> {
+    Blocking(AgentHookMut<RV, V>),
+    Async(AsyncAgentHookMut<RV, V>),
+}
+
+impl<RV: UserValuesConstraint, V: UserValuesConstraint> Clone for AgentHook<RV, V> {
+    fn clone(&self) -> Self {
+        match self {
+            AgentHook::Blocking(hook) => AgentHook::Blocking(hook.clone()),
+            AgentHook::Async(hook) => AgentHook::Async(hook.clone()),
+        }
+    }
+}
+
+impl<RV: UserValuesConstraint, V: UserValuesConstraint> AgentHook<RV, V> {
+    pub(crate) fn is_async(&self) -> bool {
+        matches!(self, AgentHook::Async(_))
+    }
+
+    /// Run the hook from an agent thread, blocking until an async hook completes.
+    pub(crate) fn call_blocking(&self, ctx: &mut AgentContext<RV, V>) -> HookResult {
+        match self {
+            AgentHook::Blocking(hook) => hook(ctx),
+            AgentHook::Async(hook) => {
+                let executor = ctx.runner_context().executor().clone();
+                executor.execute_in_place(hook(ctx))
+            }
+        }
+    }
+
+    /// Run the hook from an agent task.
+    ///
+    /// Blocking hooks are run with [tokio::task::block_in_place], so that they can still use
+    /// [Executor::execute_in_place](crate::executor::Executor::execute_in_place).
+    pub(crate) async fn call(&self, ctx: &mut AgentContext<RV, V>) -> HookResult {
+        match self {
+            AgentHook::Blocking(hook) => tokio::task::block_in_place(|| hook(ctx)),
+            AgentHook::Async(hook) => hook(ctx).await,
+        }
+    }
+}
 
 /// The builder for a scenario definition.
 ///
@@ -28,13 +86,22 @@ pub struct ScenarioDefinitionBuilder<RV: UserValuesConstraint, V: UserValuesCons
     cli: WindTunnelScenarioCli,
     default_agent_count: Option<usize>,
     default_duration_s: Option<u64>,
+    default_warmup_s: Option<u64>,
     capture_env: HashSet<String>,
+    params: BTreeMap<String, ParamSpec>,
     build_info_fn: Option<BuildInfoFn<RV>>,
     setup_fn: Option<GlobalHookMut<RV>>,
-    setup_agent_fn: Option<AgentHookMut<RV, V>>,
-    agent_behaviour: HashMap<String, AgentHookMut<RV, V>>,
-    teardown_agent_fn: Option<AgentHookMut<RV, V>>,
+    setup_agent_fn: Option<AgentHook<RV, V>>,
+    agent_behaviour: HashMap<String, AgentHook<RV, V>>,
+    teardown_agent_fn: Option<AgentHook<RV, V>>,
     teardown_fn: Option<GlobalHook<RV>>,
+    arrival_rates: HashMap<String, (ArrivalRate, RateScope)>,
+    max_schedule_lag: Option<Duration>,
+    load_profile: Option<LoadProfile>,
+    setup_barrier_timeout: Option<Duration>,
+    failure_policy: FailurePolicy,
+    error_classifier: Option<ErrorClassifier>,
+    thresholds: Vec<Threshold>,
 }
 
 pub struct AssignedBehaviour {
@@ -47,16 +114,28 @@ pub struct ScenarioDefinition<RV: UserValuesConstraint, V: UserValuesConstraint>
     pub(crate) name: String,
     pub(crate) assigned_behaviours: Vec<AssignedBehaviour>,
     pub(crate) duration_s: Option<u64>,
+    pub(crate) warmup_s: Option<u64>,
     pub(crate) connection_string: Option<String>,
     pub(crate) capture_env: HashSet<String>,
+    pub(crate) params: ScenarioParams,
     pub(crate) no_progress: bool,
-    pub(crate) reporter: ReporterOpt,
+    pub(crate) reporters: Vec<ReporterConfig>,
+    pub(crate) metrics_dir: Option<PathBuf>,
+    pub(crate) time_server: Option<String>,
+    pub(crate) run_summary_path: Option<PathBuf>,
     pub(crate) build_info_fn: Option<BuildInfoFn<RV>>,
     pub(crate) setup_fn: Option<GlobalHookMut<RV>>,
-    pub(crate) setup_agent_fn: Option<AgentHookMut<RV, V>>,
-    pub(crate) agent_behaviour: HashMap<String, AgentHookMut<RV, V>>,
-    pub(crate) teardown_agent_fn: Option<AgentHookMut<RV, V>>,
+    pub(crate) setup_agent_fn: Option<AgentHook<RV, V>>,
+    pub(crate) agent_behaviour: HashMap<String, AgentHook<RV, V>>,
+    pub(crate) teardown_agent_fn: Option<AgentHook<RV, V>>,
     pub(crate) teardown_fn: Option<GlobalHook<RV>>,
+    pub(crate) arrival_rates: HashMap<String, (ArrivalRate, RateScope)>,
+    pub(crate) max_schedule_lag: Option<Duration>,
+    pub(crate) load_profile: Option<LoadProfile>,
+    pub(crate) setup_barrier_timeout: Option<Duration>,
+    pub(crate) failure_policy: FailurePolicy,
+    pub(crate) error_classifier: Option<ErrorClassifier>,
+    pub(crate) thresholds: Vec<Threshold>,
     pub(crate) run_id: String,
 }
 
@@ -101,13 +180,22 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> ScenarioDefinitionBuilde
             cli,
             default_agent_count: None,
             default_duration_s: None,
+            default_warmup_s: None,
             capture_env: HashSet::with_capacity(0),
+            params: BTreeMap::new(),
             build_info_fn: None,
             setup_fn: None,
             setup_agent_fn: None,
             agent_behaviour: HashMap::new(),
             teardown_agent_fn: None,
             teardown_fn: None,
+            arrival_rates: HashMap::new(),
+            max_schedule_lag: None,
+            load_profile: None,
+            setup_barrier_timeout: None,
+            failure_policy: FailurePolicy::default(),
+            error_classifier: None,
+            thresholds: Vec::new(),
         }
     }
 
@@ -127,33 +215,95 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> ScenarioDefinitionBuilde
         self
     }
 
+    /// Sets the default warmup period for this scenario, in seconds.
+    ///
+    /// Operations and custom metrics recorded during the warmup are tagged with `phase=warmup` so
+    /// that they can be excluded from the results. The warmup starts when the agents are started
+    /// and is included in the duration of the scenario.
+    ///
+    /// This can be overridden when the scenario is run using the `--warmup` flag.
+    pub fn with_default_warmup_s(mut self, warmup: u64) -> Self {
+        self.default_warmup_s = Some(warmup);
+        self
+    }
+
     pub fn add_capture_env(mut self, key: &str) -> Self {
         self.capture_env.insert(key.to_string());
         self
     }
 
+    /// Declares a parameter for this scenario, which can then be read from hooks with
+    /// [RunnerContext::params].
+    ///
+    /// Values are read from the `--params` file and `--param` overrides. If no value is set, then
+    /// the `default` is used. Parameters without a default must be set when the scenario is run.
+    /// The scenario fails to start if a value is not valid for the type of the parameter, or if a
+    /// value is set for a parameter that has not been declared.
+    ///
+    /// The names must be unique!
+    pub fn add_param<T: Param>(mut self, name: &str, default: Option<T>) -> Self {
+        let previous = self
+            .params
+            .insert(name.to_string(), ParamSpec::new(default));
+
+        if previous.is_some() {
+            panic!("Parameter [{name}] is already defined");
+        }
+
+        self
+    }
+
     /// Sets the function to get build info for this scenario. It will be run after global setup hook.
-    pub fn use_build_info(mut self, build_info_fn: BuildInfoFn<RV>) -> Self {
-        self.build_info_fn = Some(build_info_fn);
+    pub fn use_build_info(
+        mut self,
+        build_info_fn: impl Fn(Arc<RunnerContext<RV>>) -> anyhow::Result<Option<BuildInfo>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.build_info_fn = Some(Box::new(build_info_fn));
         self
     }
 
     /// Sets the global setup hook for this scenario. It will be run once, before any agents are started.
-    pub fn use_setup(mut self, setup_fn: GlobalHookMut<RV>) -> Self {
-        self.setup_fn = Some(setup_fn);
+    pub fn use_setup(
+        mut self,
+        setup_fn: impl Fn(&mut RunnerContext<RV>) -> HookResult + Send + Sync + 'static,
+    ) -> Self {
+        self.setup_fn = Some(Box::new(setup_fn));
         self
     }
 
     /// Sets the setup hook for an agent. It will be run once for each agent, as it starts.
-    pub fn use_agent_setup(mut self, setup_agent_fn: AgentHookMut<RV, V>) -> Self {
-        self.setup_agent_fn = Some(setup_agent_fn);
+    pub fn use_agent_setup(
+        mut self,
+        setup_agent_fn: impl Fn(&mut AgentContext<RV, V>) -> HookResult + Send + Sync + 'static,
+    ) -> Self {
+        self.setup_agent_fn = Some(AgentHook::Blocking(Arc::new(setup_agent_fn)));
+        self
+    }
+
+    /// Sets an async setup hook for an agent. It will be run once for each agent, as it starts.
+    ///
+    /// See [ScenarioDefinitionBuilder::use_async_agent_behaviour] for how to write async hooks.
+    pub fn use_async_agent_setup(
+        mut self,
+        setup_agent_fn: impl for<'a> Fn(&'a mut AgentContext<RV, V>) -> HookFuture<'a>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.setup_agent_fn = Some(AgentHook::Async(Arc::new(setup_agent_fn)));
         self
     }
 
     /// Sets the default agent behaviour for this scenario. There are two ways that this can be used:
     ///
     /// This should be used when you want to run agents with the same behaviour.
-    pub fn use_agent_behaviour(self, behaviour: AgentHookMut<RV, V>) -> Self {
+    pub fn use_agent_behaviour(
+        self,
+        behaviour: impl Fn(&mut AgentContext<RV, V>) -> HookResult + Send + Sync + 'static,
+    ) -> Self {
         self.use_named_agent_behaviour("default", behaviour)
     }
 
@@ -162,7 +312,77 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> ScenarioDefinitionBuilde
     /// The names must be unique!
     ///
     /// This should be used when you want to run agents with different behaviours. Otherwise, use [ScenarioDefinitionBuilder::use_agent_behaviour].
-    pub fn use_named_agent_behaviour(mut self, name: &str, behaviour: AgentHookMut<RV, V>) -> Self {
+    pub fn use_named_agent_behaviour(
+        self,
+        name: &str,
+        behaviour: impl Fn(&mut AgentContext<RV, V>) -> HookResult + Send + Sync + 'static,
+    ) -> Self {
+        self.add_agent_behaviour(name, AgentHook::Blocking(Arc::new(behaviour)))
+    }
+
+    /// Sets the default agent behaviour for this scenario as an async hook.
+    ///
+    /// Agents that are assigned an async behaviour are run as tasks on the shared Tokio runtime,
+    /// rather than each on its own thread. This allows many more agents to be run by a single runner.
+    /// The hook must return a boxed future, which can borrow the agent context:
+    ///
+    /// ```rust
+    /// use wind_tunnel_runner::prelude::*;
+    ///
+    /// #[derive(Default, Debug)]
+    /// struct RunnerValues {}
+    ///
+    /// impl UserValuesConstraint for RunnerValues {}
+    ///
+    /// #[derive(Default, Debug)]
+    /// struct AgentValues {
+    ///     calls: usize,
+    /// }
+    ///
+    /// impl UserValuesConstraint for AgentValues {}
+    ///
+    /// fn builder(cli: WindTunnelScenarioCli) -> ScenarioDefinitionBuilder<RunnerValues, AgentValues> {
+    ///     let delay = std::time::Duration::from_millis(10);
+    ///     ScenarioDefinitionBuilder::<RunnerValues, AgentValues>::new("async_example", cli)
+    ///         .use_async_agent_behaviour(move |ctx| {
+    ///             Box::pin(async move {
+    ///                 tokio::time::sleep(delay).await;
+    ///                 ctx.get_mut().calls += 1;
+    ///                 Ok(())
+    ///             })
+    ///         })
+    /// }
+    /// ```
+    ///
+    /// Blocking setup and teardown hooks can still be used with async behaviours, and async setup
+    /// and teardown hooks can be used with blocking behaviours.
+    pub fn use_async_agent_behaviour(
+        self,
+        behaviour: impl for<'a> Fn(&'a mut AgentContext<RV, V>) -> HookFuture<'a>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.use_async_named_agent_behaviour("default", behaviour)
+    }
+
+    /// Adds a named async agent behaviour hook for this scenario.
+    ///
+    /// The names must be unique, across both blocking and async behaviours!
+    ///
+    /// See [ScenarioDefinitionBuilder::use_async_agent_behaviour] for how async behaviours are run.
+    pub fn use_async_named_agent_behaviour(
+        self,
+        name: &str,
+        behaviour: impl for<'a> Fn(&'a mut AgentContext<RV, V>) -> HookFuture<'a>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.add_agent_behaviour(name, AgentHook::Async(Arc::new(behaviour)))
+    }
+
+    fn add_agent_behaviour(mut self, name: &str, behaviour: AgentHook<RV, V>) -> Self {
         let previous = self.agent_behaviour.insert(name.to_string(), behaviour);
 
         if previous.is_some() {
@@ -176,8 +396,25 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> ScenarioDefinitionBuilde
     ///
     /// If the scenario run is bounded by time, then this hook will be run.
     /// If the scenario is configured to run forever, then this hook will be run on a best effort basis when the scenario is stopped.
-    pub fn use_agent_teardown(mut self, teardown_agent_fn: AgentHookMut<RV, V>) -> Self {
-        self.teardown_agent_fn = Some(teardown_agent_fn);
+    pub fn use_agent_teardown(
+        mut self,
+        teardown_agent_fn: impl Fn(&mut AgentContext<RV, V>) -> HookResult + Send + Sync + 'static,
+    ) -> Self {
+        self.teardown_agent_fn = Some(AgentHook::Blocking(Arc::new(teardown_agent_fn)));
+        self
+    }
+
+    /// Sets an async teardown hook for an agent, which will be run once for each agent when its behaviour is finished.
+    ///
+    /// See [ScenarioDefinitionBuilder::use_agent_teardown] for when this hook is run.
+    pub fn use_async_agent_teardown(
+        mut self,
+        teardown_agent_fn: impl for<'a> Fn(&'a mut AgentContext<RV, V>) -> HookFuture<'a>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.teardown_agent_fn = Some(AgentHook::Async(Arc::new(teardown_agent_fn)));
         self
     }
 
@@ -185,20 +422,157 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> ScenarioDefinitionBuilde
     ///
     /// If the scenario run is bounded by time, then this hook will be run.
     /// If the scenario is configured to run forever, then this hook will be run on a best effort basis when the scenario is stopped.
-    pub fn use_teardown(mut self, teardown_fn: GlobalHook<RV>) -> Self {
-        self.teardown_fn = Some(teardown_fn);
+    pub fn use_teardown(
+        mut self,
+        teardown_fn: impl Fn(Arc<RunnerContext<RV>>) -> HookResult + Send + Sync + 'static,
+    ) -> Self {
+        self.teardown_fn = Some(Box::new(teardown_fn));
+        self
+    }
+
+    /// Run the named behaviour in open-model mode, starting iterations at the target arrival rate
+    /// instead of back-to-back.
+    ///
+    /// With [RateScope::PerAgent], every agent assigned the behaviour runs at the target rate. With
+    /// [RateScope::Global], the rate is shared between all agents assigned the behaviour.
+    ///
+    /// Each iteration is reported as an `open_model_iteration` custom metric, with how late it started
+    /// relative to its intended start time and its latency measured from the intended start time.
+    /// Iterations that start late because the agent was still busy are queued rather than dropped,
+    /// unless a lag limit is set with [ScenarioDefinitionBuilder::with_max_schedule_lag].
+    pub fn use_arrival_rate(
+        mut self,
+        behaviour: &str,
+        rate: ArrivalRate,
+        scope: RateScope,
+    ) -> Self {
+        self.arrival_rates
+            .insert(behaviour.to_string(), (rate, scope));
+        self
+    }
+
+    /// Sets how far behind schedule an open-model iteration may fall before it is dropped.
+    ///
+    /// Dropped iterations are reported as an `open_model_missed` custom metric. By default, no
+    /// iterations are dropped.
+    pub fn with_max_schedule_lag(mut self, max_lag: Duration) -> Self {
+        self.max_schedule_lag = Some(max_lag);
+        self
+    }
+
+    /// Sets the load profile for this scenario, to activate agents in stages rather than all at once.
+    ///
+    /// Unless the agent count is set by the CLI, enough agents are spawned to reach the highest
+    /// target in the profile. Unless the duration is set by the CLI, the scenario runs for the total
+    /// duration of the profile.
+    ///
+    /// This can be overridden when the scenario is run using the `--load-stage` flag.
+    pub fn with_load_profile(mut self, load_profile: LoadProfile) -> Self {
+        self.load_profile = Some(load_profile);
+        self
+    }
+
+    /// Hold every agent after its setup until all agents have finished their setup, so that no
+    /// agent starts its behaviour while others are still setting up.
+    ///
+    /// Agents whose setup fails are counted as finished. If some agents are still setting up once
+    /// `timeout` has passed, the barrier is released without them and they start their behaviour
+    /// as soon as their setup completes. The scenario duration and warmup start once the barrier is
+    /// released.
+    ///
+    /// The setup duration of each agent is reported as an `agent_setup` custom metric, and the
+    /// release of the barrier as a `setup_barrier` custom metric.
+    ///
+    /// Cannot be combined with a load profile, which activates agents at different times.
+    pub fn with_setup_barrier(mut self, timeout: Duration) -> Self {
+        self.setup_barrier_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how the runner responds to errors returned from agent behaviours.
+    ///
+    /// Every behaviour error is reported as a `behaviour_error` custom metric, tagged with the kind
+    /// of error, and the number of errors of each kind is recorded in the run summary. If the
+    /// scenario is aborted because the error rate is too high, then [run](crate::prelude::run)
+    /// returns an error once the scenario has shut down.
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Sets a function to classify behaviour errors into kinds, for counting errors by kind.
+    ///
+    /// The kinds should be a small, fixed set of values, such as `timeout` or `network`. If the
+    /// function returns [None], then the error is classified by the kind of a
+    /// [ClassifiedError](wind_tunnel_instruments::prelude::ClassifiedError) in its chain, or as
+    /// `bail`, `timeout`, `io` or `other`.
+    pub fn use_error_classifier(
+        mut self,
+        classifier: impl Fn(&anyhow::Error) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.error_classifier = Some(Box::new(classifier));
+        self
+    }
+
+    /// Adds a pass/fail threshold for the scenario, which is checked at the end of the run.
+    ///
+    /// The verdict is recorded in the run summary. If any threshold fails, then
+    /// [run](crate::prelude::run) returns an error once the scenario has shut down, so that the
+    /// scenario process exits with a non-zero exit code.
+    pub fn add_threshold(mut self, threshold: Threshold) -> Self {
+        self.thresholds.push(threshold);
         self
     }
 
     pub(crate) fn build(self) -> anyhow::Result<ScenarioDefinition<RV, V>> {
+        // Priority given to the CLI, then the profile provided by the scenario
+        let load_profile = if self.cli.load_stage.is_empty() {
+            self.load_profile
+        } else {
+            Some(LoadProfile::from_stages(self.cli.load_stage.clone()))
+        };
+
         let resolved_duration = if self.cli.soak {
             None
         } else {
-            self.cli.duration.or(self.default_duration_s)
+            self.cli
+                .duration
+                .or(load_profile.as_ref().map(|p| p.total_duration().as_secs()))
+                .or(self.default_duration_s)
         };
 
-        // Priority given to the CLI, then the default value provided by the scenario, then default to 1
-        let resolved_agent_count = self.cli.agents.or(self.default_agent_count).unwrap_or(1);
+        let resolved_warmup = self.cli.warmup.or(self.default_warmup_s);
+        if let (Some(warmup), Some(duration)) = (resolved_warmup, resolved_duration)
+            && warmup >= duration
+        {
+            return Err(anyhow::anyhow!(
+                "The warmup of {warmup}s must be shorter than the duration of {duration}s"
+            ));
+        }
+
+        // Priority given to the CLI, then the load profile, then the default value provided by the
+        // scenario, then default to 1
+        let resolved_agent_count = self
+            .cli
+            .agents
+            .or(load_profile.as_ref().map(LoadProfile::max_target))
+            .or(self.default_agent_count)
+            .unwrap_or(1);
+
+        if let Some(load_profile) = &load_profile
+            && load_profile.max_target() > resolved_agent_count
+        {
+            return Err(anyhow::anyhow!(
+                "The load profile targets {} agents but only {resolved_agent_count} agents are configured",
+                load_profile.max_target()
+            ));
+        }
+
+        if load_profile.is_some() && self.setup_barrier_timeout.is_some() {
+            return Err(anyhow::anyhow!(
+                "A setup barrier cannot be used with a load profile"
+            ));
+        }
 
         // Check that the user hasn't requested behaviours that aren't registered in the scenario.
         let registered_behaviours = self
@@ -221,22 +595,53 @@ impl<RV: UserValuesConstraint, V: UserValuesConstraint> ScenarioDefinitionBuilde
             ));
         }
 
+        for (behaviour, (rate, _)) in &self.arrival_rates {
+            if !registered_behaviours.contains(behaviour) {
+                return Err(anyhow::anyhow!(
+                    "Arrival rate set for unknown behaviour: {behaviour}"
+                ));
+            }
+            rate.validate()?;
+        }
+
+        self.failure_policy.validate()?;
+        for threshold in &self.thresholds {
+            threshold.validate()?;
+        }
+
+        let params =
+            ScenarioParams::resolve(&self.params, self.cli.params.as_deref(), &self.cli.param)?;
+
+        let reporters = resolve_reporters(&self.cli.reporter, self.cli.reporter_config.as_deref())?;
+
         let run_id = self.cli.run_id.clone().unwrap_or_else(|| nanoid::nanoid!());
 
         Ok(ScenarioDefinition {
             name: self.name,
             assigned_behaviours: build_assigned_behaviours(&self.cli, resolved_agent_count)?,
             duration_s: resolved_duration,
+            warmup_s: resolved_warmup,
             connection_string: self.cli.connection_string,
             capture_env: self.capture_env,
+            params,
             no_progress: self.cli.no_progress,
-            reporter: self.cli.reporter,
+            reporters,
+            metrics_dir: self.cli.metrics_dir,
+            time_server: self.cli.time_server,
+            run_summary_path: self.cli.run_summary_path,
             build_info_fn: self.build_info_fn,
             setup_fn: self.setup_fn,
             setup_agent_fn: self.setup_agent_fn,
             agent_behaviour: self.agent_behaviour,
             teardown_agent_fn: self.teardown_agent_fn,
             teardown_fn: self.teardown_fn,
+            arrival_rates: self.arrival_rates,
+            max_schedule_lag: self.max_schedule_lag,
+            load_profile,
+            setup_barrier_timeout: self.setup_barrier_timeout,
+            failure_policy: self.failure_policy,
+            error_classifier: self.error_classifier,
+            thresholds: self.thresholds,
             run_id,
         })
     }
@@ -285,9 +690,17 @@ mod tests {
                 agents: None,
                 behaviour: vec![],
                 duration: None,
+                warmup: None,
+                load_stage: vec![],
+                params: None,
+                param: vec![],
                 soak: false,
                 no_progress: true,
-                reporter: ReporterOpt::Noop,
+                reporter: vec![ReporterOpt::Noop],
+                reporter_config: None,
+                metrics_dir: None,
+                time_server: None,
+                run_summary_path: None,
                 run_id: None,
             },
             5,
@@ -307,9 +720,17 @@ mod tests {
                 agents: None,
                 behaviour: vec![], // Not specified
                 duration: None,
+                warmup: None,
+                load_stage: vec![],
+                params: None,
+                param: vec![],
                 soak: false,
                 no_progress: true,
-                reporter: ReporterOpt::Noop,
+                reporter: vec![ReporterOpt::Noop],
+                reporter_config: None,
+                metrics_dir: None,
+                time_server: None,
+                run_summary_path: None,
                 run_id: None,
             },
             5,
@@ -329,9 +750,17 @@ mod tests {
                 agents: None,
                 behaviour: vec![("login".to_string(), 3)], // 3 of 5
                 duration: None,
+                warmup: None,
+                load_stage: vec![],
+                params: None,
+                param: vec![],
                 soak: false,
                 no_progress: true,
-                reporter: ReporterOpt::Noop,
+                reporter: vec![ReporterOpt::Noop],
+                reporter_config: None,
+                metrics_dir: None,
+                time_server: None,
+                run_summary_path: None,
                 run_id: None,
             },
             5,
@@ -353,9 +782,17 @@ mod tests {
                 agents: None,
                 behaviour: vec![("login".to_string(), 30)], // 30 of 5
                 duration: None,
+                warmup: None,
+                load_stage: vec![],
+                params: None,
+                param: vec![],
                 soak: false,
                 no_progress: true,
-                reporter: ReporterOpt::Noop,
+                reporter: vec![ReporterOpt::Noop],
+                reporter_config: None,
+                metrics_dir: None,
+                time_server: None,
+                run_summary_path: None,
                 run_id: None,
             },
             5,
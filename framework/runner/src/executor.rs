@@ -54,11 +54,29 @@ impl Executor {
     /// that the runner will wait for the future to complete before shutting down.
     ///
     /// In agent behaviour hooks, you should use [Executor::execute_in_place] instead of [Executor::spawn] to ensure that your
-    /// your future completes before the behaviour completes and is scheduled again.
+    /// your future completes before the behaviour completes and is scheduled again. Alternatively, use an async behaviour
+    /// hook with [ScenarioDefinitionBuilder::use_async_agent_behaviour](crate::prelude::ScenarioDefinitionBuilder::use_async_agent_behaviour)
+    /// and await your future directly.
     pub fn spawn(&self, fut: impl Future<Output = ()> + Send + 'static) {
         self.handle.spawn(fut);
     }
 
+    /// Spawn a task for an agent, returning a handle that can be used to wait for it to complete.
+    pub(crate) fn spawn_agent<T: Send + 'static>(
+        &self,
+        fut: impl Future<Output = T> + Send + 'static,
+    ) -> tokio::task::JoinHandle<T> {
+        self.handle.spawn(fut)
+    }
+
+    /// Block the current thread until an agent task completes.
+    pub(crate) fn join_agent<T>(
+        &self,
+        handle: tokio::task::JoinHandle<T>,
+    ) -> Result<T, tokio::task::JoinError> {
+        self.handle.block_on(handle)
+    }
+
     /// Shut down the runtime, waiting at most `timeout` for background tasks to complete.
     ///
     /// This should be called explicitly at the end of a run rather than relying on the implicit drop,
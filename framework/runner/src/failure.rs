@@ -0,0 +1,327 @@
+use crate::shutdown::ShutdownReasonCell;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use wind_tunnel_core::prelude::ShutdownHandle;
+use wind_tunnel_instruments::prelude::OperationError;
+use wind_tunnel_summary_model::ShutdownReason;
+
+/// Classifies a behaviour error into a kind, for counting errors by kind.
+///
+/// Return [None] to fall back to the default classification.
+pub type ErrorClassifier = Box<dyn Fn(&anyhow::Error) -> Option<String> + Send + Sync>;
+
+/// How the runner responds to errors returned from agent behaviours.
+///
+/// By default, behaviour errors are counted and reported but the agent keeps running its behaviour
+/// and the scenario is never aborted.
+///
+/// For example, to bail an agent after 10 consecutive errors, back off between failing iterations
+/// and abort the scenario if more than half of all iterations fail:
+/// ```rust
+/// use std::time::Duration;
+/// use wind_tunnel_runner::prelude::FailurePolicy;
+///
+/// let policy = FailurePolicy::new()
+///     .with_max_consecutive_errors(10)
+///     .with_backoff(Duration::from_millis(100), Duration::from_secs(5))
+///     .with_max_error_rate(0.5, 100);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FailurePolicy {
+    max_consecutive_errors: Option<usize>,
+    max_error_rate: Option<(f64, usize)>,
+    backoff: Option<(Duration, Duration)>,
+}
+
+impl FailurePolicy {
+    /// Create a policy that never bails agents or aborts the scenario.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bail an agent once its behaviour has failed `max` times in a row.
+    pub fn with_max_consecutive_errors(mut self, max: usize) -> Self {
+        self.max_consecutive_errors = Some(max);
+        self
+    }
+
+    /// Abort the scenario if the fraction of behaviour iterations that fail, across all agents,
+    /// rises above `max_rate`.
+    ///
+    /// The error rate is only checked once at least `min_iterations` iterations have run, so that a
+    /// few early errors do not abort the scenario.
+    pub fn with_max_error_rate(mut self, max_rate: f64, min_iterations: usize) -> Self {
+        self.max_error_rate = Some((max_rate, min_iterations));
+        self
+    }
+
+    /// Wait before running the behaviour again after it fails.
+    ///
+    /// The wait starts at `initial` and doubles for each consecutive error, up to `max`.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = Some((initial, max));
+        self
+    }
+
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if self.max_consecutive_errors == Some(0) {
+            anyhow::bail!("The maximum number of consecutive errors must be greater than 0");
+        }
+
+        if let Some((max_rate, _)) = self.max_error_rate
+            && !(0.0..1.0).contains(&max_rate)
+        {
+            anyhow::bail!("The maximum error rate must be at least 0 and less than 1");
+        }
+
+        if let Some((initial, max)) = self.backoff
+            && initial > max
+        {
+            anyhow::bail!("The initial backoff must not be longer than the maximum backoff");
+        }
+
+        Ok(())
+    }
+
+    /// Whether an agent should bail after this many consecutive errors.
+    pub(crate) fn should_bail(&self, consecutive_errors: usize) -> bool {
+        self.max_consecutive_errors
+            .is_some_and(|max| consecutive_errors >= max)
+    }
+
+    /// How long to wait after this many consecutive errors, if the policy backs off.
+    pub(crate) fn backoff(&self, consecutive_errors: usize) -> Option<Duration> {
+        let (initial, max) = self.backoff?;
+        let exponent = consecutive_errors.saturating_sub(1).min(31) as u32;
+
+        Some(initial.saturating_mul(2u32.pow(exponent)).min(max))
+    }
+}
+
+/// Counts behaviour iterations and errors across all agents, and aborts the scenario if the error
+/// rate goes above the limit set by the [FailurePolicy].
+pub(crate) struct FailureTracker {
+    policy: FailurePolicy,
+    classifier: Option<ErrorClassifier>,
+    shutdown_handle: ShutdownHandle,
+    shutdown_reason: ShutdownReasonCell,
+    counts: Mutex<FailureCounts>,
+}
+
+#[derive(Default)]
+struct FailureCounts {
+    iterations: u64,
+    errors: u64,
+    errors_by_kind: BTreeMap<String, u64>,
+    abort_reason: Option<String>,
+}
+
+impl FailureTracker {
+    pub(crate) fn new(
+        policy: FailurePolicy,
+        classifier: Option<ErrorClassifier>,
+        shutdown_handle: ShutdownHandle,
+        shutdown_reason: ShutdownReasonCell,
+    ) -> Self {
+        Self {
+            policy,
+            classifier,
+            shutdown_handle,
+            shutdown_reason,
+            counts: Mutex::new(FailureCounts::default()),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> &FailurePolicy {
+        &self.policy
+    }
+
+    /// Record a behaviour iteration that succeeded.
+    pub(crate) fn record_success(&self) {
+        self.counts
+            .lock()
+            .expect("Failure counts lock poisoned")
+            .iterations += 1;
+    }
+
+    /// Record a behaviour iteration that failed, returning the kind of the error.
+    ///
+    /// Shuts down the scenario if the error rate is now above the limit.
+    pub(crate) fn record_error(&self, error: &anyhow::Error) -> String {
+        let kind = self
+            .classifier
+            .as_ref()
+            .and_then(|classifier| classifier(error))
+            .unwrap_or_else(|| error.error_kind().into_owned());
+
+        let mut counts = self.counts.lock().expect("Failure counts lock poisoned");
+        counts.iterations += 1;
+        counts.errors += 1;
+        *counts.errors_by_kind.entry(kind.clone()).or_default() += 1;
+
+        if let Some((max_rate, min_iterations)) = self.policy.max_error_rate
+            && counts.abort_reason.is_none()
+            && counts.iterations >= min_iterations as u64
+        {
+            let error_rate = counts.errors as f64 / counts.iterations as f64;
+            if error_rate > max_rate {
+                let reason = format!(
+                    "Error rate of {:.1}% over {} iterations is above the limit of {:.1}%",
+                    error_rate * 100.0,
+                    counts.iterations,
+                    max_rate * 100.0
+                );
+                log::error!("Aborting scenario: {reason}");
+                counts.abort_reason = Some(reason);
+                self.shutdown_reason
+                    .shutdown(&self.shutdown_handle, ShutdownReason::Aborted);
+            }
+        }
+
+        kind
+    }
+
+    /// The total number of behaviour iterations and the number of errors of each kind.
+    ///
+    /// Errors counted by kind include agents bailing.
+    pub(crate) fn counts(&self) -> (u64, BTreeMap<String, u64>) {
+        let counts = self.counts.lock().expect("Failure counts lock poisoned");
+        (counts.iterations, counts.errors_by_kind.clone())
+    }
+
+    /// Why the scenario was aborted, if the error rate went above the limit.
+    pub(crate) fn abort_reason(&self) -> Option<String> {
+        self.counts
+            .lock()
+            .expect("Failure counts lock poisoned")
+            .abort_reason
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wind_tunnel_core::prelude::AgentBailError;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy =
+            FailurePolicy::new().with_backoff(Duration::from_millis(100), Duration::from_secs(1));
+
+        assert_eq!(Some(Duration::from_millis(100)), policy.backoff(1));
+        assert_eq!(Some(Duration::from_millis(200)), policy.backoff(2));
+        assert_eq!(Some(Duration::from_millis(800)), policy.backoff(4));
+        assert_eq!(Some(Duration::from_secs(1)), policy.backoff(5));
+        assert_eq!(Some(Duration::from_secs(1)), policy.backoff(100));
+        assert_eq!(None, FailurePolicy::new().backoff(1));
+    }
+
+    #[test]
+    fn invalid_policies_are_rejected() {
+        assert!(FailurePolicy::new().validate().is_ok());
+        assert!(
+            FailurePolicy::new()
+                .with_max_consecutive_errors(0)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            FailurePolicy::new()
+                .with_max_error_rate(1.0, 10)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            FailurePolicy::new()
+                .with_backoff(Duration::from_secs(2), Duration::from_secs(1))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn errors_are_classified() {
+        let tracker = FailureTracker::new(
+            FailurePolicy::new(),
+            None,
+            ShutdownHandle::default(),
+            ShutdownReasonCell::default(),
+        );
+
+        assert_eq!(
+            "bail",
+            tracker.record_error(&AgentBailError::default().into())
+        );
+        assert_eq!(
+            "timeout",
+            tracker.record_error(&std::io::Error::from(std::io::ErrorKind::TimedOut).into())
+        );
+        assert_eq!(
+            "io",
+            tracker.record_error(
+                &anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::NotFound))
+                    .context("Failed to read")
+            )
+        );
+        assert_eq!("other", tracker.record_error(&anyhow::anyhow!("Failed")));
+        tracker.record_success();
+
+        let (iterations, errors_by_kind) = tracker.counts();
+        assert_eq!(5, iterations);
+        assert_eq!(
+            BTreeMap::from([
+                ("bail".to_string(), 1),
+                ("io".to_string(), 1),
+                ("other".to_string(), 1),
+                ("timeout".to_string(), 1),
+            ]),
+            errors_by_kind
+        );
+    }
+
+    #[test]
+    fn custom_classifier_takes_priority() {
+        let tracker = FailureTracker::new(
+            FailurePolicy::new(),
+            Some(Box::new(|e| {
+                e.to_string()
+                    .contains("ribosome")
+                    .then(|| "ribosome".to_string())
+            })),
+            ShutdownHandle::default(),
+            ShutdownReasonCell::default(),
+        );
+
+        assert_eq!(
+            "ribosome",
+            tracker.record_error(&anyhow::anyhow!("ribosome error"))
+        );
+        assert_eq!("other", tracker.record_error(&anyhow::anyhow!("Failed")));
+    }
+
+    #[test]
+    fn scenario_is_aborted_above_max_error_rate() {
+        let shutdown_handle = ShutdownHandle::default();
+        let shutdown_reason = ShutdownReasonCell::default();
+        let mut listener = shutdown_handle.new_listener();
+        let tracker = FailureTracker::new(
+            FailurePolicy::new().with_max_error_rate(0.5, 4),
+            None,
+            shutdown_handle,
+            shutdown_reason.clone(),
+        );
+
+        // Not enough iterations to check the error rate yet.
+        tracker.record_error(&anyhow::anyhow!("Failed"));
+        tracker.record_error(&anyhow::anyhow!("Failed"));
+        tracker.record_success();
+        assert!(tracker.abort_reason().is_none());
+
+        tracker.record_error(&anyhow::anyhow!("Failed"));
+        assert!(tracker.abort_reason().is_some());
+        assert!(listener.should_shutdown());
+        assert_eq!(Some(ShutdownReason::Aborted), shutdown_reason.get());
+    }
+}
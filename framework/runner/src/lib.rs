@@ -1,24 +1,38 @@
+mod agent;
+mod barrier;
 mod cli;
+mod clock;
 mod context;
 mod definition;
 mod executor;
+mod failure;
 mod init;
 mod monitor;
+mod params;
+mod profile;
 mod progress;
+mod reporters;
 mod run;
+mod schedule;
 mod shutdown;
+mod thresholds;
 mod types;
 
-pub use cli::parse_agent_behaviour;
+pub use cli::{parse_agent_behaviour, parse_load_stage, parse_param};
 
 pub mod prelude {
     pub use crate::cli::{ReporterOpt, WindTunnelScenarioCli};
     pub use crate::context::UserValuesConstraint;
-    pub use crate::context::{AgentContext, RunnerContext};
-    pub use crate::definition::{HookResult, ScenarioDefinitionBuilder};
+    pub use crate::context::{AgentBailHandle, AgentContext, RunnerContext};
+    pub use crate::definition::{HookFuture, HookResult, ScenarioDefinitionBuilder};
     pub use crate::executor::Executor;
+    pub use crate::failure::{ErrorClassifier, FailurePolicy};
     pub use crate::init::init;
+    pub use crate::params::{Param, ParamKind, ParamValue, ScenarioParams};
+    pub use crate::profile::{LoadProfile, LoadStage};
     pub use crate::run::run;
+    pub use crate::schedule::{ArrivalRate, RateScope};
+    pub use crate::thresholds::Threshold;
     pub use crate::types::WindTunnelResult;
 
     // Re-export of the `wind_tunnel_instruments` prelude. This is for convenience so that you can
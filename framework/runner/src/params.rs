@@ -0,0 +1,403 @@
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// The type of a scenario parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    Bool,
+    Integer,
+    Float,
+    String,
+}
+
+/// The value of a scenario parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Display for ParamValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamValue::Bool(value) => write!(f, "{value}"),
+            ParamValue::Integer(value) => write!(f, "{value}"),
+            ParamValue::Float(value) => write!(f, "{value}"),
+            ParamValue::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl ParamValue {
+    /// Parse a value of the given kind from a `--param` override.
+    fn parse(kind: ParamKind, value: &str) -> anyhow::Result<Self> {
+        Ok(match kind {
+            ParamKind::Bool => ParamValue::Bool(value.parse()?),
+            ParamKind::Integer => ParamValue::Integer(value.parse()?),
+            ParamKind::Float => ParamValue::Float(value.parse()?),
+            ParamKind::String => ParamValue::String(value.to_string()),
+        })
+    }
+
+    /// Convert a value of the given kind from a parameters file.
+    fn from_json(kind: ParamKind, value: &serde_json::Value) -> anyhow::Result<Self> {
+        let converted = match kind {
+            ParamKind::Bool => value.as_bool().map(ParamValue::Bool),
+            ParamKind::Integer => value.as_i64().map(ParamValue::Integer),
+            ParamKind::Float => value.as_f64().map(ParamValue::Float),
+            ParamKind::String => value.as_str().map(|s| ParamValue::String(s.to_string())),
+        };
+
+        converted
+            .ok_or_else(|| anyhow::anyhow!("Expected a value of type {kind:?} but got {value}"))
+    }
+}
+
+/// A type that can be used as a scenario parameter.
+pub trait Param: Sized {
+    /// The kind of value that this parameter is read from.
+    const KIND: ParamKind;
+
+    /// Convert from a parameter value, returning [None] if the value is not valid for this type.
+    fn from_value(value: &ParamValue) -> Option<Self>;
+
+    /// Convert to a parameter value, returning [None] if this value cannot be represented as one,
+    /// for example an unsigned integer that is too large.
+    fn into_value(self) -> Option<ParamValue>;
+}
+
+impl Param for bool {
+    const KIND: ParamKind = ParamKind::Bool;
+
+    fn from_value(value: &ParamValue) -> Option<Self> {
+        match value {
+            ParamValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Option<ParamValue> {
+        Some(ParamValue::Bool(self))
+    }
+}
+
+impl Param for f64 {
+    const KIND: ParamKind = ParamKind::Float;
+
+    fn from_value(value: &ParamValue) -> Option<Self> {
+        match value {
+            ParamValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Option<ParamValue> {
+        Some(ParamValue::Float(self))
+    }
+}
+
+impl Param for String {
+    const KIND: ParamKind = ParamKind::String;
+
+    fn from_value(value: &ParamValue) -> Option<Self> {
+        match value {
+            ParamValue::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Option<ParamValue> {
+        Some(ParamValue::String(self))
+    }
+}
+
+macro_rules! impl_integer_param {
+    ($($t:ty),*) => {
+        $(
+            impl Param for $t {
+                const KIND: ParamKind = ParamKind::Integer;
+
+                fn from_value(value: &ParamValue) -> Option<Self> {
+                    match value {
+                        ParamValue::Integer(value) => (*value).try_into().ok(),
+                        _ => None,
+                    }
+                }
+
+                fn into_value(self) -> Option<ParamValue> {
+                    i64::try_from(self).ok().map(ParamValue::Integer)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_param!(i64, i32, u64, u32, u16, usize);
+
+/// A parameter declared by a scenario.
+pub(crate) struct ParamSpec {
+    kind: ParamKind,
+    /// The default value, or [Err] if the declared default cannot be represented as a value.
+    default: Result<Option<ParamValue>, ()>,
+    /// Checks that a value can be read as the declared type, for example that it is in range.
+    is_valid: fn(&ParamValue) -> bool,
+}
+
+impl ParamSpec {
+    pub(crate) fn new<T: Param>(default: Option<T>) -> Self {
+        Self {
+            kind: T::KIND,
+            default: match default {
+                Some(default) => default.into_value().map(Some).ok_or(()),
+                None => Ok(None),
+            },
+            is_valid: |value| T::from_value(value).is_some(),
+        }
+    }
+}
+
+/// The parameters for a scenario run.
+///
+/// Parameters are declared by the scenario with
+/// [ScenarioDefinitionBuilder::add_param](crate::prelude::ScenarioDefinitionBuilder::add_param).
+/// Values are read from the `--params` file and `--param` overrides, falling back to the declared
+/// default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScenarioParams {
+    values: BTreeMap<String, ParamValue>,
+}
+
+impl ScenarioParams {
+    /// Get the value of a parameter.
+    ///
+    /// Fails if the parameter was not declared by the scenario or was declared with a different type.
+    pub fn get<T: Param>(&self, name: &str) -> anyhow::Result<T> {
+        let value = self
+            .values
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Parameter [{name}] was not declared"))?;
+
+        T::from_value(value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Parameter [{name}] with value {value:?} is not a {}",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Resolve the parameters declared by a scenario.
+    ///
+    /// Priority is given to `overrides`, then the parameters file, then the declared default.
+    pub(crate) fn resolve(
+        specs: &BTreeMap<String, ParamSpec>,
+        params_file: Option<&Path>,
+        overrides: &[(String, String)],
+    ) -> anyhow::Result<Self> {
+        let file_values = match params_file {
+            Some(path) => load_params_file(path)?,
+            None => serde_json::Map::new(),
+        };
+
+        let unknown = file_values
+            .keys()
+            .chain(overrides.iter().map(|(name, _)| name))
+            .filter(|name| !specs.contains_key(*name))
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            anyhow::bail!("Unknown parameters set: {unknown:?}");
+        }
+
+        let mut values = BTreeMap::new();
+        for (name, spec) in specs {
+            let value = match overrides.iter().rev().find(|(n, _)| n == name) {
+                Some((_, value)) => Some(
+                    ParamValue::parse(spec.kind, value)
+                        .with_context(|| format!("Invalid value for parameter [{name}]"))?,
+                ),
+                None => match file_values.get(name) {
+                    Some(value) => Some(
+                        ParamValue::from_json(spec.kind, value)
+                            .with_context(|| format!("Invalid value for parameter [{name}]"))?,
+                    ),
+                    None => spec.default.clone().map_err(|()| {
+                        anyhow::anyhow!("Default value for parameter [{name}] is out of range")
+                    })?,
+                },
+            };
+
+            let Some(value) = value else {
+                anyhow::bail!("Parameter [{name}] is required but was not set");
+            };
+            if !(spec.is_valid)(&value) {
+                anyhow::bail!("Value {value} is out of range for parameter [{name}]");
+            }
+
+            values.insert(name.clone(), value);
+        }
+
+        Ok(Self { values })
+    }
+
+    /// The parameters as strings, for recording in the run summary.
+    pub(crate) fn to_summary(&self) -> BTreeMap<String, String> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect()
+    }
+}
+
+/// Load a parameters file, which is read as JSON if it has a `.json` extension and as TOML
+/// otherwise.
+fn load_params_file(path: &Path) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read parameters file {}", path.display()))?;
+
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse parameters file {}", path.display()))
+    } else {
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse parameters file {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn specs() -> BTreeMap<String, ParamSpec> {
+        let mut specs = BTreeMap::new();
+        specs.insert("min_agents".to_string(), ParamSpec::new::<usize>(Some(2)));
+        specs.insert("send_interval_ms".to_string(), ParamSpec::new::<u64>(None));
+        specs.insert("verbose".to_string(), ParamSpec::new(Some(false)));
+        specs
+    }
+
+    fn params_file(extension: &str, content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{extension}"))
+            .tempfile()
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn params_are_resolved_from_overrides_then_file_then_default() {
+        let file = params_file("toml", "send_interval_ms = 100\nmin_agents = 5\n");
+
+        let params = ScenarioParams::resolve(
+            &specs(),
+            Some(file.path()),
+            &[("min_agents".to_string(), "10".to_string())],
+        )
+        .unwrap();
+
+        assert_eq!(10, params.get::<usize>("min_agents").unwrap());
+        assert_eq!(100, params.get::<u64>("send_interval_ms").unwrap());
+        assert!(!params.get::<bool>("verbose").unwrap());
+    }
+
+    #[test]
+    fn params_can_be_read_from_json() {
+        let file = params_file("json", r#"{"send_interval_ms": 250, "verbose": true}"#);
+
+        let params = ScenarioParams::resolve(&specs(), Some(file.path()), &[]).unwrap();
+
+        assert_eq!(250, params.get::<u64>("send_interval_ms").unwrap());
+        assert!(params.get::<bool>("verbose").unwrap());
+        assert_eq!(
+            BTreeMap::from([
+                ("min_agents".to_string(), "2".to_string()),
+                ("send_interval_ms".to_string(), "250".to_string()),
+                ("verbose".to_string(), "true".to_string()),
+            ]),
+            params.to_summary()
+        );
+    }
+
+    #[test]
+    fn out_of_range_default_is_rejected() {
+        let mut specs = specs();
+        specs.insert(
+            "max_bytes".to_string(),
+            ParamSpec::new::<u64>(Some(u64::MAX)),
+        );
+
+        let result = ScenarioParams::resolve(
+            &specs,
+            None,
+            &[("send_interval_ms".to_string(), "100".to_string())],
+        );
+        assert!(result.is_err());
+
+        // A value that is set does not need the default
+        let params = ScenarioParams::resolve(
+            &specs,
+            None,
+            &[
+                ("send_interval_ms".to_string(), "100".to_string()),
+                ("max_bytes".to_string(), "1024".to_string()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(1024, params.get::<u64>("max_bytes").unwrap());
+    }
+
+    #[test]
+    fn missing_required_param_is_rejected() {
+        let result = ScenarioParams::resolve(&specs(), None, &[]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn unknown_param_is_rejected() {
+        let result = ScenarioParams::resolve(
+            &specs(),
+            None,
+            &[
+                ("send_interval_ms".to_string(), "100".to_string()),
+                ("unknown".to_string(), "1".to_string()),
+            ],
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let wrong_type = ScenarioParams::resolve(
+            &specs(),
+            None,
+            &[("send_interval_ms".to_string(), "fast".to_string())],
+        );
+        assert!(wrong_type.is_err());
+
+        let out_of_range = ScenarioParams::resolve(
+            &specs(),
+            None,
+            &[("send_interval_ms".to_string(), "-1".to_string())],
+        );
+        assert!(out_of_range.is_err());
+    }
+
+    #[test]
+    fn get_with_wrong_type_fails() {
+        let params = ScenarioParams::resolve(
+            &specs(),
+            None,
+            &[("send_interval_ms".to_string(), "100".to_string())],
+        )
+        .unwrap();
+
+        assert!(params.get::<String>("send_interval_ms").is_err());
+        assert!(params.get::<u64>("undeclared").is_err());
+    }
+}
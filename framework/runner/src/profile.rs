@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+/// A stage of a [LoadProfile].
+///
+/// Over the `ramp` duration, the number of active agents moves linearly from the target of the
+/// previous stage to `target_agents`. The target is then held for the `hold` duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadStage {
+    pub target_agents: usize,
+    pub ramp: Duration,
+    pub hold: Duration,
+}
+
+impl LoadStage {
+    fn duration(&self) -> Duration {
+        self.ramp + self.hold
+    }
+}
+
+/// Staged activation of agents over the course of a scenario run.
+///
+/// Agents are activated in order of their index as the target number of active agents rises, and
+/// drained in reverse order as it falls. An agent runs its setup hook when it is activated and its
+/// teardown hook when it is drained. Each agent is activated at most once, so an agent that has
+/// been drained is not activated again if the target rises later in the profile.
+///
+/// For example, to run 10 agents for 60s, then add 10 agents every 30s up to 100, hold for 5
+/// minutes and then drain over 30s:
+/// ```rust
+/// use std::time::Duration;
+/// use wind_tunnel_runner::prelude::LoadProfile;
+///
+/// let mut profile = LoadProfile::new().step_to(10, Duration::from_secs(60));
+/// for target in (20..=100).step_by(10) {
+///     profile = profile.step_to(target, Duration::from_secs(30));
+/// }
+/// let profile = profile
+///     .hold(Duration::from_secs(300))
+///     .ramp_to(0, Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadProfile {
+    stages: Vec<LoadStage>,
+}
+
+impl LoadProfile {
+    /// Create an empty load profile, with no active agents.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a load profile from a list of stages.
+    pub fn from_stages(stages: Vec<LoadStage>) -> Self {
+        Self { stages }
+    }
+
+    /// Linearly ramp the number of active agents to `target_agents` over `ramp`.
+    pub fn ramp_to(mut self, target_agents: usize, ramp: Duration) -> Self {
+        self.stages.push(LoadStage {
+            target_agents,
+            ramp,
+            hold: Duration::ZERO,
+        });
+        self
+    }
+
+    /// Immediately change the number of active agents to `target_agents` and hold for `hold`.
+    pub fn step_to(mut self, target_agents: usize, hold: Duration) -> Self {
+        self.stages.push(LoadStage {
+            target_agents,
+            ramp: Duration::ZERO,
+            hold,
+        });
+        self
+    }
+
+    /// Hold the current number of active agents for `hold`.
+    pub fn hold(self, hold: Duration) -> Self {
+        let target_agents = self.stages.last().map(|s| s.target_agents).unwrap_or(0);
+        self.step_to(target_agents, hold)
+    }
+
+    /// The stages of this profile.
+    pub fn stages(&self) -> &[LoadStage] {
+        &self.stages
+    }
+
+    /// The total time taken to run through every stage.
+    pub(crate) fn total_duration(&self) -> Duration {
+        self.stages.iter().map(LoadStage::duration).sum()
+    }
+
+    /// The highest number of agents that are active at any point.
+    pub(crate) fn max_target(&self) -> usize {
+        self.stages
+            .iter()
+            .map(|s| s.target_agents)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The offsets from the start of the profile at which each stage begins.
+    pub(crate) fn stage_starts(&self) -> Vec<Duration> {
+        self.stages
+            .iter()
+            .scan(Duration::ZERO, |start, stage| {
+                let stage_start = *start;
+                *start += stage.duration();
+                Some(stage_start)
+            })
+            .collect()
+    }
+
+    /// The window during which the agent with `agent_index` is active, as offsets from the start of
+    /// the profile.
+    ///
+    /// Returns [None] if the agent is never activated. Otherwise, returns the activation offset and
+    /// the drain offset, if the agent is drained before the end of the profile.
+    pub(crate) fn active_window(&self, agent_index: usize) -> Option<(Duration, Option<Duration>)> {
+        // The agent is active while the target is at least this level.
+        let level = agent_index + 1;
+
+        let mut activated = None;
+        let mut previous_target = 0;
+        for (stage, start) in self.stages.iter().zip(self.stage_starts()) {
+            let target = stage.target_agents;
+            match activated {
+                None if previous_target < level && target >= level => {
+                    let fraction =
+                        (level - previous_target) as f64 / (target - previous_target) as f64;
+                    activated = Some(start + stage.ramp.mul_f64(fraction));
+                }
+                Some(activated) if previous_target >= level && target < level => {
+                    let fraction =
+                        (previous_target - level) as f64 / (previous_target - target) as f64;
+                    return Some((activated, Some(start + stage.ramp.mul_f64(fraction))));
+                }
+                _ => {}
+            }
+
+            previous_target = target;
+        }
+
+        activated.map(|activated| (activated, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn step_profile_activates_agents_at_each_step() {
+        let profile = LoadProfile::new()
+            .step_to(2, secs(60))
+            .step_to(4, secs(30))
+            .hold(secs(30))
+            .step_to(0, Duration::ZERO);
+
+        assert_eq!(secs(120), profile.total_duration());
+        assert_eq!(4, profile.max_target());
+        assert_eq!(
+            vec![secs(0), secs(60), secs(90), secs(120)],
+            profile.stage_starts()
+        );
+
+        assert_eq!(Some((secs(0), Some(secs(120)))), profile.active_window(0));
+        assert_eq!(Some((secs(0), Some(secs(120)))), profile.active_window(1));
+        assert_eq!(Some((secs(60), Some(secs(120)))), profile.active_window(2));
+        assert_eq!(Some((secs(60), Some(secs(120)))), profile.active_window(3));
+        assert_eq!(None, profile.active_window(4));
+    }
+
+    #[test]
+    fn ramp_profile_activates_and_drains_agents_linearly() {
+        let profile = LoadProfile::new()
+            .ramp_to(4, secs(40))
+            .hold(secs(10))
+            .ramp_to(0, secs(40));
+
+        assert_eq!(Some((secs(10), Some(secs(80)))), profile.active_window(0));
+        assert_eq!(Some((secs(20), Some(secs(70)))), profile.active_window(1));
+        assert_eq!(Some((secs(30), Some(secs(60)))), profile.active_window(2));
+        assert_eq!(Some((secs(40), Some(secs(50)))), profile.active_window(3));
+    }
+
+    #[test]
+    fn agents_still_active_at_the_end_are_not_drained() {
+        let profile = LoadProfile::new().step_to(3, secs(10)).step_to(1, secs(10));
+
+        assert_eq!(Some((secs(0), None)), profile.active_window(0));
+        assert_eq!(Some((secs(0), Some(secs(10)))), profile.active_window(1));
+        assert_eq!(Some((secs(0), Some(secs(10)))), profile.active_window(2));
+    }
+}
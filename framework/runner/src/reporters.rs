@@ -0,0 +1,505 @@
+use crate::cli::ReporterOpt;
+use anyhow::Context;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use wind_tunnel_instruments::prelude::{
+    BackpressurePolicy, FileCompression, InfluxClientConfig, InfluxFileConfig, OtlpConfig,
+    OtlpProtocol, ReportConfig,
+};
+
+/// A reporter to enable for a run, with its options.
+///
+/// Reporters selected with `--reporter` use the default options. To set options, list the
+/// reporters in a TOML or JSON file and pass it with `--reporter-config`. For example:
+/// ```toml
+/// [[reporters]]
+/// type = "influx-file"
+/// dir = "/var/lib/wind-tunnel/metrics"
+/// flush_interval_ms = 5000
+///
+/// [[reporters]]
+/// type = "in-memory"
+/// ```
+///
+/// Directories that are not set fall back to `--metrics-dir`, and Influx connection details that
+/// are not set fall back to the `INFLUX_*` environment variables.
+///
+/// The Influx reporters queue metrics while they are written. When the queue is full, the
+/// `backpressure` policy is either `block`, which slows agents down until there is space, or `drop`,
+/// which drops the metric. Dropped metrics are counted in the run summary. The `influx-file`
+/// reporter blocks by default, and the `influx-client` reporter drops by default so that an
+/// unreachable InfluxDB does not hold up the agents while writes are retried. Each write to InfluxDB
+/// waits at most `request_timeout_ms` for a response, and metrics that are still being written
+/// when the run shuts down get one more request timeout before they are spilled to disk.
+///
+/// The `influx-file` reporter starts a new file once the current one reaches `max_file_size_mb` or
+/// has been open for `rotate_interval_s`, and compresses closed files if `compression` is set to
+/// `gzip` or `zstd`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) enum ReporterConfig {
+    Noop,
+    InMemory,
+    InMemoryWithCustomMetrics,
+    InfluxFile {
+        dir: Option<PathBuf>,
+        flush_interval_ms: Option<u64>,
+        queue_capacity: Option<usize>,
+        backpressure: Option<BackpressurePolicy>,
+        max_file_size_mb: Option<u64>,
+        rotate_interval_s: Option<u64>,
+        compression: Option<FileCompression>,
+    },
+    InfluxClient {
+        url: Option<String>,
+        org: Option<String>,
+        bucket: Option<String>,
+        token: Option<String>,
+        spill_dir: Option<PathBuf>,
+        flush_interval_ms: Option<u64>,
+        request_timeout_ms: Option<u64>,
+        queue_capacity: Option<usize>,
+        backpressure: Option<BackpressurePolicy>,
+    },
+    Otlp {
+        endpoint: Option<String>,
+        protocol: Option<OtlpProtocol>,
+        export_interval_ms: Option<u64>,
+    },
+}
+
+impl From<ReporterOpt> for ReporterConfig {
+    fn from(value: ReporterOpt) -> Self {
+        match value {
+            ReporterOpt::Noop => ReporterConfig::Noop,
+            ReporterOpt::InMemory => ReporterConfig::InMemory,
+            ReporterOpt::InMemoryWithCustomMetrics => ReporterConfig::InMemoryWithCustomMetrics,
+            ReporterOpt::InfluxFile => ReporterConfig::InfluxFile {
+                dir: None,
+                flush_interval_ms: None,
+                queue_capacity: None,
+                backpressure: None,
+                max_file_size_mb: None,
+                rotate_interval_s: None,
+                compression: None,
+            },
+            ReporterOpt::InfluxClient => ReporterConfig::InfluxClient {
+                url: None,
+                org: None,
+                bucket: None,
+                token: None,
+                spill_dir: None,
+                flush_interval_ms: None,
+                request_timeout_ms: None,
+                queue_capacity: None,
+                backpressure: None,
+            },
+            ReporterOpt::Otlp => ReporterConfig::Otlp {
+                endpoint: None,
+                protocol: None,
+                export_interval_ms: None,
+            },
+        }
+    }
+}
+
+impl ReporterConfig {
+    fn name(&self) -> &'static str {
+        match self {
+            ReporterConfig::Noop => "noop",
+            ReporterConfig::InMemory => "in-memory",
+            ReporterConfig::InMemoryWithCustomMetrics => "in-memory-with-custom-metrics",
+            ReporterConfig::InfluxFile { .. } => "influx-file",
+            ReporterConfig::InfluxClient { .. } => "influx-client",
+            ReporterConfig::Otlp { .. } => "otlp",
+        }
+    }
+
+    /// Enable this reporter on the report config.
+    fn enable(
+        &self,
+        report_config: ReportConfig,
+        metrics_dir: Option<&Path>,
+    ) -> anyhow::Result<ReportConfig> {
+        let metrics_dir = |dir: &Option<PathBuf>| {
+            dir.as_deref()
+                .or(metrics_dir)
+                .map(Path::to_path_buf)
+                .with_context(|| {
+                    format!(
+                        "The {} reporter needs a directory, set one in the reporter config or with --metrics-dir",
+                        self.name()
+                    )
+                })
+        };
+        let env = |name: &str| {
+            std::env::var(name).with_context(|| format!("Missing environment variable {name}"))
+        };
+
+        Ok(match self {
+            ReporterConfig::Noop => report_config,
+            ReporterConfig::InMemory => report_config.enable_in_memory(),
+            ReporterConfig::InMemoryWithCustomMetrics => {
+                report_config.enable_in_memory_with_custom_metrics()
+            }
+            ReporterConfig::InfluxFile {
+                dir,
+                flush_interval_ms,
+                queue_capacity,
+                backpressure,
+                max_file_size_mb,
+                rotate_interval_s,
+                compression,
+            } => {
+                let mut config = InfluxFileConfig::new(metrics_dir(dir)?);
+                if let Some(flush_interval_ms) = flush_interval_ms {
+                    config = config.with_flush_interval(Duration::from_millis(*flush_interval_ms));
+                }
+                let capacity = queue_capacity.unwrap_or(config.queue_capacity);
+                let backpressure = backpressure.unwrap_or(config.backpressure);
+                config = config.with_queue(capacity, backpressure);
+                if let Some(max_file_size_mb) = max_file_size_mb {
+                    config = config.with_max_file_size(max_file_size_mb * 1024 * 1024);
+                }
+                if let Some(rotate_interval_s) = rotate_interval_s {
+                    config = config.with_rotate_interval(Duration::from_secs(*rotate_interval_s));
+                }
+                if let Some(compression) = compression {
+                    config = config.with_compression(*compression);
+                }
+                report_config.enable_influx_file(config)
+            }
+            ReporterConfig::InfluxClient {
+                url,
+                org,
+                bucket,
+                token,
+                spill_dir,
+                flush_interval_ms,
+                request_timeout_ms,
+                queue_capacity,
+                backpressure,
+            } => {
+                let mut config = InfluxClientConfig::new(
+                    url.clone().map_or_else(|| env("INFLUX_HOST"), Ok)?,
+                    org.clone()
+                        .or_else(|| env("INFLUX_ORG").ok())
+                        .unwrap_or_else(|| "holo".to_string()),
+                    bucket.clone().map_or_else(|| env("INFLUX_BUCKET"), Ok)?,
+                    metrics_dir(spill_dir)?,
+                )
+                .with_token(token.clone().map_or_else(|| env("INFLUX_TOKEN"), Ok)?);
+                if let Some(flush_interval_ms) = flush_interval_ms {
+                    config = config.with_flush_interval(Duration::from_millis(*flush_interval_ms));
+                }
+                if let Some(request_timeout_ms) = request_timeout_ms {
+                    config =
+                        config.with_request_timeout(Duration::from_millis(*request_timeout_ms));
+                }
+                let capacity = queue_capacity.unwrap_or(config.queue_capacity);
+                let backpressure = backpressure.unwrap_or(config.backpressure);
+                config = config.with_queue(capacity, backpressure);
+                report_config.enable_influx_client(config)
+            }
+            ReporterConfig::Otlp {
+                endpoint,
+                protocol,
+                export_interval_ms,
+            } => {
+                let protocol = match (protocol, env("OTEL_EXPORTER_OTLP_PROTOCOL")) {
+                    (Some(protocol), _) => *protocol,
+                    (None, Ok(protocol)) => parse_otlp_protocol(&protocol)?,
+                    (None, Err(_)) => OtlpProtocol::HttpProtobuf,
+                };
+                let mut config = OtlpConfig::new(protocol);
+                if let Some(endpoint) = endpoint {
+                    config = config.with_endpoint(endpoint.clone());
+                }
+                if let Some(export_interval_ms) = export_interval_ms {
+                    config =
+                        config.with_export_interval(Duration::from_millis(*export_interval_ms));
+                }
+                report_config.enable_otlp(config)
+            }
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReporterConfigFile {
+    reporters: Vec<ReporterConfig>,
+}
+
+/// Resolve the reporters to use, from the reporter config file if one is given and otherwise from
+/// the reporters selected on the command line.
+pub(crate) fn resolve_reporters(
+    reporter: &[ReporterOpt],
+    reporter_config: Option<&Path>,
+) -> anyhow::Result<Vec<ReporterConfig>> {
+    let reporters = match reporter_config {
+        Some(path) => load_reporter_config_file(path)?.reporters,
+        None => reporter.iter().copied().map(ReporterConfig::from).collect(),
+    };
+
+    for (i, reporter) in reporters.iter().enumerate() {
+        if reporters[..i].iter().any(|r| r.name() == reporter.name()) {
+            anyhow::bail!(
+                "The {} reporter is configured more than once",
+                reporter.name()
+            );
+        }
+    }
+
+    Ok(reporters)
+}
+
+/// Enable each of the reporters on the report config.
+pub(crate) fn enable_reporters(
+    mut report_config: ReportConfig,
+    reporters: &[ReporterConfig],
+    metrics_dir: Option<&Path>,
+) -> anyhow::Result<ReportConfig> {
+    if reporters
+        .iter()
+        .all(|reporter| *reporter == ReporterConfig::Noop)
+    {
+        log::info!("No reporter enabled");
+    }
+
+    for reporter in reporters {
+        report_config = reporter.enable(report_config, metrics_dir)?;
+    }
+
+    Ok(report_config)
+}
+
+/// Parse the OTLP protocol from the `OTEL_EXPORTER_OTLP_PROTOCOL` environment variable, which is
+/// used when the protocol is not set in the reporter config.
+fn parse_otlp_protocol(protocol: &str) -> anyhow::Result<OtlpProtocol> {
+    match protocol {
+        "grpc" => Ok(OtlpProtocol::Grpc),
+        "http/protobuf" => Ok(OtlpProtocol::HttpProtobuf),
+        other => anyhow::bail!("Unsupported OTLP protocol: {other}"),
+    }
+}
+
+fn load_reporter_config_file(path: &Path) -> anyhow::Result<ReporterConfigFile> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read reporter config file {}", path.display()))?;
+
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse reporter config file {}", path.display()))
+    } else {
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse reporter config file {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn config_file(extension: &str, content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{extension}"))
+            .tempfile()
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn reporters_from_cli_use_defaults() {
+        let reporters =
+            resolve_reporters(&[ReporterOpt::InfluxFile, ReporterOpt::InMemory], None).unwrap();
+
+        assert_eq!(
+            vec![
+                ReporterConfig::InfluxFile {
+                    dir: None,
+                    flush_interval_ms: None,
+                    queue_capacity: None,
+                    backpressure: None,
+                    max_file_size_mb: None,
+                    rotate_interval_s: None,
+                    compression: None,
+                },
+                ReporterConfig::InMemory,
+            ],
+            reporters
+        );
+    }
+
+    #[test]
+    fn reporters_from_toml_file() {
+        let file = config_file(
+            "toml",
+            r#"
+            [[reporters]]
+            type = "influx-file"
+            dir = "/tmp/metrics"
+            flush_interval_ms = 5000
+            queue_capacity = 1000
+            backpressure = "drop"
+            max_file_size_mb = 512
+            rotate_interval_s = 3600
+            compression = "zstd"
+
+            [[reporters]]
+            type = "in-memory"
+            "#,
+        );
+
+        let reporters = resolve_reporters(&[], Some(file.path())).unwrap();
+
+        assert_eq!(
+            vec![
+                ReporterConfig::InfluxFile {
+                    dir: Some(PathBuf::from("/tmp/metrics")),
+                    flush_interval_ms: Some(5000),
+                    queue_capacity: Some(1000),
+                    backpressure: Some(BackpressurePolicy::Drop),
+                    max_file_size_mb: Some(512),
+                    rotate_interval_s: Some(3600),
+                    compression: Some(FileCompression::Zstd),
+                },
+                ReporterConfig::InMemory,
+            ],
+            reporters
+        );
+    }
+
+    #[test]
+    fn reporters_from_json_file() {
+        let file = config_file(
+            "json",
+            r#"{"reporters": [{"type": "otlp", "protocol": "grpc", "endpoint": "http://localhost:4317"}]}"#,
+        );
+
+        let reporters = resolve_reporters(&[], Some(file.path())).unwrap();
+
+        assert_eq!(
+            vec![ReporterConfig::Otlp {
+                endpoint: Some("http://localhost:4317".to_string()),
+                protocol: Some(OtlpProtocol::Grpc),
+                export_interval_ms: None,
+            }],
+            reporters
+        );
+    }
+
+    #[test]
+    fn unknown_reporter_options_are_rejected() {
+        let file = config_file(
+            "toml",
+            r#"
+            [[reporters]]
+            type = "influx-file"
+            directory = "/tmp/metrics"
+            "#,
+        );
+
+        assert!(resolve_reporters(&[], Some(file.path())).is_err());
+    }
+
+    #[test]
+    fn unsupported_reporter_option_values_are_rejected() {
+        for (option, value) in [
+            ("backpressure", "wait"),
+            ("compression", "brotli"),
+            ("protocol", "http/json"),
+        ] {
+            let reporter = if option == "protocol" {
+                "otlp"
+            } else {
+                "influx-file"
+            };
+            let file = config_file(
+                "toml",
+                &format!(
+                    r#"
+                    [[reporters]]
+                    type = "{reporter}"
+                    {option} = "{value}"
+                    "#
+                ),
+            );
+
+            let err = resolve_reporters(&[], Some(file.path())).unwrap_err();
+            assert!(
+                format!("{err:#}").contains(&format!("unknown variant `{value}`")),
+                "{err:#}"
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_reporters_are_rejected() {
+        let err =
+            resolve_reporters(&[ReporterOpt::InMemory, ReporterOpt::InMemory], None).unwrap_err();
+
+        assert_eq!(
+            "The in-memory reporter is configured more than once",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn influx_file_needs_a_directory() {
+        let reporters = [ReporterConfig::from(ReporterOpt::InfluxFile)];
+        let report_config = || ReportConfig::new("run".to_string(), "scenario".to_string());
+
+        assert!(enable_reporters(report_config(), &reporters, None).is_err());
+
+        let report_config =
+            enable_reporters(report_config(), &reporters, Some(Path::new("/tmp/metrics"))).unwrap();
+        assert!(report_config.enable_influx_file);
+        assert_eq!(
+            Some(PathBuf::from("/tmp/metrics")),
+            report_config.influx_file.map(|config| config.dir)
+        );
+    }
+
+    #[test]
+    fn otlp_is_enabled_by_its_reporter() {
+        let reporters = resolve_reporters(&[ReporterOpt::Otlp], None).unwrap();
+        let report_config = || ReportConfig::new("run".to_string(), "scenario".to_string());
+
+        assert!(report_config().otlp.is_none());
+        let report_config = enable_reporters(report_config(), &reporters, None).unwrap();
+        assert!(report_config.otlp.is_some());
+    }
+
+    #[test]
+    fn influx_client_drops_metrics_by_default() {
+        let reporters = [ReporterConfig::InfluxClient {
+            url: Some("http://localhost:8087".to_string()),
+            org: None,
+            bucket: Some("windtunnel".to_string()),
+            token: Some("token".to_string()),
+            spill_dir: None,
+            flush_interval_ms: None,
+            request_timeout_ms: None,
+            queue_capacity: None,
+            backpressure: None,
+        }];
+
+        let report_config = enable_reporters(
+            ReportConfig::new("run".to_string(), "scenario".to_string()),
+            &reporters,
+            Some(Path::new("/tmp/metrics")),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(BackpressurePolicy::Drop),
+            report_config
+                .influx_client
+                .map(|config| config.backpressure)
+        );
+    }
+}
@@ -1,32 +1,51 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
 use std::sync::atomic::AtomicUsize;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::cli::ReporterOpt;
+use crate::agent::AgentRun;
+use crate::barrier::{BarrierRelease, SetupBarrier};
+use crate::clock::measure_clock_offset;
+use crate::failure::FailureTracker;
 use crate::monitor::start_monitor;
+use crate::profile::LoadProfile;
 use crate::progress::start_progress;
+use crate::reporters::enable_reporters;
+use crate::schedule::{ArrivalSchedule, RateScope};
+use crate::thresholds::{RunOutcome, check_thresholds};
 use crate::{
     context::{AgentContext, RunnerContext, UserValuesConstraint},
-    definition::ScenarioDefinitionBuilder,
+    definition::{AgentHook, ScenarioDefinitionBuilder},
     executor::Executor,
-    shutdown::start_shutdown_listener,
+    shutdown::{ShutdownReasonCell, start_shutdown_listener},
 };
 use anyhow::Context;
 use log::debug;
-use wind_tunnel_core::prelude::{AgentBailError, ShutdownHandle, ShutdownSignalError};
-use wind_tunnel_instruments::ReportConfig;
-use wind_tunnel_summary_model::{RunSummaryInitArgs, append_run_summary};
+use wind_tunnel_core::prelude::{DelegatedShutdownListener, ShutdownHandle};
+use wind_tunnel_instruments::prelude::ReportMetric;
+use wind_tunnel_instruments::{ReportConfig, Reporter};
+use wind_tunnel_summary_model::{
+    AgentOutcome, CounterTotal, RunSummaryInitArgs, SetupOutcome, ShutdownReason,
+    append_run_summary,
+};
 
-/// Environment variable name to set a custom run summary file path
-const RUN_SUMMARY_PATH_ENV: &str = "RUN_SUMMARY_PATH";
 /// Default path for the run summary file
 const DEFAULT_RUN_SUMMARY_PATH: &str = "run_summary.jsonl";
 
+/// How often the values recorded by typed metrics are reported.
+const METRICS_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A handle to an agent, which runs either on its own thread or as a task on the shared runtime.
+enum AgentHandle {
+    Thread(std::thread::JoinHandle<AgentOutcome>),
+    Task(tokio::task::JoinHandle<AgentOutcome>),
+}
+
 pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
     definition: ScenarioDefinitionBuilder<RV, V>,
 ) -> anyhow::Result<usize> {
-    let definition = definition.build()?;
+    let mut definition = definition.build()?;
 
     println!("#RunId: [{}]", definition.run_id);
 
@@ -46,6 +65,8 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
             .to_string(),
     })
     .with_run_duration(definition.duration_s)
+    .with_warmup(definition.warmup_s)
+    .with_params(definition.params.to_summary())
     .with_assigned_behaviours(
         definition
             .assigned_behaviours
@@ -64,7 +85,8 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
 
     let runtime = tokio::runtime::Runtime::new().context("Failed to create Tokio runtime")?;
 
-    let shutdown_handle = start_shutdown_listener(&runtime)?;
+    let shutdown_reason = ShutdownReasonCell::default();
+    let shutdown_handle = start_shutdown_listener(&runtime, shutdown_reason.clone())?;
     let report_shutdown_handle = ShutdownHandle::default();
 
     let reporter = {
@@ -72,36 +94,50 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
         let mut report_config =
             ReportConfig::new(definition.run_id.clone(), definition.name.clone());
 
-        match definition.reporter {
-            ReporterOpt::InMemory => {
-                report_config = report_config.enable_in_memory();
-            }
-            ReporterOpt::InMemoryWithCustomMetrics => {
-                report_config = report_config.enable_in_memory_with_custom_metrics();
-            }
-            ReporterOpt::InfluxFile => {
-                let dir = PathBuf::from(
-                    std::env::var("WT_METRICS_DIR")
-                        .context("Missing environment variable WT_METRICS_DIR".to_string())?,
+        report_config = enable_reporters(
+            report_config,
+            &definition.reporters,
+            definition.metrics_dir.as_deref(),
+        )?;
+
+        if definition
+            .thresholds
+            .iter()
+            .any(|threshold| threshold.needs_operation_stats())
+        {
+            report_config = report_config.enable_operation_stats();
+        }
+
+        Arc::new(report_config.init_reporter(runtime.handle(), &report_shutdown_handle)?)
+    };
+    if let Some(time_server) = &definition.time_server {
+        match measure_clock_offset(time_server) {
+            Ok(clock_offset) => {
+                log::info!(
+                    "Clock offset from time server {time_server} is {}ns, with a round trip of {}ns",
+                    clock_offset.offset_ns,
+                    clock_offset.round_trip_ns
                 );
-                report_config = report_config.enable_influx_file(dir);
+                reporter.set_clock_offset(clock_offset.offset_ns);
+                summary.set_clock_offset(clock_offset);
             }
-            ReporterOpt::Noop => {
-                log::info!("No reporter enabled");
+            Err(e) => {
+                log::warn!(
+                    "Could not measure the clock offset from time server {time_server}: {e:?}"
+                )
             }
         }
+    }
 
-        Arc::new(
-            report_config.init_reporter(runtime.handle(), report_shutdown_handle.new_listener())?,
-        )
-    };
     let executor = Arc::new(Executor::new(runtime, shutdown_handle.clone()));
     let mut runner_context = RunnerContext::new(
         executor,
         reporter,
         shutdown_handle.clone(),
+        shutdown_reason.clone(),
         definition.run_id.clone(),
         definition.connection_string.clone(),
+        definition.params.clone(),
     );
 
     if let Some(setup_fn) = &definition.setup_fn {
@@ -112,7 +148,7 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
     let runner_context_for_teardown = runner_context.clone();
 
     // Get build info and add to summary
-    if let Some(build_info_fn) = definition.build_info_fn {
+    if let Some(build_info_fn) = &definition.build_info_fn {
         match build_info_fn(runner_context.clone()) {
             Ok(Some(build_info)) => {
                 summary.set_build_info(build_info);
@@ -124,24 +160,20 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
         }
     }
 
-    // After the setup has run, and if this is a time bounded scenario, then we need to take additional actions
-    if let Some(duration) = definition.duration_s {
-        if !definition.no_progress {
-            // If the scenario is time bounded then start the progress monitor to show the user how long is left
-            start_progress(
-                Duration::from_secs(duration),
-                shutdown_handle.new_listener(),
-            );
-        }
-
-        // Set a timer to shut down the test after the duration has elapsed
-        let shutdown_handle = shutdown_handle.clone();
-        runner_context.executor().spawn(async move {
-            tokio::time::sleep(tokio::time::Duration::from_secs(duration)).await;
-            shutdown_handle.shutdown();
-        });
+    // Without a setup barrier, the run is timed from when the agents are spawned. Otherwise, it is
+    // timed from when the barrier is released.
+    if definition.setup_barrier_timeout.is_none() {
+        start_run_timer(
+            &runner_context,
+            definition.duration_s,
+            definition.warmup_s,
+            definition.no_progress,
+            &shutdown_handle,
+        );
     }
 
+    start_metrics_flush(&runner_context, report_shutdown_handle.new_listener());
+
     // Ready to start spawning agents so start the resource monitor to report high usage by agents
     // which might lead to a misleading outcome.
     start_monitor(shutdown_handle.new_listener());
@@ -150,96 +182,163 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
 
     let agents_run_to_completion = Arc::new(AtomicUsize::new(0));
 
+    let failures = Arc::new(FailureTracker::new(
+        definition.failure_policy.clone(),
+        definition.error_classifier.take(),
+        shutdown_handle.clone(),
+        shutdown_reason.clone(),
+    ));
+
+    // Schedules for open-model behaviours whose rate is shared between all of their agents.
+    let global_schedules = definition
+        .arrival_rates
+        .iter()
+        .filter(|(_, (_, scope))| *scope == RateScope::Global)
+        .map(|(behaviour, (rate, _))| {
+            (
+                behaviour.clone(),
+                Arc::new(Mutex::new(ArrivalSchedule::new(
+                    rate.clone(),
+                    definition.max_schedule_lag,
+                ))),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let setup_barrier = definition
+        .setup_barrier_timeout
+        .map(|timeout| Arc::new(SetupBarrier::new(assigned_behaviours.len(), timeout)));
+
+    // Agents are activated and drained relative to this point when running with a load profile.
+    let profile_started = Instant::now();
+    if let Some(load_profile) = &definition.load_profile {
+        start_load_profile_phase_reporting(
+            &runner_context,
+            load_profile,
+            profile_started,
+            shutdown_handle.new_listener(),
+        );
+    }
+
     let mut handles = Vec::new();
     for (agent_index, assigned_behaviour) in assigned_behaviours.iter().enumerate() {
-        // Read access to the runner context for each agent
-        let runner_context = runner_context.clone();
-
-        let setup_agent_fn = definition.setup_agent_fn;
-        let agent_behaviour_fn = definition.agent_behaviour.get(assigned_behaviour).cloned();
-        let teardown_agent_fn = definition.teardown_agent_fn;
+        let active_window = match &definition.load_profile {
+            Some(load_profile) => match load_profile.active_window(agent_index) {
+                Some((activate, drain)) => Some((
+                    profile_started + activate,
+                    drain.map(|drain| profile_started + drain),
+                )),
+                None => {
+                    log::debug!("Agent {agent_index} is never activated by the load profile");
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let schedule = match definition.arrival_rates.get(assigned_behaviour) {
+            Some((_, RateScope::Global)) => global_schedules.get(assigned_behaviour).cloned(),
+            Some((rate, RateScope::PerAgent)) => Some(Arc::new(Mutex::new(ArrivalSchedule::new(
+                rate.clone(),
+                definition.max_schedule_lag,
+            )))),
+            None => None,
+        };
 
-        let agents_run_to_completion = agents_run_to_completion.clone();
-
-        // For us to check if the agent should shut down between behaviour cycles
-        let mut cycle_shutdown_receiver = shutdown_handle.new_listener();
-        // For the behaviour implementation to listen for shutdown and respond appropriately
-        let delegated_shutdown_listener = shutdown_handle.new_listener();
-
-        let assigned_behaviour = assigned_behaviour.clone();
         let agent_name = format!("agent-{agent_index}");
+        let outcome = AgentOutcome {
+            agent_name: agent_name.clone(),
+            behaviour: assigned_behaviour.clone(),
+            setup: SetupOutcome::NotRun,
+            bail_reason: None,
+            stopped_before_behaviour: false,
+            iterations: 0,
+        };
+        let behaviour = definition.agent_behaviour.get(assigned_behaviour).cloned();
+        let is_async = behaviour.as_ref().is_some_and(AgentHook::is_async);
+
+        let agent = AgentRun {
+            context: AgentContext::new(
+                agent_index,
+                agent_name.clone(),
+                assigned_behaviour.clone(),
+                // Read access to the runner context for each agent
+                runner_context.clone(),
+                // For the behaviour implementation to listen for shutdown and respond appropriately
+                shutdown_handle.new_listener(),
+            ),
+            setup: definition.setup_agent_fn.clone(),
+            behaviour,
+            teardown: definition.teardown_agent_fn.clone(),
+            active_window,
+            schedule,
+            setup_barrier: setup_barrier.clone(),
+            reporter: runner_context.reporter(),
+            failures: failures.clone(),
+            cycle_shutdown_receiver: shutdown_handle.new_listener(),
+            agents_run_to_completion: agents_run_to_completion.clone(),
+            outcome: outcome.clone(),
+        };
+
+        if is_async {
+            handles.push((
+                AgentHandle::Task(runner_context.executor().spawn_agent(agent.run_async())),
+                outcome,
+            ));
+        } else {
+            handles.push((
+                AgentHandle::Thread(
+                    std::thread::Builder::new()
+                        .name(agent_name)
+                        .spawn(move || agent.run_blocking())
+                        .expect("Failed to spawn thread for test agent"),
+                ),
+                outcome,
+            ));
+        }
+    }
 
-        handles.push(
-            std::thread::Builder::new()
-                .name(agent_name.clone())
-                .spawn(move || {
-                    // TODO synchronize these setups so that the scenario waits for all of them to complete before proceeding.
-                    let mut context = AgentContext::new(
-                        agent_index,
-                        agent_name.clone(),
-                        assigned_behaviour.clone(),
-                        runner_context,
-                        delegated_shutdown_listener,
-                    );
-                    if let Some(Err(e)) = setup_agent_fn.map(|f| f(&mut context)) {
-                        log::error!("Agent setup failed for agent {agent_name}: {e:?}");
-
-                        if let Some(Err(e)) = teardown_agent_fn.map(|f| f(&mut context)) {
-                            log::error!("Agent teardown failed for agent {agent_name}: {e:?}");
-                        }
-
-                        return;
-                    }
-
-                    // TODO implement warmup
-                    let mut behaviour_ran_to_complete = true;
-                    if let Some(behaviour) = agent_behaviour_fn {
-                        loop {
-                            if cycle_shutdown_receiver.should_shutdown() {
-                                log::debug!("Stopping agent {agent_name}");
-                                break;
-                            }
-
-                            match behaviour(&mut context) {
-                                Ok(()) => {}
-                                Err(e) if e.is::<ShutdownSignalError>() => {
-                                    // Do nothing, this is expected if the agent is being shutdown.
-                                    // The check at the top of the loop will catch this and break out.
-                                }
-                                Err(e) if e.is::<AgentBailError>() => {
-                                    // A single agent has failed, we don't want to stop the whole
-                                    // scenario so warn and exit the loop.
-                                    log::warn!("Agent {agent_name} bailed: {e:?}");
-                                    behaviour_ran_to_complete = false;
-                                    break;
-                                }
-                                Err(e) => {
-                                    log::error!(
-                                        "Agent behaviour [{assigned_behaviour}] failed for agent {agent_name}: {e:?}"
-                                    );
-                                }
-                            }
-                        }
-                    }
-
-                    if let Some(Err(e)) = teardown_agent_fn.map(|f| f(&mut context)) {
-                        log::error!("Agent teardown failed for agent {agent_name}: {e:?}");
-                    }
-
-                    if behaviour_ran_to_complete {
-                        agents_run_to_completion.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-                    }
-                })
-                .expect("Failed to spawn thread for test agent"),
+    if let Some(setup_barrier) = &setup_barrier
+        && let Some(release) = setup_barrier.wait(&mut shutdown_handle.new_listener())
+    {
+        report_barrier_release(&runner_context.reporter(), &release);
+        start_run_timer(
+            &runner_context,
+            definition.duration_s,
+            definition.warmup_s,
+            definition.no_progress,
+            &shutdown_handle,
         );
     }
 
-    for (index, handle) in handles.into_iter().enumerate() {
-        if let Err(e) = handle.join() {
-            log::error!("Could not join thread for test agent {index}: {e:?}")
+    let mut agent_outcomes = Vec::with_capacity(handles.len());
+    for (index, (handle, mut outcome)) in handles.into_iter().enumerate() {
+        match handle {
+            AgentHandle::Thread(handle) => match handle.join() {
+                Ok(joined) => outcome = joined,
+                Err(e) => {
+                    log::error!("Could not join thread for test agent {index}: {e:?}");
+                    outcome.bail_reason = Some("Agent thread panicked".to_string());
+                }
+            },
+            AgentHandle::Task(handle) => match runner_context.executor().join_agent(handle) {
+                Ok(joined) => outcome = joined,
+                Err(e) => {
+                    log::error!("Could not join task for test agent {index}: {e:?}");
+                    outcome.bail_reason = Some(format!("Agent task failed: {e}"));
+                }
+            },
         }
+        agent_outcomes.push(outcome);
     }
 
+    // If nothing shut the run down, then every agent stopped by itself.
+    let shutdown_reason = shutdown_reason
+        .get()
+        .unwrap_or(ShutdownReason::AgentsFinished);
+    log::info!("Run ended: {shutdown_reason:?}");
+    summary.set_end(chrono::Utc::now().timestamp(), shutdown_reason);
+
     if let Some(teardown_fn) = definition.teardown_fn {
         // Don't crash the runner if the teardown fails. We still want the reporting and runner
         // shutdown to happen cleanly. The hook is documented as 'best effort'
@@ -250,16 +349,80 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
 
     // Manually shutdown the reporting once all the teardown steps are complete, this doesn't
     // respond to Ctrl+C like the user-provided code does.
+    runner_context_for_teardown.reporter().flush_metrics();
     report_shutdown_handle.shutdown();
     // Then wait for the reporting to finish
     runner_context_for_teardown.reporter().finalize();
 
-    summary.set_peer_end_count(agents_run_to_completion.load(std::sync::atomic::Ordering::Acquire));
+    let pipeline_stats = runner_context_for_teardown.reporter().pipeline_stats();
+    if pipeline_stats.dropped > 0 || pipeline_stats.failed > 0 {
+        log::warn!(
+            "Metrics are incomplete, {} were dropped and {} failed to be written",
+            pipeline_stats.dropped,
+            pipeline_stats.failed
+        );
+    }
+
+    let agents_completed = agents_run_to_completion.load(std::sync::atomic::Ordering::Acquire);
+    summary.set_peer_end_count(agents_completed);
+    let (behaviour_iterations, behaviour_errors) = failures.counts();
+
+    let verdict = (!definition.thresholds.is_empty()).then(|| {
+        check_thresholds(
+            &definition.thresholds,
+            &RunOutcome {
+                operation_stats: runner_context_for_teardown.reporter().operation_stats(),
+                behaviour_iterations,
+                behaviour_errors: behaviour_errors.values().sum(),
+                agents_completed,
+            },
+        )
+    });
+    if let Some(verdict) = &verdict {
+        for check in &verdict.checks {
+            if check.passed {
+                log::info!(
+                    "Threshold passed: {} ({:?})",
+                    check.threshold,
+                    check.observed
+                );
+            } else {
+                log::error!(
+                    "Threshold failed: {} ({:?})",
+                    check.threshold,
+                    check.observed
+                );
+            }
+        }
+        summary.set_verdict(verdict.clone());
+    }
+
+    summary.set_agent_outcomes(agent_outcomes);
+    for (name, overlay) in runner_context_for_teardown.config_overlays() {
+        summary.add_config_overlay(name, overlay);
+    }
+    summary.set_behaviour_iterations(behaviour_iterations);
+    summary.set_behaviour_errors(behaviour_errors);
+    summary.set_operation_errors(runner_context_for_teardown.reporter().operation_errors());
+    summary.set_lost_metrics(pipeline_stats.dropped, pipeline_stats.failed);
+    summary.set_counters(
+        runner_context_for_teardown
+            .reporter()
+            .counter_totals()
+            .into_iter()
+            .map(|counter| CounterTotal {
+                name: counter.name,
+                tags: counter.tags.into_iter().collect(),
+                total: counter.total,
+            })
+            .collect(),
+    );
 
     // append run summary
-    let summary_path = std::env::var(RUN_SUMMARY_PATH_ENV)
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from(DEFAULT_RUN_SUMMARY_PATH));
+    let summary_path = definition
+        .run_summary_path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_RUN_SUMMARY_PATH));
     debug!("Appending run summary to {}", summary_path.display());
     if let Err(e) = append_run_summary(summary, summary_path) {
         log::error!("Failed to append run summary: {e:?}");
@@ -274,5 +437,138 @@ pub fn run<RV: UserValuesConstraint, V: UserValuesConstraint>(
         .executor()
         .shutdown_with_timeout(Duration::from_secs(30));
 
-    Ok(agents_run_to_completion.load(std::sync::atomic::Ordering::Acquire))
+    if let Some(reason) = failures.abort_reason() {
+        return Err(anyhow::anyhow!("Scenario aborted: {reason}"));
+    }
+
+    if let Some(verdict) = verdict
+        && !verdict.passed
+    {
+        let failed = verdict
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.threshold.as_str())
+            .collect::<Vec<_>>();
+        return Err(anyhow::anyhow!("Scenario failed thresholds: {failed:?}"));
+    }
+
+    Ok(agents_completed)
+}
+
+/// Start timing the run, from the point that agents start their behaviours.
+///
+/// If the scenario is time bounded, then the scenario is shut down once the duration has elapsed.
+/// The warmup period, if there is one, starts at the same time.
+fn start_run_timer<RV: UserValuesConstraint>(
+    runner_context: &RunnerContext<RV>,
+    duration_s: Option<u64>,
+    warmup_s: Option<u64>,
+    no_progress: bool,
+    shutdown_handle: &ShutdownHandle,
+) {
+    if let Some(duration) = duration_s {
+        if !no_progress {
+            // If the scenario is time bounded then start the progress monitor to show the user how long is left
+            start_progress(
+                Duration::from_secs(duration),
+                shutdown_handle.new_listener(),
+            );
+        }
+
+        // Set a timer to shut down the test after the duration has elapsed
+        let shutdown_handle = shutdown_handle.clone();
+        let shutdown_reason = runner_context.shutdown_reason().clone();
+        runner_context.executor().spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_secs(duration)).await;
+            shutdown_reason.shutdown(&shutdown_handle, ShutdownReason::DurationElapsed);
+        });
+    }
+
+    if let Some(warmup) = warmup_s {
+        log::info!("Warming up for {warmup}s");
+        runner_context
+            .reporter()
+            .start_warmup(Duration::from_secs(warmup));
+    }
+}
+
+/// Periodically report the values recorded by typed metrics, until the reporters are shut down.
+fn start_metrics_flush<RV: UserValuesConstraint>(
+    runner_context: &RunnerContext<RV>,
+    mut shutdown_listener: DelegatedShutdownListener,
+) {
+    let reporter = runner_context.reporter();
+    runner_context.executor().spawn(async move {
+        let mut interval = tokio::time::interval(METRICS_FLUSH_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => reporter.flush_metrics(),
+                _ = shutdown_listener.wait_for_shutdown() => break,
+            }
+        }
+    });
+}
+
+/// Report the release of the setup barrier, with the distribution of agent setup durations.
+fn report_barrier_release(reporter: &Reporter, release: &BarrierRelease) {
+    let mut setup_durations = release.setup_durations.clone();
+    setup_durations.sort();
+    let min = setup_durations.first().copied().unwrap_or_default();
+    let median = setup_durations
+        .get(setup_durations.len() / 2)
+        .copied()
+        .unwrap_or_default();
+    let max = setup_durations.last().copied().unwrap_or_default();
+
+    log::info!(
+        "Setup barrier released with {} agents ready and {} missed. Setup took min {min:?}, median {median:?}, max {max:?}",
+        setup_durations.len(),
+        release.missed
+    );
+    reporter.add_custom(
+        ReportMetric::new("setup_barrier")
+            .with_field("arrived", setup_durations.len() as u64)
+            .with_field("missed", release.missed as u64)
+            .with_field("min_setup_s", min.as_secs_f64())
+            .with_field("median_setup_s", median.as_secs_f64())
+            .with_field("max_setup_s", max.as_secs_f64()),
+    );
+}
+
+/// Report the start of each phase of the load profile, so that metrics can be grouped by phase.
+fn start_load_profile_phase_reporting<RV: UserValuesConstraint>(
+    runner_context: &RunnerContext<RV>,
+    load_profile: &LoadProfile,
+    started: Instant,
+    mut shutdown_listener: DelegatedShutdownListener,
+) {
+    let reporter = runner_context.reporter();
+    let phases = load_profile
+        .stages()
+        .iter()
+        .copied()
+        .zip(load_profile.stage_starts())
+        .collect::<Vec<_>>();
+
+    runner_context.executor().spawn(async move {
+        for (phase, (stage, start)) in phases.into_iter().enumerate() {
+            tokio::select! {
+                _ = tokio::time::sleep_until((started + start).into()) => {}
+                _ = shutdown_listener.wait_for_shutdown() => break,
+            }
+
+            log::info!(
+                "Starting load profile phase {phase}, targeting {} agents",
+                stage.target_agents
+            );
+            reporter.add_custom(
+                ReportMetric::new("load_profile_phase")
+                    .with_tag("phase", phase as u64)
+                    .with_field("target_agents", stage.target_agents as u64)
+                    .with_field("ramp_s", stage.ramp.as_secs_f64())
+                    .with_field("hold_s", stage.hold.as_secs_f64()),
+            );
+        }
+    });
 }
@@ -0,0 +1,431 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::time::{Duration, Instant};
+use wind_tunnel_core::prelude::DelegatedShutdownListener;
+
+/// The target rate at which a behaviour is scheduled when running in open-model mode.
+///
+/// Rates are expressed in iterations per second.
+#[derive(Debug, Clone)]
+pub enum ArrivalRate {
+    /// Start iterations at evenly spaced intervals.
+    Fixed(f64),
+    /// Start iterations with exponentially distributed gaps, so that arrivals follow a Poisson
+    /// process with the given mean rate.
+    Poisson(f64),
+    /// Run at each rate for the paired duration, in order. No iterations are started during a step
+    /// with a rate of zero. The last rate is held once all the steps have elapsed, unless it is
+    /// zero, in which case the schedule ends and the agents stop running the behaviour.
+    Stepped(Vec<(Duration, f64)>),
+}
+
+impl ArrivalRate {
+    /// The rate in effect at `elapsed` time since the schedule started.
+    fn rate_at(&self, elapsed: Duration) -> f64 {
+        match self {
+            ArrivalRate::Fixed(rate) | ArrivalRate::Poisson(rate) => *rate,
+            ArrivalRate::Stepped(steps) => {
+                let mut step_end = Duration::ZERO;
+                for (hold, rate) in steps {
+                    step_end += *hold;
+                    if elapsed < step_end {
+                        return *rate;
+                    }
+                }
+
+                steps.last().map(|(_, rate)| *rate).unwrap_or(0.0)
+            }
+        }
+    }
+
+    /// The time since the schedule started at which the step after the one active at `elapsed`
+    /// begins, if there is one.
+    fn next_step_start(&self, elapsed: Duration) -> Option<Duration> {
+        match self {
+            ArrivalRate::Stepped(steps) => {
+                let mut step_end = Duration::ZERO;
+                for (hold, _) in steps {
+                    step_end += *hold;
+                    if elapsed < step_end {
+                        return Some(step_end);
+                    }
+                }
+
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        let valid = match self {
+            ArrivalRate::Fixed(rate) | ArrivalRate::Poisson(rate) => {
+                rate.is_finite() && *rate > 0.0
+            }
+            ArrivalRate::Stepped(steps) => {
+                steps.iter().any(|(_, rate)| *rate > 0.0)
+                    && steps
+                        .iter()
+                        .all(|(_, rate)| rate.is_finite() && *rate >= 0.0)
+            }
+        };
+
+        if !valid {
+            anyhow::bail!("Invalid arrival rate {self:?}, rates must be positive");
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether an [ArrivalRate] applies to each agent individually or is shared between all the agents
+/// that are assigned a behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateScope {
+    /// Every agent runs the behaviour at the target rate.
+    PerAgent,
+    /// The target rate is the total across all agents assigned the behaviour. Each iteration is
+    /// picked up by whichever agent is free first.
+    Global,
+}
+
+/// A slot claimed from an [ArrivalSchedule].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ScheduledSlot {
+    /// The time at which the iteration was intended to start.
+    pub(crate) intended_start: Instant,
+    /// The number of slots that were dropped before this one because they were too far behind
+    /// schedule.
+    pub(crate) missed: usize,
+}
+
+/// Produces the intended start times for iterations of a behaviour in open-model mode.
+pub(crate) struct ArrivalSchedule {
+    rate: ArrivalRate,
+    max_lag: Option<Duration>,
+    started: Option<Instant>,
+    next: Option<Instant>,
+    rng: StdRng,
+}
+
+impl ArrivalSchedule {
+    pub(crate) fn new(rate: ArrivalRate, max_lag: Option<Duration>) -> Self {
+        Self {
+            rate,
+            max_lag,
+            started: None,
+            next: None,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    /// Claim the next slot from the schedule, or [None] if the schedule has ended because its
+    /// final rate is zero.
+    ///
+    /// The schedule starts on the first claim. If a maximum lag is configured, then any slots that
+    /// are further behind `now` than that lag are skipped and counted as missed.
+    pub(crate) fn claim(&mut self, now: Instant) -> Option<ScheduledSlot> {
+        let started = match self.started {
+            Some(started) => started,
+            None => {
+                self.started = Some(now);
+                self.next = self.next_active_slot(now, now);
+                now
+            }
+        };
+        let mut slot = self.next?;
+        let mut missed = 0;
+
+        loop {
+            let following = self.advance(started, slot);
+            match (self.max_lag, following) {
+                (Some(max_lag), Some(following))
+                    if now.saturating_duration_since(slot) > max_lag =>
+                {
+                    missed += 1;
+                    slot = following;
+                }
+                _ => {
+                    self.next = following;
+                    return Some(ScheduledSlot {
+                        intended_start: slot,
+                        missed,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Compute the slot that follows `slot`, or [None] if there are no more slots.
+    fn advance(&mut self, started: Instant, slot: Instant) -> Option<Instant> {
+        let rate = self.rate.rate_at(slot.saturating_duration_since(started));
+        let gap_s = match self.rate {
+            ArrivalRate::Poisson(_) => {
+                let u: f64 = self.rng.random();
+                -(1.0 - u).ln() / rate
+            }
+            _ => 1.0 / rate,
+        };
+
+        self.next_active_slot(started, slot + Duration::from_secs_f64(gap_s))
+    }
+
+    /// The first time at or after `slot` at which the rate is positive, skipping any steps with a
+    /// rate of zero, or [None] if the rate stays at zero.
+    fn next_active_slot(&self, started: Instant, mut slot: Instant) -> Option<Instant> {
+        loop {
+            let elapsed = slot.saturating_duration_since(started);
+            if self.rate.rate_at(elapsed) > 0.0 {
+                return Some(slot);
+            }
+            slot = started + self.rate.next_step_start(elapsed)?;
+        }
+    }
+}
+
+/// Block the current thread until `deadline`, checking for shutdown while waiting.
+///
+/// Returns `false` if the runner is shutting down and the agent should stop.
+pub(crate) fn wait_until(
+    deadline: Instant,
+    shutdown_listener: &mut DelegatedShutdownListener,
+) -> bool {
+    loop {
+        if shutdown_listener.should_shutdown() {
+            return false;
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+
+        std::thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+}
+
+/// Wait until `deadline` from an agent task, or until the runner is shutting down.
+///
+/// Returns `false` if the runner is shutting down and the agent should stop.
+pub(crate) async fn wait_until_async(
+    deadline: Instant,
+    shutdown_listener: &mut DelegatedShutdownListener,
+) -> bool {
+    tokio::select! {
+        biased;
+        _ = shutdown_listener.wait_for_shutdown() => false,
+        _ = tokio::time::sleep_until(deadline.into()) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_rate_slots_are_evenly_spaced() {
+        let mut schedule = ArrivalSchedule::new(ArrivalRate::Fixed(10.0), None);
+        let now = Instant::now();
+
+        let first = schedule.claim(now).unwrap();
+        let second = schedule.claim(now).unwrap();
+        let third = schedule.claim(now).unwrap();
+
+        assert_eq!(now, first.intended_start);
+        assert_eq!(
+            Duration::from_millis(100),
+            second.intended_start - first.intended_start
+        );
+        assert_eq!(
+            Duration::from_millis(100),
+            third.intended_start - second.intended_start
+        );
+        assert_eq!(0, third.missed);
+    }
+
+    #[test]
+    fn fixed_rate_schedules_slots_for_duration() {
+        let mut schedule = ArrivalSchedule::new(ArrivalRate::Fixed(10.0), None);
+        let now = Instant::now();
+        let end = now + Duration::from_secs(2);
+
+        let mut slots = 0;
+        while schedule.claim(now).unwrap().intended_start < end {
+            slots += 1;
+        }
+
+        assert_eq!(20, slots);
+    }
+
+    #[test]
+    fn shared_schedule_slots_are_claimed_once() {
+        // Agents sharing a global schedule claim from the same schedule, so each slot is taken by
+        // only one of them, whichever agent claims it first.
+        let schedule = std::sync::Mutex::new(ArrivalSchedule::new(ArrivalRate::Fixed(10.0), None));
+        let now = Instant::now();
+
+        let slots = (0..4)
+            .map(|_| schedule.lock().unwrap().claim(now).unwrap().intended_start - now)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                Duration::from_millis(0),
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(300),
+            ],
+            slots
+        );
+    }
+
+    #[test]
+    fn stepped_rate_changes_between_steps() {
+        let mut schedule = ArrivalSchedule::new(
+            ArrivalRate::Stepped(vec![
+                (Duration::from_secs(1), 2.0),
+                (Duration::from_secs(1), 4.0),
+            ]),
+            None,
+        );
+        let now = Instant::now();
+
+        let slots = (0..6)
+            .map(|_| schedule.claim(now).unwrap().intended_start - now)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                Duration::from_millis(0),
+                Duration::from_millis(500),
+                Duration::from_millis(1000),
+                Duration::from_millis(1250),
+                Duration::from_millis(1500),
+                Duration::from_millis(1750),
+            ],
+            slots
+        );
+    }
+
+    #[test]
+    fn stepped_rate_skips_idle_steps() {
+        let mut schedule = ArrivalSchedule::new(
+            ArrivalRate::Stepped(vec![
+                (Duration::from_secs(2), 0.0),
+                (Duration::from_secs(1), 1.0),
+            ]),
+            None,
+        );
+        let now = Instant::now();
+
+        let slots = (0..2)
+            .map(|_| schedule.claim(now).unwrap().intended_start - now)
+            .collect::<Vec<_>>();
+
+        // Nothing runs at the start, during the idle first step
+        assert_eq!(vec![Duration::from_secs(2), Duration::from_secs(3)], slots);
+    }
+
+    #[test]
+    fn stepped_rate_skips_idle_middle_step() {
+        let mut schedule = ArrivalSchedule::new(
+            ArrivalRate::Stepped(vec![
+                (Duration::from_secs(1), 2.0),
+                (Duration::from_secs(2), 0.0),
+                (Duration::from_secs(1), 2.0),
+            ]),
+            None,
+        );
+        let now = Instant::now();
+
+        let slots = (0..4)
+            .map(|_| schedule.claim(now).unwrap().intended_start - now)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                Duration::from_millis(0),
+                Duration::from_millis(500),
+                Duration::from_millis(3000),
+                Duration::from_millis(3500),
+            ],
+            slots
+        );
+    }
+
+    #[test]
+    fn stepped_rate_ends_after_idle_last_step() {
+        let mut schedule = ArrivalSchedule::new(
+            ArrivalRate::Stepped(vec![
+                (Duration::from_secs(1), 2.0),
+                (Duration::from_secs(1), 0.0),
+            ]),
+            None,
+        );
+        let now = Instant::now();
+
+        assert_eq!(
+            Duration::ZERO,
+            schedule.claim(now).unwrap().intended_start - now
+        );
+        assert_eq!(
+            Duration::from_millis(500),
+            schedule.claim(now).unwrap().intended_start - now
+        );
+        assert_eq!(None, schedule.claim(now));
+        assert_eq!(None, schedule.claim(now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn slots_behind_max_lag_are_missed() {
+        let mut schedule =
+            ArrivalSchedule::new(ArrivalRate::Fixed(10.0), Some(Duration::from_millis(150)));
+        let started = Instant::now();
+        schedule.claim(started);
+
+        // One second later, the slots at 100ms through 800ms are too far behind.
+        let slot = schedule.claim(started + Duration::from_secs(1)).unwrap();
+
+        assert_eq!(8, slot.missed);
+        assert_eq!(Duration::from_millis(900), slot.intended_start - started);
+    }
+
+    #[test]
+    fn poisson_rate_averages_to_target() {
+        let mut schedule = ArrivalSchedule::new(ArrivalRate::Poisson(100.0), None);
+        let now = Instant::now();
+
+        let first = schedule.claim(now).unwrap().intended_start;
+        let mut last = first;
+        for _ in 0..10_000 {
+            last = schedule.claim(now).unwrap().intended_start;
+        }
+
+        let mean_gap_s = (last - first).as_secs_f64() / 10_000.0;
+        assert!(
+            (0.009..0.011).contains(&mean_gap_s),
+            "mean gap was {mean_gap_s}"
+        );
+    }
+
+    #[test]
+    fn invalid_rates_are_rejected() {
+        assert!(ArrivalRate::Fixed(0.0).validate().is_err());
+        assert!(ArrivalRate::Poisson(-1.0).validate().is_err());
+        assert!(ArrivalRate::Stepped(vec![]).validate().is_err());
+        assert!(
+            ArrivalRate::Stepped(vec![(Duration::from_secs(1), 0.0)])
+                .validate()
+                .is_err()
+        );
+        assert!(
+            ArrivalRate::Stepped(vec![
+                (Duration::from_secs(1), 0.0),
+                (Duration::from_secs(1), 5.0)
+            ])
+            .validate()
+            .is_ok()
+        );
+    }
+}
@@ -1,8 +1,29 @@
+use std::sync::{Arc, OnceLock};
 use tokio::signal;
 use wind_tunnel_core::prelude::ShutdownHandle;
+use wind_tunnel_summary_model::ShutdownReason;
+
+/// Why the run was shut down, shared between everything that can shut it down.
+///
+/// Only the first reason is kept, since later shutdowns are a consequence of the first.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ShutdownReasonCell(Arc<OnceLock<ShutdownReason>>);
+
+impl ShutdownReasonCell {
+    /// Record the reason and shut down the run.
+    pub(crate) fn shutdown(&self, shutdown_handle: &ShutdownHandle, reason: ShutdownReason) {
+        let _ = self.0.set(reason);
+        shutdown_handle.shutdown();
+    }
+
+    pub(crate) fn get(&self) -> Option<ShutdownReason> {
+        self.0.get().copied()
+    }
+}
 
 pub(crate) fn start_shutdown_listener(
     runtime: &tokio::runtime::Runtime,
+    shutdown_reason: ShutdownReasonCell,
 ) -> anyhow::Result<ShutdownHandle> {
     let handle = ShutdownHandle::default();
 
@@ -11,7 +32,7 @@ pub(crate) fn start_shutdown_listener(
         signal::ctrl_c()
             .await
             .expect("Failed to receive Ctrl-C signal");
-        listener_handle.shutdown();
+        shutdown_reason.shutdown(&listener_handle, ShutdownReason::Interrupted);
         println!("Received shutdown signal, shutting down...");
     });
 
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use wind_tunnel_instruments::prelude::OperationStats;
+use wind_tunnel_summary_model::{ThresholdCheck, Verdict};
+
+/// A pass/fail criterion for a scenario run, checked at the end of the run.
+///
+/// Operation thresholds are checked against the operations reported during the run, excluding the
+/// warmup period. If there are no operations to check a threshold against, then it fails.
+///
+/// For example, to require that the 95th percentile of `app_install` calls completes within 2s,
+/// that fewer than 1% of them fail and that at least 10 agents complete:
+/// ```rust
+/// use std::time::Duration;
+/// use wind_tunnel_runner::prelude::Threshold;
+///
+/// let thresholds = [
+///     Threshold::OperationLatency {
+///         operation_id: "app_install".to_string(),
+///         percentile: 95.0,
+///         max: Duration::from_secs(2),
+///     },
+///     Threshold::OperationErrorRate {
+///         operation_id: "app_install".to_string(),
+///         max_rate: 0.01,
+///     },
+///     Threshold::AgentsCompleted { min: 10 },
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Threshold {
+    /// The `percentile` of the latency of successful operations with `operation_id` must be at
+    /// most `max`.
+    OperationLatency {
+        operation_id: String,
+        percentile: f64,
+        max: Duration,
+    },
+    /// The fraction of operations with `operation_id` that fail must be at most `max_rate`.
+    OperationErrorRate { operation_id: String, max_rate: f64 },
+    /// The fraction of behaviour iterations that fail, across all agents, must be at most
+    /// `max_rate`.
+    BehaviourErrorRate { max_rate: f64 },
+    /// At least `min` agents must run their behaviour to completion.
+    AgentsCompleted { min: usize },
+}
+
+impl Display for Threshold {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Threshold::OperationLatency {
+                operation_id,
+                percentile,
+                max,
+            } => write!(
+                f,
+                "p{percentile} latency of {operation_id} <= {}ms",
+                max.as_secs_f64() * 1000.0
+            ),
+            Threshold::OperationErrorRate {
+                operation_id,
+                max_rate,
+            } => write!(f, "error rate of {operation_id} <= {max_rate}"),
+            Threshold::BehaviourErrorRate { max_rate } => {
+                write!(f, "behaviour error rate <= {max_rate}")
+            }
+            Threshold::AgentsCompleted { min } => write!(f, "agents completed >= {min}"),
+        }
+    }
+}
+
+/// What happened during a run, to check the thresholds against.
+pub(crate) struct RunOutcome {
+    pub(crate) operation_stats: HashMap<String, OperationStats>,
+    pub(crate) behaviour_iterations: u64,
+    pub(crate) behaviour_errors: u64,
+    pub(crate) agents_completed: usize,
+}
+
+impl Threshold {
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Threshold::OperationLatency { percentile, .. }
+                if !(*percentile > 0.0 && *percentile <= 100.0) =>
+            {
+                anyhow::bail!("Invalid threshold [{self}], the percentile must be in (0, 100]")
+            }
+            Threshold::OperationErrorRate { max_rate, .. }
+            | Threshold::BehaviourErrorRate { max_rate }
+                if !(0.0..=1.0).contains(max_rate) =>
+            {
+                anyhow::bail!("Invalid threshold [{self}], the error rate must be in [0, 1]")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether operation stats need to be kept during the run to check this threshold.
+    pub(crate) fn needs_operation_stats(&self) -> bool {
+        matches!(
+            self,
+            Threshold::OperationLatency { .. } | Threshold::OperationErrorRate { .. }
+        )
+    }
+
+    fn check(&self, outcome: &RunOutcome) -> ThresholdCheck {
+        let (observed, passed) = match self {
+            Threshold::OperationLatency {
+                operation_id,
+                percentile,
+                max,
+            } => {
+                let latency = outcome
+                    .operation_stats
+                    .get(operation_id)
+                    .and_then(|stats| stats.percentile(*percentile));
+                (
+                    latency.map(|latency| latency.as_secs_f64() * 1000.0),
+                    latency.is_some_and(|latency| latency <= *max),
+                )
+            }
+            Threshold::OperationErrorRate {
+                operation_id,
+                max_rate,
+            } => {
+                let error_rate = outcome
+                    .operation_stats
+                    .get(operation_id)
+                    .filter(|stats| stats.count() > 0)
+                    .map(OperationStats::error_rate);
+                (error_rate, error_rate.is_some_and(|rate| rate <= *max_rate))
+            }
+            Threshold::BehaviourErrorRate { max_rate } => {
+                let error_rate = match outcome.behaviour_iterations {
+                    0 => 0.0,
+                    iterations => outcome.behaviour_errors as f64 / iterations as f64,
+                };
+                (Some(error_rate), error_rate <= *max_rate)
+            }
+            Threshold::AgentsCompleted { min } => (
+                Some(outcome.agents_completed as f64),
+                outcome.agents_completed >= *min,
+            ),
+        };
+
+        ThresholdCheck {
+            threshold: self.to_string(),
+            observed,
+            passed,
+        }
+    }
+}
+
+/// Check every threshold against the outcome of the run.
+pub(crate) fn check_thresholds(thresholds: &[Threshold], outcome: &RunOutcome) -> Verdict {
+    let checks = thresholds
+        .iter()
+        .map(|threshold| threshold.check(outcome))
+        .collect::<Vec<_>>();
+
+    Verdict {
+        passed: checks.iter().all(|check| check.passed),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome() -> RunOutcome {
+        RunOutcome {
+            operation_stats: HashMap::new(),
+            behaviour_iterations: 100,
+            behaviour_errors: 5,
+            agents_completed: 3,
+        }
+    }
+
+    #[test]
+    fn thresholds_are_checked_against_the_outcome() {
+        let verdict = check_thresholds(
+            &[
+                Threshold::BehaviourErrorRate { max_rate: 0.1 },
+                Threshold::AgentsCompleted { min: 3 },
+            ],
+            &outcome(),
+        );
+
+        assert!(verdict.passed);
+        assert_eq!(
+            vec![
+                ThresholdCheck {
+                    threshold: "behaviour error rate <= 0.1".to_string(),
+                    observed: Some(0.05),
+                    passed: true,
+                },
+                ThresholdCheck {
+                    threshold: "agents completed >= 3".to_string(),
+                    observed: Some(3.0),
+                    passed: true,
+                },
+            ],
+            verdict.checks
+        );
+    }
+
+    #[test]
+    fn any_failed_threshold_fails_the_verdict() {
+        let verdict = check_thresholds(
+            &[
+                Threshold::BehaviourErrorRate { max_rate: 0.1 },
+                Threshold::AgentsCompleted { min: 4 },
+            ],
+            &outcome(),
+        );
+
+        assert!(!verdict.passed);
+        assert!(verdict.checks[0].passed);
+        assert!(!verdict.checks[1].passed);
+    }
+
+    #[test]
+    fn operation_threshold_without_data_fails() {
+        let verdict = check_thresholds(
+            &[Threshold::OperationLatency {
+                operation_id: "app_install".to_string(),
+                percentile: 95.0,
+                max: Duration::from_secs(1),
+            }],
+            &outcome(),
+        );
+
+        assert!(!verdict.passed);
+        assert_eq!(None, verdict.checks[0].observed);
+    }
+
+    #[test]
+    fn invalid_thresholds_are_rejected() {
+        assert!(
+            Threshold::OperationLatency {
+                operation_id: "app_install".to_string(),
+                percentile: 0.0,
+                max: Duration::from_secs(1),
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            Threshold::BehaviourErrorRate { max_rate: 2.0 }
+                .validate()
+                .is_err()
+        );
+        assert!(Threshold::AgentsCompleted { min: 1 }.validate().is_ok());
+    }
+}
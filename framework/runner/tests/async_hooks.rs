@@ -0,0 +1,125 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use wind_tunnel_core::prelude::AgentBailError;
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, ScenarioDefinitionBuilder, UserValuesConstraint, run,
+};
+
+mod common;
+use common::sample_cli_cfg_with_agents;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {
+    iterations: usize,
+}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+#[test]
+fn async_agents_run_as_tasks() {
+    let setups = Arc::new(AtomicUsize::new(0));
+    let agents_with_iterations = Arc::new(AtomicUsize::new(0));
+    let delay = Duration::from_millis(20);
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "async_agents_run_as_tasks",
+        sample_cli_cfg_with_agents(500),
+    )
+    .with_default_duration_s(1)
+    .use_async_agent_setup({
+        let setups = setups.clone();
+        move |_ctx| {
+            let setups = setups.clone();
+            Box::pin(async move {
+                setups.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    })
+    .use_async_agent_behaviour(move |ctx| {
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            ctx.get_mut().iterations += 1;
+            Ok(())
+        })
+    })
+    .use_agent_teardown({
+        let agents_with_iterations = agents_with_iterations.clone();
+        move |ctx| {
+            if ctx.get().iterations > 0 {
+                agents_with_iterations.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(())
+        }
+    });
+
+    let result = run(scenario);
+
+    assert_eq!(500, result.unwrap());
+    assert_eq!(500, setups.load(Ordering::SeqCst));
+    assert_eq!(500, agents_with_iterations.load(Ordering::SeqCst));
+}
+
+#[test]
+fn long_running_async_behaviour_is_cancelled_on_shutdown() {
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "long_running_async_behaviour_is_cancelled_on_shutdown",
+        sample_cli_cfg_with_agents(2),
+    )
+    .with_default_duration_s(1)
+    .use_async_agent_behaviour(|_ctx| {
+        Box::pin(async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        })
+    });
+
+    let started = std::time::Instant::now();
+    let result = run(scenario);
+
+    assert_eq!(2, result.unwrap());
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn closures_capturing_configuration_are_accepted_as_hooks() {
+    let iterations = Arc::new(AtomicUsize::new(0));
+    let fail_after = 3;
+
+    fn agent_teardown(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "closures_capturing_configuration_are_accepted_as_hooks",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(1)
+    .use_agent_behaviour({
+        let iterations = iterations.clone();
+        move |ctx| {
+            iterations.fetch_add(1, Ordering::SeqCst);
+            ctx.get_mut().iterations += 1;
+            if ctx.get().iterations >= fail_after {
+                Err(AgentBailError::default().into())
+            } else {
+                Ok(())
+            }
+        }
+    })
+    .use_async_agent_teardown(|ctx| Box::pin(async move { agent_teardown(ctx) }));
+
+    let result = run(scenario);
+
+    // The agent bailed after the configured number of iterations.
+    assert_eq!(0, result.unwrap());
+    assert_eq!(3, iterations.load(Ordering::SeqCst));
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, OperationRecord, ScenarioDefinitionBuilder, Threshold,
+    UserValuesConstraint, report_operation, run,
+};
+
+mod common;
+use common::sample_cli_cfg_with_agents;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+fn call(ctx: &AgentContext<RunnerContextValue, AgentContextValue>, operation_id: &str) {
+    let operation_record = OperationRecord::new(operation_id.to_string());
+    std::thread::sleep(Duration::from_millis(20));
+    report_operation(
+        ctx.runner_context().reporter(),
+        operation_record,
+        &Ok::<(), ()>(()),
+    );
+}
+
+#[test]
+fn behaviour_iterations_and_transactions_are_reported() {
+    fn agent_behaviour(
+        ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        let transaction = ctx.start_transaction("journey");
+        call(ctx, "first_call");
+        call(ctx, "second_call");
+        transaction.finish(&Ok::<(), ()>(()));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "behaviour_iterations_and_transactions_are_reported",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(1)
+    .add_threshold(Threshold::OperationErrorRate {
+        operation_id: "behaviour.default".to_string(),
+        max_rate: 0.0,
+    })
+    .add_threshold(Threshold::OperationErrorRate {
+        operation_id: "transaction.journey".to_string(),
+        max_rate: 0.0,
+    })
+    .add_threshold(Threshold::OperationLatency {
+        operation_id: "transaction.journey".to_string(),
+        percentile: 50.0,
+        max: Duration::from_secs(5),
+    })
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert_eq!(1, result.unwrap());
+}
+
+#[test]
+fn failed_behaviour_iterations_are_reported_as_errors() {
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        std::thread::sleep(Duration::from_millis(20));
+        anyhow::bail!("Behaviour failed")
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "failed_behaviour_iterations_are_reported_as_errors",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(1)
+    .add_threshold(Threshold::OperationErrorRate {
+        operation_id: "behaviour.default".to_string(),
+        max_rate: 0.0,
+    })
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,65 @@
+//! Helpers shared by the runner integration tests.
+
+// Each test binary uses a different subset of these helpers.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use tempfile::TempDir;
+use wind_tunnel_runner::prelude::{ReporterOpt, WindTunnelScenarioCli};
+use wind_tunnel_summary_model::{RunSummary, load_summary_runs};
+
+/// The CLI config for a test scenario, which runs without a progress bar and with the noop
+/// reporter. The number of agents is left to the scenario's default.
+pub fn sample_cli_cfg() -> WindTunnelScenarioCli {
+    WindTunnelScenarioCli {
+        connection_string: None,
+        agents: None,
+        behaviour: vec![],
+        duration: None,
+        warmup: None,
+        load_stage: vec![],
+        params: None,
+        param: vec![],
+        soak: false,
+        no_progress: true,
+        reporter: vec![ReporterOpt::Noop],
+        reporter_config: None,
+        metrics_dir: None,
+        time_server: None,
+        run_summary_path: None,
+        run_id: None,
+    }
+}
+
+/// The CLI config from [sample_cli_cfg], with a fixed number of agents.
+pub fn sample_cli_cfg_with_agents(agents: usize) -> WindTunnelScenarioCli {
+    WindTunnelScenarioCli {
+        agents: Some(agents),
+        ..sample_cli_cfg()
+    }
+}
+
+/// A run summary file in a temporary directory, so that a test can read back the summary of its
+/// own run without seeing those written by other tests.
+pub struct TempRunSummary {
+    dir: TempDir,
+}
+
+impl TempRunSummary {
+    pub fn new() -> Self {
+        Self {
+            dir: tempfile::tempdir().expect("failed to create temp dir"),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.path().join("run_summary.jsonl")
+    }
+
+    /// The summary of the single run written to this file.
+    pub fn load(&self) -> RunSummary {
+        let mut runs = load_summary_runs(self.path()).expect("failed to load run summaries");
+        assert_eq!(1, runs.len(), "expected the summary of exactly one run");
+        runs.remove(0)
+    }
+}
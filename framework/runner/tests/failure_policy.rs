@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use wind_tunnel_runner::prelude::{
+    AgentContext, FailurePolicy, HookResult, ScenarioDefinitionBuilder, UserValuesConstraint, run,
+};
+
+mod common;
+use common::sample_cli_cfg_with_agents;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+#[test]
+fn agent_bails_after_max_consecutive_errors() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        anyhow::bail!("Error in agent behaviour")
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "agent_bails_after_max_consecutive_errors",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(1)
+    .with_failure_policy(FailurePolicy::new().with_max_consecutive_errors(3))
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    // The agent bailed so did not run to completion, but the scenario was not aborted.
+    assert_eq!(0, result.unwrap());
+    assert_eq!(3, CALLS.load(Ordering::SeqCst));
+}
+
+#[test]
+fn scenario_is_aborted_above_max_error_rate() {
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        std::thread::sleep(Duration::from_millis(1));
+        // Fail 9 out of every 10 iterations.
+        if CALLS.fetch_add(1, Ordering::SeqCst).is_multiple_of(10) {
+            Ok(())
+        } else {
+            anyhow::bail!("Error in agent behaviour")
+        }
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "scenario_is_aborted_above_max_error_rate",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(60)
+    .with_failure_policy(FailurePolicy::new().with_max_error_rate(0.5, 20))
+    .use_agent_behaviour(agent_behaviour);
+
+    let started = Instant::now();
+    let result = run(scenario);
+
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn failing_iterations_back_off() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        anyhow::bail!("Error in agent behaviour")
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "failing_iterations_back_off",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(1)
+    .with_failure_policy(
+        FailurePolicy::new().with_backoff(Duration::from_millis(100), Duration::from_millis(400)),
+    )
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert_eq!(1, result.unwrap());
+    // Waits of 100ms, 200ms and 400ms leave time for at most 4 iterations within the duration.
+    let calls = CALLS.load(Ordering::SeqCst);
+    assert!(
+        (2..=4).contains(&calls),
+        "Expected 2 to 4 calls, got {calls}"
+    );
+}
+
+#[test]
+fn invalid_failure_policy_is_rejected() {
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "invalid_failure_policy_is_rejected",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_failure_policy(FailurePolicy::new().with_max_error_rate(1.5, 10))
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
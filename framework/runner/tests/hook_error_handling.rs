@@ -1,10 +1,13 @@
 use std::sync::Arc;
 use wind_tunnel_core::prelude::AgentBailError;
 use wind_tunnel_runner::prelude::{
-    AgentContext, HookResult, ReporterOpt, RunnerContext, ScenarioDefinitionBuilder,
-    UserValuesConstraint, WindTunnelScenarioCli, run,
+    AgentContext, HookResult, RunnerContext, ScenarioDefinitionBuilder, UserValuesConstraint,
+    WindTunnelScenarioCli, run,
 };
 
+mod common;
+use common::TempRunSummary;
+
 #[derive(Default, Debug)]
 struct RunnerContextValue {}
 
@@ -20,13 +23,7 @@ impl UserValuesConstraint for AgentContextValue {}
 fn sample_cli_cfg() -> WindTunnelScenarioCli {
     WindTunnelScenarioCli {
         connection_string: Some("test_connection_string".to_string()),
-        agents: None,
-        behaviour: vec![],
-        duration: None,
-        soak: false,
-        no_progress: true,
-        reporter: ReporterOpt::Noop,
-        run_id: None,
+        ..common::sample_cli_cfg()
     }
 }
 
@@ -125,6 +122,46 @@ fn bail_error_stops_agent_behaviour() {
     assert_eq!(1, result.unwrap());
 }
 
+#[test]
+fn bail_handle_stops_agent_behaviour() {
+    fn agent_setup(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+        let bail_handle = ctx.bail_handle();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            bail_handle.bail("Dependency stopped");
+        });
+        Ok(())
+    }
+
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        Ok(())
+    }
+
+    let run_summary = TempRunSummary::new();
+    let mut cfg = sample_cli_cfg();
+    cfg.agents = Some(2);
+    cfg.run_summary_path = Some(run_summary.path());
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "bail_handle_stops_agent_behaviour",
+        cfg,
+    )
+    .with_default_duration_s(1)
+    .use_agent_setup(agent_setup)
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_ok());
+    // Neither agent ran its behaviour to completion
+    assert_eq!(0, result.unwrap());
+
+    let summary = run_summary.load();
+    assert_eq!(Some(&2), summary.behaviour_errors.get("bail"));
+}
+
 #[test]
 fn capture_error_in_agent_teardown() {
     fn agent_teardown(
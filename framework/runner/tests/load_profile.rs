@@ -0,0 +1,110 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, LoadProfile, ScenarioDefinitionBuilder, UserValuesConstraint, run,
+};
+
+mod common;
+use common::sample_cli_cfg;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+#[test]
+fn agents_are_activated_and_drained_in_stages() {
+    static SETUPS: Mutex<Vec<(usize, Instant)>> = Mutex::new(Vec::new());
+    static TEARDOWNS: Mutex<Vec<(usize, Instant)>> = Mutex::new(Vec::new());
+
+    fn agent_setup(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+        SETUPS
+            .lock()
+            .unwrap()
+            .push((ctx.agent_index(), Instant::now()));
+        Ok(())
+    }
+
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        std::thread::sleep(Duration::from_millis(10));
+        Ok(())
+    }
+
+    fn agent_teardown(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+        TEARDOWNS
+            .lock()
+            .unwrap()
+            .push((ctx.agent_index(), Instant::now()));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "agents_are_activated_and_drained_in_stages",
+        sample_cli_cfg(),
+    )
+    .use_agent_setup(agent_setup)
+    .use_agent_behaviour(agent_behaviour)
+    .use_agent_teardown(agent_teardown)
+    .with_load_profile(
+        LoadProfile::new()
+            .step_to(1, Duration::from_secs(1))
+            .step_to(2, Duration::from_secs(1))
+            .step_to(1, Duration::from_secs(1)),
+    );
+
+    let result = run(scenario);
+
+    // Both agents were drained or ran until the end of the profile.
+    assert_eq!(2, result.unwrap());
+
+    let setups = SETUPS.lock().unwrap().clone();
+    let teardowns = TEARDOWNS.lock().unwrap().clone();
+    let time_of = |events: &[(usize, Instant)], agent_index: usize| {
+        events
+            .iter()
+            .find(|(index, _)| *index == agent_index)
+            .map(|(_, at)| *at)
+            .unwrap()
+    };
+
+    let second_agent_delay = time_of(&setups, 1) - time_of(&setups, 0);
+    assert!(
+        second_agent_delay >= Duration::from_millis(900),
+        "second agent started after {second_agent_delay:?}"
+    );
+
+    let second_agent_drained = time_of(&teardowns, 0) - time_of(&teardowns, 1);
+    assert!(
+        second_agent_drained >= Duration::from_millis(900),
+        "second agent drained {second_agent_drained:?} before the end"
+    );
+}
+
+#[test]
+fn load_profile_larger_than_agent_count_is_rejected() {
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        Ok(())
+    }
+
+    let mut cfg = sample_cli_cfg();
+    cfg.agents = Some(2);
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "load_profile_larger_than_agent_count_is_rejected",
+        cfg,
+    )
+    .use_agent_behaviour(agent_behaviour)
+    .with_load_profile(LoadProfile::new().step_to(5, Duration::from_secs(1)));
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
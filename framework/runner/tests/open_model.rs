@@ -0,0 +1,136 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use wind_tunnel_runner::prelude::{
+    AgentContext, ArrivalRate, HookResult, RateScope, ScenarioDefinitionBuilder,
+    UserValuesConstraint, run,
+};
+
+mod common;
+use common::sample_cli_cfg_with_agents;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+/// Check that the iterations started at `started`, which all came from one arrival schedule, are
+/// within 25% of the count that the schedule gives for the time they span at `rate` per second.
+///
+/// The span is checked too, so that a schedule that runs too few iterations cannot pass by ending
+/// early.
+fn assert_follows_schedule(started: &[Instant], rate: f64, min_span: Duration) {
+    let span = *started.iter().max().unwrap() - *started.iter().min().unwrap();
+    assert!(span >= min_span, "iterations only spanned {span:?}");
+
+    let expected = span.as_secs_f64() * rate + 1.0;
+    let iterations = started.len() as f64;
+    assert!(
+        (iterations - expected).abs() <= expected * 0.25,
+        "ran {iterations} iterations in {span:?}, expected {expected:.0}"
+    );
+}
+
+#[test]
+fn per_agent_rate_limits_iterations() {
+    static STARTED: Mutex<Vec<(usize, Instant)>> = Mutex::new(Vec::new());
+
+    fn agent_behaviour(
+        ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        STARTED
+            .lock()
+            .unwrap()
+            .push((ctx.agent_index(), Instant::now()));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "per_agent_rate_limits_iterations",
+        sample_cli_cfg_with_agents(2),
+    )
+    .with_default_duration_s(2)
+    .use_agent_behaviour(agent_behaviour)
+    .use_arrival_rate("default", ArrivalRate::Fixed(10.0), RateScope::PerAgent);
+
+    let result = run(scenario);
+
+    assert!(result.is_ok());
+    // Each agent follows its own schedule of 10 iterations per second.
+    let started = STARTED.lock().unwrap();
+    for agent_index in 0..2 {
+        let agent_started = started
+            .iter()
+            .filter(|(index, _)| *index == agent_index)
+            .map(|(_, started)| *started)
+            .collect::<Vec<_>>();
+        assert_follows_schedule(&agent_started, 10.0, Duration::from_secs(1));
+    }
+}
+
+#[test]
+fn global_rate_is_shared_between_agents() {
+    static STARTED: Mutex<Vec<(usize, Instant)>> = Mutex::new(Vec::new());
+
+    fn agent_behaviour(
+        ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        STARTED
+            .lock()
+            .unwrap()
+            .push((ctx.agent_index(), Instant::now()));
+        // Slower than the arrival interval, so a single agent could not keep up alone.
+        std::thread::sleep(Duration::from_millis(150));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "global_rate_is_shared_between_agents",
+        sample_cli_cfg_with_agents(4),
+    )
+    .with_default_duration_s(2)
+    .use_agent_behaviour(agent_behaviour)
+    .use_arrival_rate("default", ArrivalRate::Fixed(10.0), RateScope::Global);
+
+    let result = run(scenario);
+
+    assert!(result.is_ok());
+    // The agents share one schedule of 10 iterations per second, where a rate per agent would run
+    // four times as many.
+    let started = STARTED.lock().unwrap();
+    let all_started = started
+        .iter()
+        .map(|(_, started)| *started)
+        .collect::<Vec<_>>();
+    assert_follows_schedule(&all_started, 10.0, Duration::from_secs(1));
+    // More than one agent picked up iterations.
+    assert!(
+        started.iter().any(|(index, _)| *index != started[0].0),
+        "only one agent ran iterations"
+    );
+}
+
+#[test]
+fn arrival_rate_for_unknown_behaviour_is_rejected() {
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "arrival_rate_for_unknown_behaviour_is_rejected",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(1)
+    .use_agent_behaviour(agent_behaviour)
+    .use_arrival_rate("missing", ArrivalRate::Fixed(10.0), RateScope::PerAgent);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
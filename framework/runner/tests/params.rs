@@ -0,0 +1,76 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, RunnerContext, ScenarioDefinitionBuilder, UserValuesConstraint, run,
+};
+
+mod common;
+use common::sample_cli_cfg_with_agents;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+fn agent_behaviour(_ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+    Ok(())
+}
+
+#[test]
+fn params_are_available_to_hooks() {
+    static SEND_INTERVAL_MS: AtomicU64 = AtomicU64::new(0);
+    static PAYLOAD_SIZE: AtomicU64 = AtomicU64::new(0);
+
+    fn setup(ctx: &mut RunnerContext<RunnerContextValue>) -> HookResult {
+        SEND_INTERVAL_MS.store(ctx.params().get("send_interval_ms")?, Ordering::SeqCst);
+        PAYLOAD_SIZE.store(ctx.params().get("payload_size")?, Ordering::SeqCst);
+        Ok(())
+    }
+
+    let mut params_file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+    writeln!(params_file, "send_interval_ms = 100").unwrap();
+    writeln!(params_file, "payload_size = 1024").unwrap();
+
+    let mut cli = sample_cli_cfg_with_agents(1);
+    cli.params = Some(params_file.path().to_path_buf());
+    cli.param = vec![("payload_size".to_string(), "2048".to_string())];
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "params_are_available_to_hooks",
+        cli,
+    )
+    .with_default_duration_s(1)
+    .add_param::<u64>("send_interval_ms", None)
+    .add_param("payload_size", Some(512u64))
+    .use_setup(setup)
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert_eq!(1, result.unwrap());
+    assert_eq!(100, SEND_INTERVAL_MS.load(Ordering::SeqCst));
+    assert_eq!(2048, PAYLOAD_SIZE.load(Ordering::SeqCst));
+}
+
+#[test]
+fn unknown_param_fails_the_scenario() {
+    let mut cli = sample_cli_cfg_with_agents(1);
+    cli.param = vec![("send_interval".to_string(), "100".to_string())];
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "unknown_param_fails_the_scenario",
+        cli,
+    )
+    .with_default_duration_s(1)
+    .add_param("send_interval_ms", Some(100u64))
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
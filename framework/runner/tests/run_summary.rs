@@ -0,0 +1,95 @@
+use std::time::Duration;
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, ScenarioDefinitionBuilder, UserValuesConstraint, run,
+};
+use wind_tunnel_summary_model::{SetupOutcome, ShutdownReason};
+
+mod common;
+use common::{TempRunSummary, sample_cli_cfg_with_agents};
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+#[test]
+fn run_summary_records_how_the_run_ended() {
+    fn agent_setup(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+        if ctx.agent_index() == 1 {
+            anyhow::bail!("No app available");
+        }
+        ctx.runner_context()
+            .record_config_overlay("service_config", serde_json::json!({"batch_size": 10}));
+        Ok(())
+    }
+
+    fn agent_behaviour(
+        ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        ctx.counter("iterations").inc();
+        std::thread::sleep(Duration::from_millis(50));
+        Ok(())
+    }
+
+    let run_summary = TempRunSummary::new();
+    let mut cfg = sample_cli_cfg_with_agents(2);
+    cfg.run_summary_path = Some(run_summary.path());
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "run_summary_records_how_the_run_ended",
+        cfg,
+    )
+    .with_default_duration_s(1)
+    .use_agent_setup(agent_setup)
+    .use_agent_behaviour(agent_behaviour);
+
+    run(scenario).unwrap();
+
+    let summary = run_summary.load();
+    assert_eq!(
+        "run_summary_records_how_the_run_ended",
+        summary.scenario_name
+    );
+    assert_eq!(
+        Some(ShutdownReason::DurationElapsed),
+        summary.shutdown_reason
+    );
+    let ended_at = summary.ended_at.expect("ended_at should be set");
+    assert!(ended_at > summary.started_at);
+
+    assert_eq!(
+        Some(&serde_json::json!({"batch_size": 10})),
+        summary.config_overlays.get("service_config")
+    );
+
+    let iterations = summary
+        .counters
+        .iter()
+        .find(|counter| counter.name == "iterations")
+        .expect("iterations counter should be in the summary");
+    assert_eq!(
+        Some("agent-0"),
+        iterations.tags.get("agent").map(String::as_str)
+    );
+
+    assert_eq!(2, summary.agent_outcomes.len());
+    let succeeded = &summary.agent_outcomes[0];
+    assert_eq!("agent-0", succeeded.agent_name);
+    assert_eq!(SetupOutcome::Succeeded, succeeded.setup);
+    assert_eq!(None, succeeded.bail_reason);
+    assert!(succeeded.iterations > 0);
+
+    let failed = &summary.agent_outcomes[1];
+    assert_eq!("agent-1", failed.agent_name);
+    assert!(
+        matches!(&failed.setup, SetupOutcome::Failed(e) if e.contains("No app available")),
+        "{:?}",
+        failed.setup
+    );
+    assert_eq!(0, failed.iterations);
+    assert_eq!(succeeded.iterations, iterations.total);
+}
@@ -0,0 +1,175 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, LoadProfile, ScenarioDefinitionBuilder, UserValuesConstraint, run,
+};
+use wind_tunnel_summary_model::SetupOutcome;
+
+mod common;
+use common::{TempRunSummary, sample_cli_cfg_with_agents};
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+#[test]
+fn behaviours_start_after_all_setups_complete() {
+    static SETUPS_DONE: Mutex<Vec<Instant>> = Mutex::new(Vec::new());
+    static FIRST_ITERATIONS: Mutex<Vec<Instant>> = Mutex::new(Vec::new());
+
+    fn agent_setup(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+        // Later agents take longer to set up.
+        std::thread::sleep(Duration::from_millis(200 * ctx.agent_index() as u64));
+        SETUPS_DONE.lock().unwrap().push(Instant::now());
+        Ok(())
+    }
+
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        thread_local! {
+            static STARTED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+        }
+        if !STARTED.replace(true) {
+            FIRST_ITERATIONS.lock().unwrap().push(Instant::now());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "behaviours_start_after_all_setups_complete",
+        sample_cli_cfg_with_agents(3),
+    )
+    .with_default_duration_s(1)
+    .with_setup_barrier(Duration::from_secs(10))
+    .use_agent_setup(agent_setup)
+    .use_agent_behaviour(agent_behaviour);
+
+    let started = Instant::now();
+    let result = run(scenario);
+
+    assert_eq!(3, result.unwrap());
+
+    let last_setup = *SETUPS_DONE.lock().unwrap().iter().max().unwrap();
+    let first_iterations = FIRST_ITERATIONS.lock().unwrap().clone();
+    assert_eq!(3, first_iterations.len());
+    assert!(first_iterations.iter().all(|i| *i >= last_setup));
+
+    // The duration is timed from the release of the barrier, not the start of the run.
+    assert!(started.elapsed() >= Duration::from_millis(1400));
+}
+
+#[test]
+fn barrier_is_released_after_timeout() {
+    static SLOW_AGENT_STARTED: Mutex<Option<Instant>> = Mutex::new(None);
+    static FAST_AGENT_STARTED: Mutex<Option<Instant>> = Mutex::new(None);
+
+    fn agent_setup(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+        if ctx.agent_index() == 1 {
+            std::thread::sleep(Duration::from_millis(1500));
+        }
+        Ok(())
+    }
+
+    fn agent_behaviour(
+        ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        let started = if ctx.agent_index() == 1 {
+            &SLOW_AGENT_STARTED
+        } else {
+            &FAST_AGENT_STARTED
+        };
+        started.lock().unwrap().get_or_insert_with(Instant::now);
+        std::thread::sleep(Duration::from_millis(10));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "barrier_is_released_after_timeout",
+        sample_cli_cfg_with_agents(2),
+    )
+    .with_default_duration_s(2)
+    .with_setup_barrier(Duration::from_millis(500))
+    .use_agent_setup(agent_setup)
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert_eq!(2, result.unwrap());
+
+    // The fast agent did not wait for the slow agent, which missed the barrier but still ran.
+    let fast_started = FAST_AGENT_STARTED.lock().unwrap().unwrap();
+    let slow_started = SLOW_AGENT_STARTED.lock().unwrap().unwrap();
+    assert!(slow_started - fast_started >= Duration::from_millis(500));
+}
+
+#[test]
+fn agents_stopped_at_barrier_do_not_complete() {
+    fn agent_setup(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+        if ctx.agent_index() == 1 {
+            // Stop the run while the other agent is waiting, and hold back the barrier until it
+            // has seen the shutdown.
+            ctx.runner_context().force_stop_scenario();
+            std::thread::sleep(Duration::from_secs(1));
+            anyhow::bail!("Stopped the run");
+        }
+        Ok(())
+    }
+
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        Ok(())
+    }
+
+    let run_summary = TempRunSummary::new();
+    let mut cfg = sample_cli_cfg_with_agents(2);
+    cfg.run_summary_path = Some(run_summary.path());
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "agents_stopped_at_barrier_do_not_complete",
+        cfg,
+    )
+    .with_default_duration_s(5)
+    .with_setup_barrier(Duration::from_secs(10))
+    .use_agent_setup(agent_setup)
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert_eq!(0, result.unwrap());
+
+    let summary = run_summary.load();
+    let stopped = &summary.agent_outcomes[0];
+    assert_eq!(SetupOutcome::Succeeded, stopped.setup);
+    assert!(stopped.stopped_before_behaviour);
+    assert_eq!(0, stopped.iterations);
+    assert!(!summary.agent_outcomes[1].stopped_before_behaviour);
+}
+
+#[test]
+fn setup_barrier_cannot_be_used_with_load_profile() {
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "setup_barrier_cannot_be_used_with_load_profile",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_setup_barrier(Duration::from_secs(1))
+    .with_load_profile(LoadProfile::new().step_to(1, Duration::from_secs(1)))
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
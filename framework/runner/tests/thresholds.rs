@@ -0,0 +1,99 @@
+use std::time::Duration;
+use wind_tunnel_core::prelude::AgentBailError;
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, OperationRecord, ScenarioDefinitionBuilder, Threshold,
+    UserValuesConstraint, report_operation, run,
+};
+
+mod common;
+// Thresholds are checked in-process, whichever reporter is selected, so the noop reporter is used.
+use common::sample_cli_cfg_with_agents;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+fn agent_behaviour(ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>) -> HookResult {
+    let operation_record = OperationRecord::new("slow_operation".to_string());
+    std::thread::sleep(Duration::from_millis(50));
+    report_operation(
+        ctx.runner_context().reporter(),
+        operation_record,
+        &Ok::<(), ()>(()),
+    );
+    Ok(())
+}
+
+#[test]
+fn run_passes_when_thresholds_are_met() {
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "run_passes_when_thresholds_are_met",
+        sample_cli_cfg_with_agents(2),
+    )
+    .with_default_duration_s(1)
+    .add_threshold(Threshold::OperationLatency {
+        operation_id: "slow_operation".to_string(),
+        percentile: 95.0,
+        max: Duration::from_secs(5),
+    })
+    .add_threshold(Threshold::OperationErrorRate {
+        operation_id: "slow_operation".to_string(),
+        max_rate: 0.0,
+    })
+    .add_threshold(Threshold::AgentsCompleted { min: 2 })
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert_eq!(2, result.unwrap());
+}
+
+#[test]
+fn run_fails_when_latency_threshold_is_exceeded() {
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "run_fails_when_latency_threshold_is_exceeded",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(1)
+    .add_threshold(Threshold::OperationLatency {
+        operation_id: "slow_operation".to_string(),
+        percentile: 50.0,
+        max: Duration::from_millis(10),
+    })
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn run_fails_when_too_few_agents_complete() {
+    fn agent_behaviour(
+        ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        if ctx.agent_index() == 0 {
+            return Err(AgentBailError::default().into());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "run_fails_when_too_few_agents_complete",
+        sample_cli_cfg_with_agents(2),
+    )
+    .with_default_duration_s(1)
+    .add_threshold(Threshold::AgentsCompleted { min: 2 })
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
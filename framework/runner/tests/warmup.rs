@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use wind_tunnel_runner::prelude::{
+    AgentContext, HookResult, ScenarioDefinitionBuilder, UserValuesConstraint, run,
+};
+
+mod common;
+use common::sample_cli_cfg_with_agents;
+
+#[derive(Default, Debug)]
+struct RunnerContextValue {}
+
+impl UserValuesConstraint for RunnerContextValue {}
+
+#[derive(Default, Debug)]
+struct AgentContextValue {}
+
+impl UserValuesConstraint for AgentContextValue {}
+
+#[test]
+fn warmup_ends_before_the_run() {
+    static WARMUP_ITERATIONS: AtomicUsize = AtomicUsize::new(0);
+    static MEASURED_ITERATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    fn agent_behaviour(
+        ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        if ctx.runner_context().reporter().in_warmup() {
+            WARMUP_ITERATIONS.fetch_add(1, Ordering::SeqCst);
+        } else {
+            MEASURED_ITERATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        Ok(())
+    }
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "warmup_ends_before_the_run",
+        sample_cli_cfg_with_agents(1),
+    )
+    .with_default_duration_s(2)
+    .with_default_warmup_s(1)
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_ok());
+    assert!(WARMUP_ITERATIONS.load(Ordering::SeqCst) > 0);
+    assert!(MEASURED_ITERATIONS.load(Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn warmup_must_be_shorter_than_duration() {
+    fn agent_behaviour(
+        _ctx: &mut AgentContext<RunnerContextValue, AgentContextValue>,
+    ) -> HookResult {
+        Ok(())
+    }
+
+    let mut cli = sample_cli_cfg_with_agents(1);
+    cli.warmup = Some(5);
+
+    let scenario = ScenarioDefinitionBuilder::<RunnerContextValue, AgentContextValue>::new(
+        "warmup_must_be_shorter_than_duration",
+        cli,
+    )
+    .with_default_duration_s(5)
+    .use_agent_behaviour(agent_behaviour);
+
+    let result = run(scenario);
+
+    assert!(result.is_err());
+}
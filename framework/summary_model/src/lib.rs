@@ -49,6 +49,17 @@ pub struct RunSummary {
     ///
     /// This is a Unix timestamp in seconds.
     pub started_at: i64,
+    /// The time the run ended, once every agent had stopped
+    ///
+    /// This is a Unix timestamp in seconds.
+    #[serde(default)]
+    pub ended_at: Option<i64>,
+    /// Why the run ended
+    ///
+    /// Runs that ended for any reason other than [ShutdownReason::DurationElapsed] may have been
+    /// cut short, so their metrics should be treated with care.
+    #[serde(default)]
+    pub shutdown_reason: Option<ShutdownReason>,
     /// The duration that the run was configured with, in seconds
     ///
     /// If the run was configured for soak testing, then this will not be set.
@@ -57,6 +68,13 @@ pub struct RunSummary {
     /// behaviours failed. As long as [RunSummary::peer_end_count] is greater than 0 then that
     /// number of agents will have run for the full duration.
     pub run_duration: Option<u64>,
+    /// The warmup period that the run was configured with, in seconds
+    ///
+    /// Operations and custom metrics recorded during the warmup are tagged with `phase=warmup` so
+    /// that they can be excluded from the summary. The warmup is included in the
+    /// [RunSummary::run_duration].
+    #[serde(default)]
+    pub warmup: Option<u64>,
     /// The number of peers configured
     ///
     /// This is the number of peers that were either configured or required by the behaviour
@@ -75,6 +93,47 @@ pub struct RunSummary {
     /// or with the TryCP runner. In general, each node only sees the roles it was assigned and not
     /// the roles that were assigned across the network.
     pub peer_end_count: usize,
+    /// What happened to each agent during the run
+    ///
+    /// Note: This is only meaningful for single-conductor tests with the standard Wind Tunnel runner.
+    /// In general, each node only sees its own agents.
+    #[serde(default)]
+    pub agent_outcomes: Vec<AgentOutcome>,
+    /// The number of behaviour iterations that were run, across all agents
+    #[serde(default)]
+    pub behaviour_iterations: u64,
+    /// The number of behaviour iterations that failed, by kind of error
+    ///
+    /// A high number of errors relative to [RunSummary::behaviour_iterations] means that the
+    /// metrics from the run may not be representative, even if the agents ran to completion.
+    #[serde(default)]
+    pub behaviour_errors: BTreeMap<String, u64>,
+    /// The number of instrumented operations that failed, by operation ID and kind of error
+    #[serde(default)]
+    pub operation_errors: BTreeMap<String, BTreeMap<String, u64>>,
+    /// The number of metrics that reporters dropped, because they were reported faster than they
+    /// could be written or the reporter had stopped
+    ///
+    /// If this is greater than 0 then the metrics from the run are incomplete.
+    #[serde(default)]
+    pub dropped_metrics: u64,
+    /// The number of metrics that reporters failed to write, for example because of a disk error
+    ///
+    /// If this is greater than 0 then the metrics from the run are incomplete.
+    #[serde(default)]
+    pub failed_metrics: u64,
+    /// The total of each counter metric over the run, such as the number of errors that each
+    /// agent's conductor logged, excluding anything counted during the warmup
+    ///
+    /// Counters that were never incremented after the warmup are not included.
+    #[serde(default)]
+    pub counters: Vec<CounterTotal>,
+    /// The offset of the runner's clock from a reference time server, measured at the start of
+    /// the run
+    ///
+    /// This is only set if a time server was configured and the offset could be measured.
+    #[serde(default)]
+    pub clock_offset: Option<ClockOffset>,
     /// The behaviour configuration
     ///
     /// This is the number of agents that were assigned to each behaviour.
@@ -88,12 +147,124 @@ pub struct RunSummary {
     /// This won't capture all environment variables. Just the ones that the runner is aware of or
     /// that are included by the scenario itself.
     pub env: BTreeMap<String, String>,
+    /// The scenario parameters that were used for this run
+    ///
+    /// These are the resolved values of the parameters declared by the scenario, whether they were
+    /// set by the user or taken from the default.
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+    /// Configuration overlays that the scenario applied on top of its defaults, by name
+    ///
+    /// For example, the conductor config overrides used for each behaviour of a Holochain
+    /// scenario.
+    #[serde(default)]
+    pub config_overlays: BTreeMap<String, serde_json::Value>,
     /// The version of Wind Tunnel that was used for this run
     ///
     /// This is the version of the Wind Tunnel runner that was used to run the scenario.
     pub wind_tunnel_version: String,
     /// The build info that was used for this run
     pub build_info: Option<BuildInfo>,
+    /// The verdict from checking the thresholds set by the scenario
+    ///
+    /// This is only set if the scenario has thresholds.
+    #[serde(default)]
+    pub verdict: Option<Verdict>,
+}
+
+/// Why a run ended
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownReason {
+    /// The run duration elapsed
+    DurationElapsed,
+    /// The runner was interrupted with Ctrl-C
+    Interrupted,
+    /// The scenario stopped itself with `force_stop_scenario`
+    ForceStopped,
+    /// The failure policy aborted the scenario because the error rate was too high
+    Aborted,
+    /// Every agent stopped by itself, for example because they all bailed, before the run was
+    /// shut down
+    AgentsFinished,
+}
+
+/// What happened to a single agent during a run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AgentOutcome {
+    /// The name of the agent, such as `agent-0`
+    pub agent_name: String,
+    /// The behaviour that the agent was assigned
+    pub behaviour: String,
+    /// The result of the agent's setup
+    pub setup: SetupOutcome,
+    /// Why the agent stopped running its behaviour before the end of the run, if it bailed
+    pub bail_reason: Option<String>,
+    /// Whether the agent was stopped after its setup but before its behaviour started, such as
+    /// while waiting for the setup barrier
+    #[serde(default)]
+    pub stopped_before_behaviour: bool,
+    /// The number of behaviour iterations that the agent ran, including failed iterations
+    pub iterations: u64,
+}
+
+/// The result of an agent's setup
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupOutcome {
+    /// The agent was stopped before its setup ran, for example because it was never activated
+    NotRun,
+    /// The setup succeeded, or the scenario has no agent setup
+    Succeeded,
+    /// The setup failed with this error, so the agent did not run its behaviour
+    Failed(String),
+}
+
+/// The total of a counter metric over a run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CounterTotal {
+    /// The name of the counter
+    pub name: String,
+    /// The tags of the counter, such as the agent that it counts for
+    pub tags: BTreeMap<String, String>,
+    /// The total count
+    pub total: u64,
+}
+
+/// The offset of a runner's clock from a reference time server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClockOffset {
+    /// The time server that the clock was compared against
+    pub time_server: String,
+    /// The amount to add to the runner's clock to get the time server's time, in nanoseconds
+    pub offset_ns: i64,
+    /// The round trip time of the exchange with the time server, in nanoseconds
+    ///
+    /// The offset is accurate to within half of the round trip time.
+    pub round_trip_ns: u64,
+}
+
+/// The outcome of checking the thresholds set by a scenario at the end of a run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Verdict {
+    /// Whether every threshold passed
+    pub passed: bool,
+    /// The result of checking each threshold
+    pub checks: Vec<ThresholdCheck>,
+}
+
+/// The result of checking a single threshold
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThresholdCheck {
+    /// A description of the threshold, including the unit of the observed value
+    pub threshold: String,
+    /// The value observed during the run
+    ///
+    /// This is not set if there was no data to check the threshold against, in which case the
+    /// threshold fails.
+    pub observed: Option<f64>,
+    /// Whether the threshold passed
+    pub passed: bool,
 }
 
 impl RunSummary {
@@ -103,13 +274,27 @@ impl RunSummary {
             run_id: args.run_id,
             scenario_name: args.scenario_name,
             started_at: args.started_at,
+            ended_at: None,
+            shutdown_reason: None,
             run_duration: None,
+            warmup: None,
             peer_count: args.peer_count,
             peer_end_count: 0,
+            agent_outcomes: Vec::new(),
+            behaviour_iterations: 0,
+            behaviour_errors: BTreeMap::new(),
+            operation_errors: BTreeMap::new(),
+            dropped_metrics: 0,
+            failed_metrics: 0,
+            counters: Vec::new(),
+            clock_offset: None,
             assigned_behaviours: BTreeMap::new(),
             env: BTreeMap::new(),
+            params: BTreeMap::new(),
+            config_overlays: BTreeMap::new(),
             wind_tunnel_version: args.wind_tunnel_version,
             build_info: None,
+            verdict: None,
         }
     }
 
@@ -119,6 +304,12 @@ impl RunSummary {
         self
     }
 
+    /// Construct [`RunSummary`] with the specified warmup period
+    pub fn with_warmup(mut self, warmup: Option<u64>) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
     /// Construct [`RunSummary`] with the specified assigned behaviours
     pub fn with_assigned_behaviours(
         mut self,
@@ -128,16 +319,77 @@ impl RunSummary {
         self
     }
 
+    /// Construct [`RunSummary`] with the specified scenario parameters
+    pub fn with_params(mut self, params: BTreeMap<String, String>) -> Self {
+        self.params = params;
+        self
+    }
+
     /// Set the peer end count
     pub fn set_peer_end_count(&mut self, peer_end_count: usize) {
         self.peer_end_count = peer_end_count;
     }
 
+    /// Set when the run ended and why
+    pub fn set_end(&mut self, ended_at: i64, shutdown_reason: ShutdownReason) {
+        self.ended_at = Some(ended_at);
+        self.shutdown_reason = Some(shutdown_reason);
+    }
+
+    /// Set what happened to each agent
+    pub fn set_agent_outcomes(&mut self, agent_outcomes: Vec<AgentOutcome>) {
+        self.agent_outcomes = agent_outcomes;
+    }
+
+    /// Set the number of behaviour iterations
+    pub fn set_behaviour_iterations(&mut self, behaviour_iterations: u64) {
+        self.behaviour_iterations = behaviour_iterations;
+    }
+
+    /// Set the number of behaviour errors by kind
+    pub fn set_behaviour_errors(&mut self, behaviour_errors: BTreeMap<String, u64>) {
+        self.behaviour_errors = behaviour_errors;
+    }
+
+    /// Set the number of failed operations by operation ID and kind of error
+    pub fn set_operation_errors(
+        &mut self,
+        operation_errors: BTreeMap<String, BTreeMap<String, u64>>,
+    ) {
+        self.operation_errors = operation_errors;
+    }
+
+    /// Set the number of metrics that reporters dropped and failed to write
+    pub fn set_lost_metrics(&mut self, dropped_metrics: u64, failed_metrics: u64) {
+        self.dropped_metrics = dropped_metrics;
+        self.failed_metrics = failed_metrics;
+    }
+
+    /// Set the counter totals
+    pub fn set_counters(&mut self, counters: Vec<CounterTotal>) {
+        self.counters = counters;
+    }
+
+    /// Set the offset of the runner's clock from a reference time server
+    pub fn set_clock_offset(&mut self, clock_offset: ClockOffset) {
+        self.clock_offset = Some(clock_offset);
+    }
+
     /// Set the build info
     pub fn set_build_info(&mut self, build_info: BuildInfo) {
         self.build_info = Some(build_info);
     }
 
+    /// Set the verdict from checking the scenario thresholds
+    pub fn set_verdict(&mut self, verdict: Verdict) {
+        self.verdict = Some(verdict);
+    }
+
+    /// Add a configuration overlay, replacing any overlay that was added with the same name
+    pub fn add_config_overlay(&mut self, name: String, overlay: serde_json::Value) {
+        self.config_overlays.insert(name, overlay);
+    }
+
     /// Add an environment variable
     pub fn add_env(&mut self, key: String, value: String) {
         self.env.insert(key, value);
@@ -149,8 +401,11 @@ impl RunSummary {
     /// It uses the
     ///     - Scenario name
     ///     - Run duration
+    ///     - Warmup period, if set
     ///     - Assigned behaviours
     ///     - Selected environment variables
+    ///     - Scenario parameters, if any
+    ///     - Configuration overlays, if any
     ///     - Wind Tunnel version
     ///
     /// The fingerprint is computed using [sha3::Sha3_256].
@@ -160,6 +415,10 @@ impl RunSummary {
         if let Some(run_duration) = self.run_duration {
             Digest::update(&mut hasher, run_duration.to_le_bytes());
         }
+        if let Some(warmup) = self.warmup {
+            Digest::update(&mut hasher, b"warmup");
+            Digest::update(&mut hasher, warmup.to_le_bytes());
+        }
         self.assigned_behaviours
             .iter()
             .sorted_by_key(|(k, _)| k.to_owned())
@@ -174,6 +433,23 @@ impl RunSummary {
                 Digest::update(&mut hasher, k.as_bytes());
                 Digest::update(&mut hasher, v.as_bytes());
             });
+        // Params and overlays are hashed as JSON, so that where one key or value ends and the next
+        // begins is unambiguous.
+        if !self.params.is_empty() {
+            Digest::update(&mut hasher, b"params");
+            Digest::update(
+                &mut hasher,
+                serde_json::to_vec(&self.params).expect("Params serialize to JSON"),
+            );
+        }
+        if !self.config_overlays.is_empty() {
+            Digest::update(&mut hasher, b"config_overlays");
+            let mut config_overlays = serde_json::to_value(&self.config_overlays)
+                .expect("Config overlays serialize to JSON");
+            // Sort the keys so that the same overlay always has the same fingerprint
+            config_overlays.sort_all_objects();
+            Digest::update(&mut hasher, config_overlays.to_string().as_bytes());
+        }
         Digest::update(&mut hasher, self.wind_tunnel_version.as_bytes());
 
         format!("{:x}", hasher.finalize())
@@ -247,6 +523,7 @@ mod tests {
         assert_eq!(run_summary.scenario_name, "scenario");
         assert_eq!(run_summary.started_at, 100);
         assert_eq!(run_summary.run_duration, None);
+        assert_eq!(run_summary.warmup, None);
         assert_eq!(run_summary.peer_count, 2);
         assert_eq!(run_summary.peer_end_count, 0);
         assert!(run_summary.assigned_behaviours.is_empty());
@@ -301,6 +578,125 @@ mod tests {
         assert_eq!(run_summary.build_info, Some(build_info));
     }
 
+    #[test]
+    fn test_warmup_changes_fingerprint() {
+        let run_summary = RunSummary::new(RunSummaryInitArgs {
+            run_id: "test".to_string(),
+            scenario_name: "scenario".to_string(),
+            started_at: 100,
+            peer_count: 2,
+            wind_tunnel_version: "1.0.0".to_string(),
+        })
+        .with_run_duration(Some(60));
+        let with_warmup = run_summary.clone().with_warmup(Some(10));
+
+        assert_ne!(run_summary.fingerprint(), with_warmup.fingerprint());
+        assert_eq!(
+            run_summary.fingerprint(),
+            run_summary.clone().with_warmup(None).fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_params_change_fingerprint() {
+        let run_summary = RunSummary::new(RunSummaryInitArgs {
+            run_id: "test".to_string(),
+            scenario_name: "scenario".to_string(),
+            started_at: 100,
+            peer_count: 2,
+            wind_tunnel_version: "1.0.0".to_string(),
+        })
+        .with_run_duration(Some(60));
+        let with_params = run_summary.clone().with_params(BTreeMap::from([(
+            "send_interval_ms".to_string(),
+            "100".to_string(),
+        )]));
+
+        assert_ne!(run_summary.fingerprint(), with_params.fingerprint());
+        assert_eq!(
+            run_summary.fingerprint(),
+            run_summary
+                .clone()
+                .with_params(BTreeMap::new())
+                .fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_params_with_same_characters_have_different_fingerprints() {
+        let run_summary = RunSummary::new(RunSummaryInitArgs {
+            run_id: "test".to_string(),
+            scenario_name: "scenario".to_string(),
+            started_at: 100,
+            peer_count: 2,
+            wind_tunnel_version: "1.0.0".to_string(),
+        });
+        let params = |k: &str, v: &str| {
+            run_summary
+                .clone()
+                .with_params(BTreeMap::from([(k.to_string(), v.to_string())]))
+                .fingerprint()
+        };
+        let with_overlay = |k: &str, v: &str| {
+            let mut run_summary = run_summary.clone();
+            run_summary.add_config_overlay(k.to_string(), serde_json::json!(v));
+            run_summary.fingerprint()
+        };
+
+        assert_ne!(params("ab", "c"), params("a", "bc"));
+        assert_ne!(with_overlay("ab", "c"), with_overlay("a", "bc"));
+    }
+
+    #[test]
+    fn test_config_overlays_change_fingerprint() {
+        let run_summary = RunSummary::new(RunSummaryInitArgs {
+            run_id: "test".to_string(),
+            scenario_name: "scenario".to_string(),
+            started_at: 100,
+            peer_count: 2,
+            wind_tunnel_version: "1.0.0".to_string(),
+        })
+        .with_run_duration(Some(60));
+        let mut with_overlay = run_summary.clone();
+        with_overlay.add_config_overlay(
+            "conductor_config".to_string(),
+            serde_json::json!({"network": {"target_arc_factor": 0, "mem_bootstrap": false}}),
+        );
+        let mut with_reordered_overlay = run_summary.clone();
+        with_reordered_overlay.add_config_overlay(
+            "conductor_config".to_string(),
+            serde_json::json!({"network": {"mem_bootstrap": false, "target_arc_factor": 0}}),
+        );
+
+        assert_ne!(run_summary.fingerprint(), with_overlay.fingerprint());
+        assert_eq!(
+            with_overlay.fingerprint(),
+            with_reordered_overlay.fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_load_run_summary_without_warmup() {
+        let json = r#"{"run_id":"test","scenario_name":"scenario","started_at":100,"run_duration":60,"peer_count":2,"peer_end_count":2,"assigned_behaviours":{},"env":{},"wind_tunnel_version":"1.0.0","build_info":null}"#;
+
+        let run_summary = load_run_summary(json.as_bytes()).unwrap();
+
+        assert_eq!(run_summary.warmup, None);
+        assert_eq!(run_summary.ended_at, None);
+        assert_eq!(run_summary.shutdown_reason, None);
+        assert!(run_summary.agent_outcomes.is_empty());
+        assert!(run_summary.params.is_empty());
+        assert!(run_summary.config_overlays.is_empty());
+        assert_eq!(run_summary.behaviour_iterations, 0);
+        assert!(run_summary.behaviour_errors.is_empty());
+        assert!(run_summary.operation_errors.is_empty());
+        assert_eq!(run_summary.dropped_metrics, 0);
+        assert_eq!(run_summary.failed_metrics, 0);
+        assert!(run_summary.counters.is_empty());
+        assert_eq!(run_summary.clock_offset, None);
+        assert_eq!(run_summary.verdict, None);
+    }
+
     #[inline(always)]
     fn build_info() -> BuildInfo {
         BuildInfo {
@@ -286,7 +286,8 @@ job "{{ (ds "vars").job_name }}" {
 
           config {
             command = "bash"
-            args    = ["${NOMAD_TASK_DIR}/upload_metrics.sh"]
+            // The upload runs after the scenario has exited, so upload every metrics file.
+            args    = ["${NOMAD_TASK_DIR}/upload_metrics.sh", "--final"]
           }
         }
       }
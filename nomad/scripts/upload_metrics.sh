@@ -2,6 +2,10 @@
 
 # this script is executed on Nomad clients, after a scenario has been run, to upload metrics with telegraf.
 # It also takes care of setting the RUN_ID if unset from env
+#
+# Pass --final once the scenario has exited, to upload every metrics file, including any that the
+# runner did not get to mark complete because it crashed or was killed. Without it, files that are
+# still being written are skipped and left for a later upload.
 
 set -euo pipefail
 
@@ -64,6 +68,17 @@ function upload_metric_file() {
   done
 }
 
+final_upload=false
+for arg in "$@"; do
+  case "$arg" in
+    --final) final_upload=true ;;
+    *)
+      echo "Unknown argument: $arg" >&2
+      exit 1
+      ;;
+  esac
+done
+
 check_envset "WT_METRICS_DIR"
 check_envset "INFLUX_TOKEN"
 check_envset "INFLUX_HOST"
@@ -89,25 +104,64 @@ else
     echo "RUN_ID is already set to '$RUN_ID'"
 fi
 
+# Metric files that have already been uploaded, so that a failed upload can be resumed.
+uploaded_record="$WT_METRICS_DIR/.uploaded"
+touch "$uploaded_record"
+
+# Segments that the influx file reporter is still writing or compressing, according to the
+# manifests it keeps for each run. These are left for the next upload, unless this is the final
+# upload, when the runner has exited and will not complete them.
+incomplete_segments=()
+shopt -s nullglob
+if [[ "$final_upload" != "true" ]]; then
+    for manifest in "$WT_METRICS_DIR"/*.manifest.json; do
+        mapfile -t -O "${#incomplete_segments[@]}" incomplete_segments < <(jq --raw-output '.segments[] | select(.complete | not) | .file' < "$manifest")
+    done
+fi
+
 # for each metric file, import to influx
 # Avoid iterating a literal glob when there are no metric files.
-shopt -s nullglob
-metric_files=("$WT_METRICS_DIR"/*.influx)
+metric_files=("$WT_METRICS_DIR"/*.influx "$WT_METRICS_DIR"/*.influx.gz "$WT_METRICS_DIR"/*.influx.zst)
 if (( ${#metric_files[@]} == 0 )); then
     echo "No metrics files found in ${WT_METRICS_DIR}, skipping upload."
     exit 0
 fi
 
 for metric_file in "${metric_files[@]}"; do
+    metric_file_name="$(basename "$metric_file")"
+    if grep --quiet --fixed-strings --line-regexp "$metric_file_name" "$uploaded_record"; then
+        echo "Skipping $metric_file, already uploaded"
+        continue
+    fi
+    if [[ " ${incomplete_segments[*]} " == *" $metric_file_name "* ]]; then
+        echo "Skipping $metric_file, still being written"
+        continue
+    fi
+
     echo "Importing $metric_file"
-    out_file="$NOMAD_ALLOC_DIR/$(basename "$metric_file")"
+    # Decompress closed segments written by the influx file reporter
+    input_file="$metric_file"
+    case "$metric_file" in
+        *.gz)
+            input_file="$NOMAD_ALLOC_DIR/${metric_file_name%.gz}.in"
+            gzip --decompress --stdout "$metric_file" > "$input_file"
+            ;;
+        *.zst)
+            input_file="$NOMAD_ALLOC_DIR/${metric_file_name%.zst}.in"
+            zstd --decompress --stdout --quiet "$metric_file" > "$input_file"
+            ;;
+    esac
+    out_file="$NOMAD_ALLOC_DIR/${metric_file_name%.gz}"
+    out_file="${out_file%.zst}"
     # Tag metrics with RUN_ID, if set
     if [[ "${RUN_ID:+x}" == "x" ]]; then
-        lp-tool -input "$metric_file" -output "$out_file" -tag run_id="$RUN_ID"
-    else
-        cp "$metric_file" "$out_file"
+        lp-tool -input "$input_file" -output "$out_file" -tag run_id="$RUN_ID"
+    elif [[ "$input_file" != "$out_file" ]]; then
+        cp "$input_file" "$out_file"
     fi
+    [[ "$input_file" != "$metric_file" ]] && rm -f "$input_file"
     # import metrics to influx
     upload_metric_file "$metric_file" "$out_file"
+    echo "$metric_file_name" >> "$uploaded_record"
     echo "Finished importing $metric_file"
 done
@@ -8,7 +8,6 @@ use timed_integrity::TimedEntry;
 
 #[derive(Debug, Default)]
 struct ScenarioValues {
-    sent_actions: u32,
     seen_actions: HashSet<ActionHash>,
 }
 
@@ -35,13 +34,7 @@ fn agent_behaviour_write(
         },
     )?;
 
-    ctx.get_mut().scenario_values.sent_actions += 1;
-
-    let agent_pub_key = ctx.get().cell_id().agent_pubkey().to_string();
-    let metric = ReportMetric::new("sent_count")
-        .with_tag("agent", agent_pub_key)
-        .with_field("value", ctx.get().scenario_values.sent_actions);
-    ctx.runner_context().reporter().clone().add_custom(metric);
+    ctx.counter("sent_count").inc();
 
     Ok(())
 }
@@ -51,6 +44,7 @@ fn agent_behaviour_record_lag(
 ) -> HookResult {
     let found: Vec<Record> = call_zome(ctx, "timed", "get_timed_entries_local", ())?;
 
+    let new_records = ctx.counter("recv_count");
     let found = found
         .into_iter()
         .filter(|r| {
@@ -89,13 +83,9 @@ fn agent_behaviour_record_lag(
             .scenario_values
             .seen_actions
             .insert(new_record.action_address().clone());
+        new_records.inc();
     }
 
-    let metric = ReportMetric::new("recv_count")
-        .with_tag("agent", agent_pub_key)
-        .with_field("value", ctx.get().scenario_values.seen_actions.len() as f64);
-    reporter_handle.add_custom(metric);
-
     Ok(())
 }
 
@@ -37,13 +37,8 @@ fn responder_behaviour(
     ctx: &mut AgentContext<PeerkitRunnerContext, PeerkitAgentContext>,
 ) -> anyhow::Result<()> {
     let messages = take_received_messages(ctx)?;
-    if !messages.is_empty() {
-        ctx.runner_context().reporter().add_custom(
-            ReportMetric::new("peerkit_messages_received")
-                .with_tag("behaviour", RESPONDER)
-                .with_field("count", messages.len() as u32),
-        );
-    }
+    ctx.counter("peerkit_messages_received")
+        .add(messages.len() as u64);
     sleep_interval(ctx)
 }
 
@@ -13,6 +13,17 @@ fn agent_setup(
 
 fn agent_behaviour(
     ctx: &mut AgentContext<HolochainRunnerContext, HolochainAgentContext>,
+) -> HookResult {
+    // Report the write and the read back as one transaction, failed if either failed.
+    let transaction = ctx.start_transaction("write_read");
+    let result = write_then_read(ctx);
+    transaction.finish(&result);
+
+    result
+}
+
+fn write_then_read(
+    ctx: &mut AgentContext<HolochainRunnerContext, HolochainAgentContext>,
 ) -> HookResult {
     let action_hash: ActionHash = call_zome(
         ctx,
@@ -380,6 +380,11 @@ pub fn agent_behaviour<SV: UnytScenarioValues>(
             amount_per_agent,
             participating_agents.len()
         );
+        // Report the payments to all counterparties as one transaction, failed if any failed.
+        let mut transaction = ctx.start_transaction("spend");
+        transaction.add_attr("arc", arc_type.as_tag().to_string());
+        transaction.add_field("counterparties", participating_agents.len() as u64);
+        let mut spend_result = Ok(());
         for counterparty in participating_agents {
             match ctx.unyt_create_commitment(CommitmentInput {
                 counterparty: counterparty.clone(),
@@ -404,9 +409,11 @@ pub fn agent_behaviour<SV: UnytScenarioValues>(
                         "[agent {}] failed to create commitment for {counterparty}: {err}",
                         ctx.agent_index()
                     );
+                    spend_result = Err(err);
                 }
             }
         }
+        transaction.finish(&spend_result);
     } else {
         log::warn!(
             "[agent {}] no spendable amount, balance: {}, fees: {}, credit_limit: {}",
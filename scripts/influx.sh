@@ -77,15 +77,31 @@ import_lp_metrics() {
     # for each metric file, import to influx
     local tmp_output_file
     local output_file
-    for metric_file in "$wt_metrics_dir"/*.influx; do
+    local tmp_input_file
+    local input_file
+    shopt -s nullglob
+    for metric_file in "$wt_metrics_dir"/*.influx "$wt_metrics_dir"/*.influx.gz "$wt_metrics_dir"/*.influx.zst; do
         echo "Importing $metric_file"
-        output_file="$metric_file"
-        # reset tmp_output_file to prevent accidental reuse and double deletion
+        # reset tmp files to prevent accidental reuse and double deletion
+        tmp_input_file=""
         tmp_output_file=""
+        # Decompress closed segments written by the influx file reporter
+        case "$metric_file" in
+            *.gz)
+                tmp_input_file="$(mktemp)"
+                gzip --decompress --stdout "$metric_file" > "$tmp_input_file"
+                ;;
+            *.zst)
+                tmp_input_file="$(mktemp)"
+                zstd --decompress --stdout --quiet "$metric_file" > "$tmp_input_file"
+                ;;
+        esac
+        input_file="${tmp_input_file:-$metric_file}"
+        output_file="$input_file"
         # Tag metrics with run_id, if set
         if [[ "${run_id:+x}" == "x" ]]; then
             tmp_output_file="$(mktemp)"
-            lp-tool -input "$metric_file" -output "$tmp_output_file" -tag run_id="$run_id"
+            lp-tool -input "$input_file" -output "$tmp_output_file" -tag run_id="$run_id"
             output_file="$tmp_output_file"
         fi
         echo "Using output file: $output_file"
@@ -97,6 +113,7 @@ import_lp_metrics() {
             --file "$output_file"
         # remove temp file
         [[ "${tmp_output_file:+x}" == "x" ]] && rm -f "$tmp_output_file"
+        [[ "${tmp_input_file:+x}" == "x" ]] && rm -f "$tmp_input_file"
         echo "Finished importing $metric_file"
     done
 }
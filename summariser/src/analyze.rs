@@ -4,6 +4,7 @@ use crate::model::{
     PartitionGaugeStats, PartitionKey, PartitionedCounterStats, PartitionedGaugeStats,
     PartitionedRateStats, PartitionedTimingStats, StandardRateStats, StandardTimingsStats,
 };
+use crate::query::CounterData;
 use anyhow::Context;
 use itertools::Itertools;
 use polars::frame::DataFrame;
@@ -13,13 +14,11 @@ pub(crate) fn standard_timing_stats(
     frame: DataFrame,
     column: &str,
     window_duration: &str,
-    skip: Option<i64>,
 ) -> anyhow::Result<StandardTimingsStats> {
-    let mut value_col = frame.column(column).context("Read value column")?.clone();
-    if let Some(skip) = skip {
-        value_col = value_col.slice(skip, usize::MAX);
-    }
-    let value_series = value_col.as_materialized_series();
+    let value_series = frame
+        .column(column)
+        .context("Read value column")?
+        .as_materialized_series();
 
     let mean = value_series.mean().context("Mean")?;
     let std = value_series.std(0).context("Std")?;
@@ -30,6 +29,22 @@ pub(crate) fn standard_timing_stats(
     let p95 = round_to_n_dp(sorted_percentile(&sorted, 0.95), 6);
     let p99 = round_to_n_dp(sorted_percentile(&sorted, 0.99), 6);
 
+    Ok(StandardTimingsStats {
+        mean: round_to_n_dp(mean, 6),
+        std: round_to_n_dp(std, 6),
+        p50,
+        p95,
+        p99,
+        trend: timing_trend(&frame, column, window_duration)?,
+    })
+}
+
+/// The mean of `column` over time, in windows of `window_duration`.
+pub(crate) fn timing_trend(
+    frame: &DataFrame,
+    column: &str,
+    window_duration: &str,
+) -> anyhow::Result<Float64Trend> {
     let trend = frame
         .clone()
         .lazy()
@@ -58,16 +73,9 @@ pub(crate) fn standard_timing_stats(
         .filter_map(|v| v.map(|v| round_to_n_dp(v, 6)))
         .collect_vec();
 
-    Ok(StandardTimingsStats {
-        mean: round_to_n_dp(mean, 6),
-        std: round_to_n_dp(std, 6),
-        p50,
-        p95,
-        p99,
-        trend: Float64Trend {
-            trend,
-            window_duration: window_duration.to_string(),
-        },
+    Ok(Float64Trend {
+        trend,
+        window_duration: window_duration.to_string(),
     })
 }
 
@@ -234,7 +242,7 @@ pub(crate) fn partitioned_timing_stats(
             .collect()
             .context("filter by partition")?;
 
-        let summary_timing = standard_timing_stats(filtered, column, window_duration, None)
+        let summary_timing = standard_timing_stats(filtered, column, window_duration)
             .with_context(|| format!("Timing stats for {key:?}"))?;
 
         sum_mean += summary_timing.mean;
@@ -427,6 +435,112 @@ pub(crate) fn partitioned_counter_stats(
     })
 }
 
+/// Get the [`PartitionedCounterStats`] for a frame where the given column holds the increase of a
+/// counter since its previous report, partitioned by tags.
+///
+/// Unlike [`partitioned_counter_stats`], which takes the difference between the first and last
+/// values of a cumulative counter, the increases are summed, so that the increase in the first
+/// report is counted too.
+pub(crate) fn partitioned_counter_increase_stats(
+    frame: DataFrame,
+    column: &str,
+    window_duration: &str,
+    partition_by: &[&str],
+) -> anyhow::Result<PartitionedCounterStats> {
+    let partition_cols: Vec<Expr> = partition_by.iter().map(|&s| col(s)).collect();
+    let frame = frame
+        .lazy()
+        .filter(col("time").is_not_null())
+        .filter(col(column).is_not_null())
+        .with_column(col(column).cast(DataType::UInt64));
+
+    let per_partition = frame
+        .clone()
+        .group_by(partition_cols.clone())
+        .agg([col(column).sum().alias("count")])
+        .collect()
+        .context("Sum counts per partition")?;
+    let counts = per_partition
+        .column("count")?
+        .u64()
+        .context("Counts as u64")?
+        .into_no_null_iter()
+        .collect::<Vec<_>>();
+
+    // The trend is the mean increase per partition in each window, across the partitions that
+    // reported in that window.
+    let trend_frame = frame
+        .sort(
+            ["time"],
+            SortMultipleOptions::default().with_maintain_order(true),
+        )
+        .group_by_dynamic(
+            col("time"),
+            partition_cols,
+            DynamicGroupOptions {
+                every: Duration::parse(window_duration),
+                period: Duration::parse(window_duration),
+                offset: Duration::parse("0s"),
+                ..Default::default()
+            },
+        )
+        .agg([col(column).sum().cast(DataType::Float64).alias("delta")])
+        .group_by([col("time")])
+        .agg([col("delta").mean().alias("mean_delta")])
+        .sort(
+            ["time"],
+            SortMultipleOptions::default().with_maintain_order(true),
+        )
+        .collect()?;
+    let trend: Vec<f64> = trend_frame
+        .column("mean_delta")?
+        .f64()?
+        .iter()
+        .map(|v| round_to_n_dp(v.unwrap_or(0.0), 4))
+        .collect();
+
+    let partition_count = counts.len();
+    let total_count: u64 = counts.iter().sum();
+    let mean_count = if partition_count == 0 {
+        0
+    } else {
+        (total_count as f64 / partition_count as f64).round() as u64
+    };
+
+    Ok(PartitionedCounterStats {
+        total_count,
+        partition_count,
+        partitions_above_zero: counts.iter().filter(|count| **count > 0).count(),
+        mean_count,
+        max_per_partition: counts.iter().copied().max().unwrap_or(0),
+        min_per_partition: counts.iter().copied().min().unwrap_or(0),
+        trend,
+        window_duration: window_duration.to_string(),
+    })
+}
+
+/// Get the [`PartitionedCounterStats`] for a counter loaded with
+/// [`query_custom_counter_data`](crate::query::query_custom_counter_data), whichever field it was
+/// reported in. Returns an empty result when no series is found, as with
+/// [`partitioned_counter_stats_allow_empty`].
+pub(crate) fn partitioned_custom_counter_stats_allow_empty(
+    data: anyhow::Result<CounterData>,
+    window_duration: &str,
+    partition_by: &[&str],
+) -> anyhow::Result<PartitionedCounterStats> {
+    match data {
+        Ok(CounterData::Typed(df)) => {
+            partitioned_counter_increase_stats(df, "count", window_duration, partition_by)
+        }
+        Ok(CounterData::Legacy(df)) => {
+            partitioned_counter_stats(df, "value", window_duration, partition_by)
+        }
+        Err(e) => {
+            partitioned_counter_stats_allow_empty(Err(e), "value", window_duration, partition_by)
+        }
+    }
+}
+
 /// Like [`partitioned_counter_stats`] but returns an empty result when no series is
 /// found in InfluxDB. Use this for metrics that track errors where zero occurrences
 /// is a valid and expected outcome.
@@ -1344,3 +1458,65 @@ pub(crate) fn running_conductors_stats(
         },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame of typed counter reports, with the increase since the previous report in `count`
+    /// and the time of the report in seconds from the start of the run.
+    fn counter_frame(reports: &[(&str, i64, u64)]) -> DataFrame {
+        let time = Series::new(
+            "time".into(),
+            reports
+                .iter()
+                .map(|(_, secs, _)| secs * 1_000_000_000)
+                .collect::<Vec<_>>(),
+        )
+        .cast(&DataType::Datetime(TimeUnit::Nanoseconds, None))
+        .unwrap();
+        let agent = Series::new(
+            "agent".into(),
+            reports
+                .iter()
+                .map(|(agent, _, _)| *agent)
+                .collect::<Vec<_>>(),
+        );
+        let count = Series::new(
+            "count".into(),
+            reports
+                .iter()
+                .map(|(_, _, count)| *count)
+                .collect::<Vec<_>>(),
+        );
+
+        DataFrame::new(vec![time.into(), agent.into(), count.into()]).unwrap()
+    }
+
+    #[test]
+    fn counter_increase_stats_count_the_first_report() {
+        let frame = counter_frame(&[("a", 1, 5), ("b", 1, 2), ("a", 2, 3), ("a", 12, 4)]);
+
+        let stats = partitioned_counter_increase_stats(frame, "count", "10s", &["agent"]).unwrap();
+
+        assert_eq!(stats.total_count, 14);
+        assert_eq!(stats.partition_count, 2);
+        assert_eq!(stats.partitions_above_zero, 2);
+        assert_eq!(stats.mean_count, 7);
+        assert_eq!(stats.max_per_partition, 12);
+        assert_eq!(stats.min_per_partition, 2);
+        assert_eq!(stats.trend, vec![5.0, 4.0]);
+        assert_eq!(stats.window_duration, "10s");
+    }
+
+    #[test]
+    fn counter_increase_stats_of_a_single_report() {
+        let frame = counter_frame(&[("a", 1, 3)]);
+
+        let stats = partitioned_counter_increase_stats(frame, "count", "10s", &["agent"]).unwrap();
+
+        assert_eq!(stats.total_count, 3);
+        assert_eq!(stats.partition_count, 1);
+        assert_eq!(stats.trend, vec![3.0]);
+    }
+}
@@ -15,6 +15,17 @@ use polars::frame::DataFrame;
 use polars::prelude::*;
 use wind_tunnel_summary_model::RunSummary;
 
+/// Filter out data recorded during the warmup period, for runs that had one.
+///
+/// Runs without a warmup get no filter, so that their queries are unchanged.
+fn warmup_filter(summary: &RunSummary) -> &'static str {
+    if summary.warmup.is_some() {
+        " AND phase != 'warmup'"
+    } else {
+        ""
+    }
+}
+
 pub async fn query_instrument_data(
     client: influxdb::Client,
     summary: &RunSummary,
@@ -22,8 +33,10 @@ pub async fn query_instrument_data(
 ) -> anyhow::Result<DataFrame> {
     const TABLE: &str = "wt.instruments.operation_duration";
     let q = ReadQuery::new(format!(
-        r#"SELECT value FROM "windtunnel"."autogen"."{TABLE}" WHERE run_id = '{}' AND operation_id = '{}' AND is_error = 'false'"#,
-        summary.run_id, operation_id
+        r#"SELECT value FROM "windtunnel"."autogen"."{TABLE}" WHERE run_id = '{}' AND operation_id = '{}' AND is_error = 'false'{}"#,
+        summary.run_id,
+        operation_id,
+        warmup_filter(summary)
     ));
     log::debug!("Querying: {q:?}");
 
@@ -53,8 +66,9 @@ pub async fn query_zome_call_instrument_data(
 ) -> anyhow::Result<DataFrame> {
     const TABLE: &str = "wt.instruments.operation_duration";
     let q = ReadQuery::new(format!(
-        r#"SELECT value, zome_name, fn_name, agent FROM "windtunnel"."autogen"."{TABLE}" WHERE run_id = '{}' AND (operation_id = 'app_call_zome' OR operation_id = 'trycp_app_call_zome') AND is_error = 'false'"#,
-        summary.run_id
+        r#"SELECT value, zome_name, fn_name, agent FROM "windtunnel"."autogen"."{TABLE}" WHERE run_id = '{}' AND (operation_id = 'app_call_zome' OR operation_id = 'trycp_app_call_zome') AND is_error = 'false'{}"#,
+        summary.run_id,
+        warmup_filter(summary)
     ));
     log::debug!("Querying: {q:?}");
 
@@ -84,8 +98,9 @@ pub async fn query_zome_call_instrument_data_errors(
 ) -> anyhow::Result<DataFrame> {
     const TABLE: &str = "wt.instruments.operation_duration";
     let q = ReadQuery::new(format!(
-        r#"SELECT value, zome_name, fn_name FROM "windtunnel"."autogen"."{TABLE}" WHERE run_id = '{}' AND (operation_id = 'app_call_zome' OR operation_id = 'trycp_app_call_zome') AND is_error = 'true'"#,
-        summary.run_id
+        r#"SELECT value, zome_name, fn_name FROM "windtunnel"."autogen"."{TABLE}" WHERE run_id = '{}' AND (operation_id = 'app_call_zome' OR operation_id = 'trycp_app_call_zome') AND is_error = 'true'{}"#,
+        summary.run_id,
+        warmup_filter(summary)
     ));
     log::debug!("Querying: {q:?}");
 
@@ -126,13 +141,61 @@ pub async fn query_custom_data(
     metric: &str,
     tags: &[&str],
 ) -> anyhow::Result<DataFrame> {
-    let mut select_columns: Vec<&str> = vec!["value"];
+    query_custom_field(client, summary, metric, "value", tags).await
+}
+
+/// The data for a custom metric that counts events, as loaded by [`query_custom_counter_data`].
+pub enum CounterData {
+    /// Reported by a typed `Counter`, with its `count` field, the increase since the previous
+    /// report.
+    Typed(DataFrame),
+    /// A cumulative count in the `value` field, as reported by runs from before the metric was
+    /// reported by a typed `Counter`.
+    Legacy(DataFrame),
+}
+
+/// Query [`CounterData`] for a custom metric reported by a typed `Counter`.
+///
+/// If the metric has no `count` field, it is assumed to be from an older run that reported a
+/// cumulative count in its `value` field, which is queried instead.
+pub async fn query_custom_counter_data(
+    client: influxdb::Client,
+    summary: &RunSummary,
+    metric: &str,
+    tags: &[&str],
+) -> anyhow::Result<CounterData> {
+    match query_custom_field(client.clone(), summary, metric, "count", tags).await {
+        Ok(frame) => Ok(CounterData::Typed(frame)),
+        Err(e)
+            if matches!(
+                e.downcast_ref::<LoadError>(),
+                Some(LoadError::NoSeriesInResult { .. })
+            ) =>
+        {
+            log::debug!("No count field for {metric}, falling back to the value field");
+            query_custom_field(client, summary, metric, "value", tags)
+                .await
+                .map(CounterData::Legacy)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn query_custom_field(
+    client: influxdb::Client,
+    summary: &RunSummary,
+    metric: &str,
+    field: &str,
+    tags: &[&str],
+) -> anyhow::Result<DataFrame> {
+    let mut select_columns: Vec<&str> = vec![field];
     select_columns.extend_from_slice(tags);
     let select = select_columns.join(", ");
 
     let q = ReadQuery::new(format!(
-        r#"SELECT {select} FROM "windtunnel"."autogen"."{metric}" WHERE run_id = '{run_id}'"#,
-        run_id = summary.run_id
+        r#"SELECT {select} FROM "windtunnel"."autogen"."{metric}" WHERE run_id = '{run_id}'{warmup_filter}"#,
+        run_id = summary.run_id,
+        warmup_filter = warmup_filter(summary)
     ));
     log::debug!("Querying: {q:?}");
 
@@ -525,4 +588,19 @@ mod tests {
                 "#,
         );
     }
+
+    #[test]
+    fn test_warmup_filter_only_applies_to_runs_with_a_warmup() {
+        let summary = RunSummary::new(wind_tunnel_summary_model::RunSummaryInitArgs {
+            run_id: "test_run_id".to_string(),
+            scenario_name: "test".to_string(),
+            started_at: 1756301266,
+            peer_count: 1,
+            wind_tunnel_version: "0.1.0".to_string(),
+        });
+        assert_eq!("", warmup_filter(&summary));
+
+        let summary = summary.with_warmup(Some(30));
+        assert_eq!(" AND phase != 'warmup'", warmup_filter(&summary));
+    }
 }
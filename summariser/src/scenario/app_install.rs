@@ -1,4 +1,4 @@
-use crate::analyze::standard_timing_stats;
+use crate::analyze::{standard_timing_stats, timing_trend};
 use crate::model::StandardTimingsStats;
 use crate::query;
 use anyhow::Context;
@@ -32,9 +32,14 @@ pub(crate) async fn summarize_app_install(
         .context("First")?
         .try_extract::<f64>()?;
 
+    // The first install is reported on its own as a cold-start baseline, but is still part of the
+    // trend so that it shows how install times changed over the whole run.
+    let mut install_app_timing = standard_timing_stats(frame.slice(1, usize::MAX), "value", "10s")
+        .context("Standard timing stats")?;
+    install_app_timing.trend = timing_trend(&frame, "value", "10s").context("Install trend")?;
+
     Ok(AppInstallSummary {
         first_install_time: first,
-        install_app_timing: standard_timing_stats(frame, "value", "10s", Some(1))
-            .context("Standard timing stats")?,
+        install_app_timing,
     })
 }
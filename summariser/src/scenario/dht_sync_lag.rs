@@ -1,5 +1,5 @@
 use crate::analyze::{
-    delivery_ratio, partitioned_counter_stats_allow_empty, partitioned_rate_stats,
+    delivery_ratio, partitioned_custom_counter_stats_allow_empty, partitioned_rate_stats,
 };
 use crate::model::{PartitionedCounterStats, PartitionedRateStats, PartitionedTimingStats};
 use crate::{analyze, query};
@@ -54,19 +54,27 @@ pub(crate) async fn summarize_dht_sync_lag(
             .await
             .context("Load lag data")?;
 
-    let sent_count_result =
-        query::query_custom_data(client.clone(), &summary, "wt.custom.sent_count", &["agent"])
-            .await;
+    let sent_count_result = query::query_custom_counter_data(
+        client.clone(),
+        &summary,
+        "wt.custom.sent_count",
+        &["agent"],
+    )
+    .await;
 
-    let recv_count_result =
-        query::query_custom_data(client.clone(), &summary, "wt.custom.recv_count", &["agent"])
-            .await;
+    let recv_count_result = query::query_custom_counter_data(
+        client.clone(),
+        &summary,
+        "wt.custom.recv_count",
+        &["agent"],
+    )
+    .await;
 
     let sent_count =
-        partitioned_counter_stats_allow_empty(sent_count_result, "value", "10s", &["agent"])
+        partitioned_custom_counter_stats_allow_empty(sent_count_result, "10s", &["agent"])
             .context("Counter stats for dht_sync_sent_count")?;
     let recv_count =
-        partitioned_counter_stats_allow_empty(recv_count_result, "value", "10s", &["agent"])
+        partitioned_custom_counter_stats_allow_empty(recv_count_result, "10s", &["agent"])
             .context("Counter stats for dht_sync_recv_count")?;
     let delivery_ratio = delivery_ratio(sent_count.total_count, &recv_count);
 
@@ -25,7 +25,7 @@ pub(crate) async fn summarize_first_call(
         .context("Load instrument data")?;
 
     Ok(FirstCallSummary {
-        zome_call_timing: standard_timing_stats(frame, "value", "10s", None)
+        zome_call_timing: standard_timing_stats(frame, "value", "10s")
             .context("Standard timing stats")?,
     })
 }
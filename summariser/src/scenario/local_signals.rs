@@ -49,10 +49,8 @@ pub(crate) async fn summarize_local_signals(
     .context("Load success ratio")?;
 
     Ok(LocalSignalsSummary {
-        send: standard_timing_stats(send_frame, "value", "10s", None)
-            .context("Send timing stats")?,
-        recv: standard_timing_stats(recv_frame, "value", "10s", None)
-            .context("Recv timing stats")?,
+        send: standard_timing_stats(send_frame, "value", "10s").context("Send timing stats")?,
+        recv: standard_timing_stats(recv_frame, "value", "10s").context("Recv timing stats")?,
         success_ratio: ratio_stats(success_ratio, "value").context("Success ratio stats")?,
     })
 }
@@ -66,7 +66,6 @@ pub(crate) async fn summarize_remote_signals(
             remote_signal_round_trip_frame,
             "value",
             "10s",
-            None,
         )
         .context("Send timing stats")?,
         remote_signal_timeout,
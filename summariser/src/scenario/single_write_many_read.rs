@@ -44,7 +44,7 @@ pub(crate) async fn summarize_single_write_many_read(
         };
 
     Ok(SingleWriteManyReadSummary {
-        read_call: standard_timing_stats(zome_calls.clone(), "value", "10s", None)?,
+        read_call: standard_timing_stats(zome_calls.clone(), "value", "10s")?,
         read_rate: standard_rate(zome_calls, "value", "10s")?,
         error_count,
     })
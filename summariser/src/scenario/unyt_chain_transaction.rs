@@ -428,7 +428,6 @@ pub(crate) async fn summarize_unyt_chain_transaction(
             check_agent_exists_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for check_agent_exists_duration_s")?,
         check_agent_exists_total_calls: aggregated_single_value(
@@ -450,7 +449,6 @@ pub(crate) async fn summarize_unyt_chain_transaction(
             ui_action_list_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_action_list_refresh_duration_s")?,
         ui_action_list_refresh_failed_calls: aggregated_single_value(
@@ -462,7 +460,6 @@ pub(crate) async fn summarize_unyt_chain_transaction(
             ui_routine_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_routine_refresh_duration_s")?,
         ui_routine_refresh_watchlist_count: aggregated_single_value(
@@ -474,7 +471,6 @@ pub(crate) async fn summarize_unyt_chain_transaction(
             ui_transaction_detail_item_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_transaction_detail_item_refresh_duration_s")?,
         ui_transaction_detail_item_refresh_primary_transaction_total_calls:
@@ -513,7 +509,6 @@ pub(crate) async fn summarize_unyt_chain_transaction(
             ui_transaction_detail_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_transaction_detail_refresh_duration_s")?,
         ui_transaction_detail_refresh_transactions_processed: aggregated_single_value(
@@ -487,7 +487,6 @@ pub(crate) async fn summarize_unyt_chain_transaction_zero_arc(
             check_agent_exists_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for check_agent_exists_duration_s")?,
         check_agent_exists_total_calls: aggregated_single_value(
@@ -509,7 +508,6 @@ pub(crate) async fn summarize_unyt_chain_transaction_zero_arc(
             ui_action_list_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_action_list_refresh_duration_s")?,
         ui_action_list_refresh_failed_calls: aggregated_single_value(
@@ -521,7 +519,6 @@ pub(crate) async fn summarize_unyt_chain_transaction_zero_arc(
             ui_routine_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_routine_refresh_duration_s")?,
         ui_routine_refresh_watchlist_count: aggregated_single_value(
@@ -533,7 +530,6 @@ pub(crate) async fn summarize_unyt_chain_transaction_zero_arc(
             ui_transaction_detail_item_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_transaction_detail_item_refresh_duration_s")?,
         ui_transaction_detail_item_refresh_primary_transaction_total_calls:
@@ -572,7 +568,6 @@ pub(crate) async fn summarize_unyt_chain_transaction_zero_arc(
             ui_transaction_detail_refresh_duration_s,
             "value",
             "10s",
-            None,
         )
         .context("Timing stats for ui_transaction_detail_refresh_duration_s")?,
         ui_transaction_detail_refresh_transactions_processed: aggregated_single_value(
@@ -268,13 +268,8 @@ pub(crate) async fn summarize_unyt_proposal(
         };
 
     Ok(UnytProposalSummary {
-        global_definition_propagation_time: standard_timing_stats(
-            propagation_time,
-            "value",
-            "10s",
-            None,
-        )
-        .context("Stats for global_definition_propagation_time")?,
+        global_definition_propagation_time: standard_timing_stats(propagation_time, "value", "10s")
+            .context("Stats for global_definition_propagation_time")?,
         proposal_round_trip_accepted: round_trip_accepted
             .filter(|f| f.height() > 0)
             .map(|f| partitioned_timing_stats(f, "value", "10s", &["agent"]))
@@ -285,7 +280,7 @@ pub(crate) async fn summarize_unyt_proposal(
             .map(|f| partitioned_timing_stats(f, "value", "10s", &["agent"]))
             .transpose()
             .context("Round-trip timing (rejected)")?,
-        negotiation_rounds: standard_timing_stats(negotiation_data, "value", "10s", None)
+        negotiation_rounds: standard_timing_stats(negotiation_data, "value", "10s")
             .context("Stats for negotiation_rounds")?,
         sync_lag_proposal,
         sync_lag_commitment,
@@ -43,10 +43,10 @@ pub(crate) async fn summarize_write_query(
         .collect()?;
 
     Ok(WriteQuerySummary {
-        write_timing: standard_timing_stats(create_zome_calls.clone(), "value", "10s", None)
+        write_timing: standard_timing_stats(create_zome_calls.clone(), "value", "10s")
             .context("Write timing stats")?,
         write_rate: standard_rate(create_zome_calls, "value", "10s").context("Write rate")?,
-        query_timing: standard_timing_stats(query_zome_calls.clone(), "value", "10s", None)
+        query_timing: standard_timing_stats(query_zome_calls.clone(), "value", "10s")
             .context("Query timing stats")?,
         query_rate: standard_rate(query_zome_calls, "value", "10s").context("Query rate")?,
         errors: zome_call_error_count(client, &summary)
@@ -49,10 +49,10 @@ pub(crate) async fn summarize_write_validated(
         .collect()?;
 
     Ok(WriteValidatedSummary {
-        write_timing: standard_timing_stats(create_zome_calls.clone(), "value", "10s", None)
+        write_timing: standard_timing_stats(create_zome_calls.clone(), "value", "10s")
             .context("Create timing stats")?,
         write_rate: standard_rate(create_zome_calls, "value", "10s").context("Create rate")?,
-        read_timing: standard_timing_stats(update_zome_calls.clone(), "value", "10s", None)
+        read_timing: standard_timing_stats(update_zome_calls.clone(), "value", "10s")
             .context("Update timing stats")?,
         read_rate: standard_rate(update_zome_calls, "value", "10s").context("Update rate")?,
         errors: zome_call_error_count(client.clone(), &summary)
@@ -33,7 +33,7 @@ pub(crate) async fn summarize_zome_call_single_value(
         .collect()?;
 
     Ok(ZomeCallSingleValueSummary {
-        call_timing: standard_timing_stats(zome_calls.clone(), "value", "10s", None)
+        call_timing: standard_timing_stats(zome_calls.clone(), "value", "10s")
             .context("Call timing stats")?,
         call_rate: standard_rate(zome_calls, "value", "10s").context("Call rate")?,
         errors: zome_call_error_count(client.clone(), &summary)